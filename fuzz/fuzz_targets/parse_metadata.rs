@@ -0,0 +1,38 @@
+//! Every public entry point must return (Ok or Err) on arbitrary bytes, never
+//! panic. The first input byte picks a format signature to prepend, so each
+//! analyzer's `parse_metadata` is reached without the fuzzer having to
+//! discover its magic bytes first.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use upload_analyzer::AnalysisOptions;
+
+const SIGNATURES: [&[u8]; 13] = [
+    b"",
+    b"MZ",
+    &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1],
+    b"!<arch>\n",
+    &[0xED, 0xAB, 0xEE, 0xDB],
+    b"070701",
+    b"070707",
+    &[0x7F, b'E', b'L', b'F'],
+    &[0xCF, 0xFA, 0xED, 0xFE],
+    b"\0asm\x01\0\0\0",
+    &[0x4C, 0, 0, 0, 0x01, 0x14, 0x02, 0],
+    &[0xCA, 0xFE, 0xBA, 0xBE],
+    &[0x78, 0x9C],
+];
+
+fuzz_target!(|input: &[u8]| {
+    let Some((&selector, rest)) = input.split_first() else {
+        return;
+    };
+    let data = [SIGNATURES[selector as usize % SIGNATURES.len()], rest].concat();
+
+    let _ = upload_analyzer::parse_metadata(&data);
+    let _ = upload_analyzer::parse_header_metadata(&data);
+    let _ = upload_analyzer::get_file_info(&data);
+    let options = AnalysisOptions::default().with_deep_scan(true).with_max_embedded_depth(2);
+    let _ = upload_analyzer::analyze_with_options(&data, &options);
+});
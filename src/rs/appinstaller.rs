@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use crate::xml::{xml_attribute, xml_element};
+use crate::{FileAnalyzer, FormatInfo, MetadataResult};
+
+/// Namespace prefix shared by every App Installer schema revision
+/// (`.../appinstaller/2017/2`, `/2018`, `/2021`).
+const APPINSTALLER_NAMESPACE: &str = "http://schemas.microsoft.com/appx/appinstaller/";
+
+/// How far into the file the root element must start.
+const SNIFF_WINDOW: usize = 1024;
+
+/// The package an App Installer file installs, in order of preference.
+const MAIN_PACKAGE_ELEMENTS: [&str; 2] = ["MainBundle", "MainPackage"];
+
+/// Elements whose packages the main package depends on.
+const DEPENDENCY_ELEMENTS: [&str; 2] = ["Package", "Bundle"];
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "AppInstaller",
+    mime_type: "application/appinstaller",
+    extensions: &["appinstaller"],
+};
+
+/// `.appinstaller` XML files, which point Windows at an MSIX package or
+/// bundle to install and keep updated.
+pub struct AppInstallerAnalyzer;
+
+impl FileAnalyzer for AppInstallerAnalyzer {
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        is_appinstaller_file(data)
+    }
+
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
+        let xml = std::str::from_utf8(data).map_err(|e| format!("App Installer file is not UTF-8: {}", e))?;
+        parse_appinstaller_metadata(xml)
+    }
+}
+
+pub fn is_appinstaller_file(data: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(&data[..data.len().min(SNIFF_WINDOW)]);
+    head.contains("<AppInstaller") && head.contains(APPINSTALLER_NAMESPACE)
+}
+
+fn parse_appinstaller_metadata(xml: &str) -> MetadataResult {
+    let root = xml_element(xml, "AppInstaller").ok_or("App Installer file has no AppInstaller element")?;
+
+    let mut meta = HashMap::new();
+    meta.insert("Format".into(), "AppInstaller".into());
+    if let Some(uri) = xml_attribute(root, "Uri") {
+        meta.insert("AppInstallerUri".into(), uri.to_string());
+    }
+    if let Some(version) = xml_attribute(root, "Version") {
+        meta.insert("AppInstallerVersion".into(), version.to_string());
+    }
+
+    let main = MAIN_PACKAGE_ELEMENTS
+        .iter()
+        .find_map(|element| xml_element(xml, element).map(|tag| (*element, tag)));
+    if let Some((element, tag)) = main {
+        meta.insert("PackageType".into(), if element == "MainBundle" { "Bundle" } else { "Package" }.into());
+        for (attribute, key) in [
+            ("Name", "ProductName"),
+            ("Version", "ProductVersion"),
+            ("Publisher", "Publisher"),
+            ("Uri", "PackageUri"),
+            ("ProcessorArchitecture", "Architecture"),
+        ] {
+            if let Some(value) = xml_attribute(tag, attribute) {
+                meta.insert(key.into(), value.to_string());
+            }
+        }
+    }
+
+    let dependencies = dependency_names(xml);
+    if !dependencies.is_empty() {
+        meta.insert("Dependencies".into(), dependencies.join(", "));
+    }
+    if let Some(hours) = xml_element(xml, "OnLaunch").and_then(|tag| xml_attribute(tag, "HoursBetweenUpdateChecks")) {
+        meta.insert("HoursBetweenUpdateChecks".into(), hours.to_string());
+    }
+    meta.insert("AutomaticBackgroundTask".into(), xml_element(xml, "AutomaticBackgroundTask").is_some().to_string());
+
+    Ok(meta)
+}
+
+/// `Name`s of the packages listed under `<Dependencies>`.
+fn dependency_names(xml: &str) -> Vec<String> {
+    let Some(section) = xml
+        .find("<Dependencies>")
+        .and_then(|start| xml[start..].find("</Dependencies>").map(|end| &xml[start..start + end]))
+    else {
+        return Vec::new();
+    };
+    section
+        .split('<')
+        .filter(|tag| DEPENDENCY_ELEMENTS.iter().any(|element| tag.starts_with(&format!("{} ", element))))
+        .filter_map(|tag| xml_attribute(tag, "Name"))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_appinstaller_metadata() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<AppInstaller xmlns="http://schemas.microsoft.com/appx/appinstaller/2018" Version="1.0.0.0" Uri="https://contoso.com/app.appinstaller">
+  <MainBundle Name="Contoso.App" Publisher="CN=Contoso, O=Contoso, C=US" Version="2.23.12.43" Uri="https://contoso.com/app.msixbundle" />
+  <Dependencies>
+    <Package Name="Microsoft.VCLibs.140.00" Publisher="CN=Microsoft Corporation" ProcessorArchitecture="x64" Uri="https://contoso.com/vclibs.appx" Version="14.0.24217.0" />
+  </Dependencies>
+  <UpdateSettings><OnLaunch HoursBetweenUpdateChecks="12" /></UpdateSettings>
+</AppInstaller>"#;
+        assert!(is_appinstaller_file(xml.as_bytes()));
+        let meta = AppInstallerAnalyzer.parse_metadata(xml.as_bytes()).unwrap_or_default();
+        let get = |key: &str| meta.get(key).map(String::as_str);
+
+        assert_eq!(get("AppInstallerUri"), Some("https://contoso.com/app.appinstaller"));
+        assert_eq!(get("PackageType"), Some("Bundle"));
+        assert_eq!(get("ProductName"), Some("Contoso.App"));
+        assert_eq!(get("ProductVersion"), Some("2.23.12.43"));
+        assert_eq!(get("Publisher"), Some("CN=Contoso, O=Contoso, C=US"));
+        assert_eq!(get("PackageUri"), Some("https://contoso.com/app.msixbundle"));
+        assert_eq!(get("Dependencies"), Some("Microsoft.VCLibs.140.00"));
+        assert_eq!(get("HoursBetweenUpdateChecks"), Some("12"));
+        assert_eq!(get("AutomaticBackgroundTask"), Some("false"));
+    }
+}
@@ -0,0 +1,358 @@
+//! Just enough DER to classify an Authenticode PKCS#7 `SignedData` by its
+//! signer certificate and read its timestamp. Nothing here verifies a
+//! signature or a chain.
+
+/// Issuer-name fragments of the public CAs that issue code-signing chains.
+/// Anyone can put them in a certificate they mint, so a match only says the
+/// name looks like one of these CAs.
+const KNOWN_CAS: [&str; 14] = [
+    "DigiCert",
+    "Sectigo",
+    "COMODO",
+    "USERTrust",
+    "GlobalSign",
+    "Entrust",
+    "VeriSign",
+    "Symantec",
+    "thawte",
+    "GoDaddy",
+    "Go Daddy",
+    "SSL.com",
+    "Certum",
+    "Microsoft",
+];
+
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const OID: u8 = 0x06;
+const UTC_TIME: u8 = 0x17;
+const GENERALIZED_TIME: u8 = 0x18;
+const CONTEXT_0: u8 = 0xA0;
+const CONTEXT_1: u8 = 0xA1;
+
+/// PKCS#9 `signingTime` (1.2.840.113549.1.9.5)
+const OID_SIGNING_TIME: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x05];
+/// PKCS#9 `countersignature` (1.2.840.113549.1.9.6), the legacy Authenticode timestamp
+const OID_COUNTERSIGNATURE: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x06];
+/// `szOID_RFC3161_counterSign` (1.3.6.1.4.1.311.3.3.1)
+const OID_RFC3161_COUNTERSIGNATURE: &[u8] = &[0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x03, 0x03, 0x01];
+
+/// A DER element's tag and contents.
+type Tlv<'a> = (u8, &'a [u8]);
+
+/// The DER elements packed in `data`, stopping at the first malformed one.
+fn elements(mut data: &[u8]) -> impl Iterator<Item = Tlv<'_>> {
+    std::iter::from_fn(move || {
+        let (&tag, rest) = data.split_first()?;
+        let (&first, rest) = rest.split_first()?;
+        let (len, rest) = if first < 0x80 {
+            (first as usize, rest)
+        } else {
+            let width = (first & 0x7F) as usize;
+            if width == 0 || width > 4 || rest.len() < width {
+                return None;
+            }
+            let len = rest[..width].iter().fold(0usize, |len, &byte| (len << 8) | byte as usize);
+            (len, &rest[width..])
+        };
+        let contents = rest.get(..len)?;
+        data = &rest[len..];
+        Some((tag, contents))
+    })
+}
+
+/// The DER-encoded issuer and subject `Name`s and the serial number of one
+/// X.509 certificate.
+struct Certificate<'a> {
+    serial: &'a [u8],
+    issuer: &'a [u8],
+    subject: &'a [u8],
+}
+
+impl<'a> Certificate<'a> {
+    fn parse(certificate: &'a [u8]) -> Option<Self> {
+        let (_, tbs) = elements(certificate).next()?;
+        let mut fields = elements(tbs).skip_while(|&(tag, _)| tag == CONTEXT_0);
+        let (_, serial) = fields.next().filter(|&(tag, _)| tag == INTEGER)?;
+        let _signature_algorithm = fields.next()?;
+        let (_, issuer) = fields.next()?;
+        let _validity = fields.next()?;
+        let (_, subject) = fields.next()?;
+        Some(Certificate { serial, issuer, subject })
+    }
+
+    fn is_self_signed(&self) -> bool {
+        self.issuer == self.subject
+    }
+}
+
+/// `SignedData.certificates`, the first `SignerInfo` and the issuer and
+/// serial number it names its certificate by.
+struct SignedData<'a> {
+    certificates: Vec<Certificate<'a>>,
+    signer_info: &'a [u8],
+    signer_issuer: &'a [u8],
+    signer_serial: &'a [u8],
+}
+
+/// The contents of the `SignedData` in a PKCS#7 `ContentInfo`'s contents.
+fn signed_data_contents(content_info: &[u8]) -> Option<&[u8]> {
+    let (_, explicit) = elements(content_info).nth(1).filter(|&(tag, _)| tag == CONTEXT_0)?;
+    let (_, signed_data) = elements(explicit).next()?;
+    Some(signed_data)
+}
+
+fn signed_data(pkcs7: &[u8]) -> Option<SignedData<'_>> {
+    let (_, content_info) = elements(pkcs7).next().filter(|&(tag, _)| tag == SEQUENCE)?;
+    let signed_data = signed_data_contents(content_info)?;
+
+    let mut certificates = Vec::new();
+    let mut signer_infos = None;
+    for (tag, contents) in elements(signed_data).skip(3) {
+        match tag {
+            CONTEXT_0 => certificates.extend(elements(contents).filter_map(|(_, cert)| Certificate::parse(cert))),
+            SET => signer_infos = Some(contents),
+            _ => {}
+        }
+    }
+    let (_, signer_info) = elements(signer_infos?).next()?;
+    let (_, issuer_and_serial) = elements(signer_info).nth(1).filter(|&(tag, _)| tag == SEQUENCE)?;
+    let mut sid = elements(issuer_and_serial);
+    let (_, signer_issuer) = sid.next()?;
+    let (_, signer_serial) = sid.next()?;
+    Some(SignedData { certificates, signer_info, signer_issuer, signer_serial })
+}
+
+/// The (first) value of the attribute with `oid` in a `SET OF Attribute`.
+fn attribute<'a>(attributes: &'a [u8], oid: &[u8]) -> Option<Tlv<'a>> {
+    elements(attributes).find_map(|(_, attribute)| {
+        let mut parts = elements(attribute);
+        parts.next().filter(|&(tag, value)| tag == OID && value == oid)?;
+        let (_, values) = parts.next()?;
+        elements(values).next()
+    })
+}
+
+/// An element of a `SignerInfo` by its context tag: `[0]` authenticated and
+/// `[1]` unauthenticated attributes.
+fn signer_attributes(signer_info: &[u8], tag: u8) -> Option<&[u8]> {
+    elements(signer_info).find(|&(element, _)| element == tag).map(|(_, attributes)| attributes)
+}
+
+/// A `UTCTime` or `GeneralizedTime` as `YYYY-MM-DDTHH:MM:SSZ`; fractional
+/// seconds are dropped.
+fn der_time(tag: u8, value: &[u8]) -> Option<String> {
+    let text = std::str::from_utf8(value).ok()?;
+    let digits = match tag {
+        UTC_TIME => {
+            let year: u32 = text.get(..2)?.parse().ok()?;
+            format!("{}{}", if year < 50 { "20" } else { "19" }, text.get(..12)?)
+        }
+        GENERALIZED_TIME => text.get(..14)?.to_string(),
+        _ => return None,
+    };
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        &digits[0..4], &digits[4..6], &digits[6..8], &digits[8..10], &digits[10..12], &digits[12..14]
+    ))
+}
+
+/// `genTime` from the `TSTInfo` an RFC 3161 timestamp token (`ContentInfo`
+/// contents) encapsulates.
+fn rfc3161_time(token: &[u8]) -> Option<String> {
+    let (_, encapsulated) = elements(signed_data_contents(token)?).nth(2)?;
+    let (_, explicit) = elements(encapsulated).nth(1).filter(|&(tag, _)| tag == CONTEXT_0)?;
+    let (_, tst_info) = elements(explicit).next().filter(|&(tag, _)| tag == OCTET_STRING)?;
+    let (_, tst_info) = elements(tst_info).next()?;
+    let (tag, gen_time) = elements(tst_info).nth(4)?;
+    der_time(tag, gen_time)
+}
+
+/// `SigningTime` for an Authenticode PKCS#7 blob: the time its timestamp
+/// countersignature vouches for, from either an RFC 3161 token or a legacy
+/// PKCS#9 countersignature's `signingTime`. `None` when the signature is not
+/// timestamped.
+pub fn signing_time(pkcs7: &[u8]) -> Option<String> {
+    let unauthenticated = signer_attributes(signed_data(pkcs7)?.signer_info, CONTEXT_1)?;
+    if let Some((_, token)) = attribute(unauthenticated, OID_RFC3161_COUNTERSIGNATURE) {
+        return rfc3161_time(token);
+    }
+    let (_, countersignature) = attribute(unauthenticated, OID_COUNTERSIGNATURE)?;
+    let (tag, time) = attribute(signer_attributes(countersignature, CONTEXT_0)?, OID_SIGNING_TIME)?;
+    der_time(tag, time)
+}
+
+fn resembles_known_ca(name: &[u8]) -> bool {
+    KNOWN_CAS.iter().any(|ca| {
+        name.windows(ca.len()).any(|window| window.eq_ignore_ascii_case(ca.as_bytes()))
+    })
+}
+
+/// `CertificateTrust` for an Authenticode PKCS#7 blob: `SelfSigned` when the
+/// signer certificate's issuer is its own subject,
+/// `IssuerNameResemblesKnownCA` when it or a certificate up its embedded chain
+/// names an issuer that looks like a well-known public CA, otherwise
+/// `Unknown`. No signature is checked, so none of these means trusted.
+pub fn certificate_trust(pkcs7: &[u8]) -> &'static str {
+    let Some(SignedData { certificates, signer_issuer, signer_serial, .. }) = signed_data(pkcs7) else {
+        return "Unknown";
+    };
+    let signer = certificates.iter().find(|cert| cert.issuer == signer_issuer && cert.serial == signer_serial);
+    let Some(mut current) = signer else {
+        return "Unknown";
+    };
+    if current.is_self_signed() {
+        return "SelfSigned";
+    }
+    // Bounded by the chain length so a loop of cross-issued certificates ends
+    for _ in 0..certificates.len() {
+        if resembles_known_ca(current.issuer) {
+            return "IssuerNameResemblesKnownCA";
+        }
+        match certificates.iter().find(|cert| cert.subject == current.issuer && !cert.is_self_signed()) {
+            Some(parent) => current = parent,
+            None => break,
+        }
+    }
+    "Unknown"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn der(tag: u8, contents: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        if contents.len() < 0x80 {
+            out.push(contents.len() as u8);
+        } else {
+            out.push(0x82);
+            out.extend_from_slice(&(contents.len() as u16).to_be_bytes());
+        }
+        out.extend_from_slice(contents);
+        out
+    }
+
+    fn name(common_name: &str) -> Vec<u8> {
+        der(SEQUENCE, &der(SET, &der(SEQUENCE, &der(0x0C, common_name.as_bytes()))))
+    }
+
+    fn certificate(serial: u8, issuer: &str, subject: &str) -> Vec<u8> {
+        let tbs = [
+            der(CONTEXT_0, &der(INTEGER, &[2])),
+            der(INTEGER, &[serial]),
+            der(SEQUENCE, &[]),
+            name(issuer),
+            der(SEQUENCE, &[]),
+            name(subject),
+        ]
+        .concat();
+        der(SEQUENCE, &der(SEQUENCE, &tbs))
+    }
+
+    /// A `SignerInfo` naming the certificate with serial 1, with `attributes`
+    /// (already tagged `[0]` or `[1]`) after its `sid`.
+    fn signer_info(signer_issuer: &str, attributes: &[u8]) -> Vec<u8> {
+        let sid = der(SEQUENCE, &[name(signer_issuer), der(INTEGER, &[1])].concat());
+        der(SEQUENCE, &[der(INTEGER, &[1]), sid, attributes.to_vec()].concat())
+    }
+
+    fn content_info(encapsulated: &[u8], certificates: &[Vec<u8>], signer_info: &[u8]) -> Vec<u8> {
+        let signed_data = der(
+            SEQUENCE,
+            &[
+                der(INTEGER, &[1]),
+                der(SET, &[]),
+                der(SEQUENCE, encapsulated),
+                der(CONTEXT_0, &certificates.concat()),
+                der(SET, signer_info),
+            ]
+            .concat(),
+        );
+        let oid = der(OID, &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02]);
+        der(SEQUENCE, &[oid, der(CONTEXT_0, &signed_data)].concat())
+    }
+
+    /// A `SignedData` whose signer is the certificate with serial 1.
+    fn pkcs7(signer_issuer: &str, certificates: &[Vec<u8>]) -> Vec<u8> {
+        content_info(&[], certificates, &signer_info(signer_issuer, &[]))
+    }
+
+    fn attribute(oid: &[u8], value: &[u8]) -> Vec<u8> {
+        der(SEQUENCE, &[der(OID, oid), der(SET, value)].concat())
+    }
+
+    #[test]
+    fn test_certificate_trust() {
+        let self_signed = pkcs7("Acme", &[certificate(1, "Acme", "Acme")]);
+        assert_eq!(certificate_trust(&self_signed), "SelfSigned");
+
+        let chained = pkcs7(
+            "Acme Issuing CA",
+            &[
+                certificate(1, "Acme Issuing CA", "Acme"),
+                certificate(7, "DigiCert Trusted Root G4", "Acme Issuing CA"),
+            ],
+        );
+        assert_eq!(certificate_trust(&chained), "IssuerNameResemblesKnownCA");
+
+        // A root anyone can mint under a public CA's name is never reported
+        // as a trusted chain
+        let spoofed = pkcs7(
+            "DigiCert Evil Root",
+            &[
+                certificate(1, "DigiCert Evil Root", "Acme"),
+                certificate(9, "DigiCert Evil Root", "DigiCert Evil Root"),
+            ],
+        );
+        assert_eq!(certificate_trust(&spoofed), "IssuerNameResemblesKnownCA");
+        assert_ne!(certificate_trust(&spoofed), "ChainedToKnownCA");
+
+        let private = pkcs7("Acme Issuing CA", &[certificate(1, "Acme Issuing CA", "Acme")]);
+        assert_eq!(certificate_trust(&private), "Unknown");
+        assert_eq!(certificate_trust(&self_signed[..20]), "Unknown");
+    }
+
+    #[test]
+    fn test_signing_time() {
+        let certificates = [certificate(1, "Acme", "Acme")];
+        assert_eq!(signing_time(&pkcs7("Acme", &certificates)), None);
+
+        let countersignature = signer_info(
+            "Timestamp CA",
+            &der(CONTEXT_0, &attribute(OID_SIGNING_TIME, &der(UTC_TIME, b"230501123456Z"))),
+        );
+        let legacy = content_info(
+            &[],
+            &certificates,
+            &signer_info("Acme", &der(CONTEXT_1, &attribute(OID_COUNTERSIGNATURE, &countersignature))),
+        );
+        assert_eq!(signing_time(&legacy).as_deref(), Some("2023-05-01T12:34:56Z"));
+
+        let tst_info = [
+            der(INTEGER, &[1]),
+            der(OID, &[0x2A, 0x03]),
+            der(SEQUENCE, &[]),
+            der(INTEGER, &[9]),
+            der(GENERALIZED_TIME, b"20240102030405.5Z"),
+        ]
+        .concat();
+        let encapsulated = [
+            der(OID, &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x10, 0x01, 0x04]),
+            der(CONTEXT_0, &der(OCTET_STRING, &der(SEQUENCE, &tst_info))),
+        ]
+        .concat();
+        let token = content_info(&encapsulated, &[], &signer_info("Timestamp CA", &[]));
+        let rfc3161 = content_info(
+            &[],
+            &certificates,
+            &signer_info("Acme", &der(CONTEXT_1, &attribute(OID_RFC3161_COUNTERSIGNATURE, &token))),
+        );
+        assert_eq!(signing_time(&rfc3161).as_deref(), Some("2024-01-02T03:04:05Z"));
+    }
+}
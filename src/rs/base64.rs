@@ -0,0 +1,90 @@
+//! Standard base64, for files passed into the WASM batch API and raw
+//! documents passed back out in metadata.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `data` as padded standard base64.
+pub fn encode_base64(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+        let group = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(group >> (18 - 6 * i)) as usize & 0x3F] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(feature = "wasm")]
+fn base64_value(c: u8) -> Option<u32> {
+    match c {
+        b'A'..=b'Z' => Some((c - b'A') as u32),
+        b'a'..=b'z' => Some((c - b'a' + 26) as u32),
+        b'0'..=b'9' => Some((c - b'0' + 52) as u32),
+        b'+' | b'-' => Some(62),
+        b'/' | b'_' => Some(63),
+        _ => None,
+    }
+}
+
+/// Decodes standard or URL-safe base64, with or without padding. Whitespace is ignored;
+/// anything but more padding after the first `=` is an error.
+#[cfg(feature = "wasm")]
+pub fn decode_base64(input: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(input.len() / 4 * 3);
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    let mut padded = false;
+
+    for &c in input.as_bytes() {
+        if c.is_ascii_whitespace() {
+            continue;
+        }
+        if c == b'=' {
+            padded = true;
+            continue;
+        }
+        if padded {
+            return Err("Unexpected base64 data after padding".to_string());
+        }
+        let value = base64_value(c).ok_or_else(|| format!("Invalid base64 character: {:?}", c as char))?;
+        acc = (acc << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_base64() {
+        assert_eq!(encode_base64(b"Man"), "TWFu");
+        assert_eq!(encode_base64(b"Ma"), "TWE=");
+        assert_eq!(encode_base64(b"M"), "TQ==");
+        assert_eq!(encode_base64(b""), "");
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn test_decode_base64() {
+        assert_eq!(decode_base64("TWFu").ok(), Some(b"Man".to_vec()));
+        assert_eq!(decode_base64("TWE=").ok(), Some(b"Ma".to_vec()));
+        assert_eq!(decode_base64("TQ").ok(), Some(b"M".to_vec()));
+        assert_eq!(decode_base64("TQ==\n").ok(), Some(b"M".to_vec()));
+        assert!(decode_base64("T!Q=").is_err());
+        assert!(decode_base64("QQ==QUJD").is_err());
+    }
+}
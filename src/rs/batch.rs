@@ -0,0 +1,45 @@
+use serde_json::{json, Value};
+
+use crate::base64::decode_base64;
+use crate::parse_metadata;
+
+/// Analyzes a JSON array of base64-encoded files and returns a JSON array with one
+/// `{index, metadata}` or `{index, error}` entry per input, in input order.
+pub fn analyze_batch_json(files_json: &str) -> String {
+    let files: Vec<String> = match serde_json::from_str(files_json) {
+        Ok(files) => files,
+        Err(e) => return json!({ "error": format!("Invalid batch input: {}", e) }).to_string(),
+    };
+
+    let results: Vec<Value> = files
+        .iter()
+        .enumerate()
+        .map(|(index, encoded)| match decode_base64(encoded) {
+            Ok(data) => match parse_metadata(&data) {
+                Ok(meta) => json!({ "index": index, "metadata": meta }),
+                Err(e) => json!({ "index": index, "error": { "message": e } }),
+            },
+            Err(e) => json!({ "index": index, "error": { "message": e } }),
+        })
+        .collect();
+
+    Value::Array(results).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_batch_reports_index_per_entry() {
+        let out: Value = serde_json::from_str(&analyze_batch_json(r#"["AAAA", "***"]"#))
+            .unwrap_or(Value::Null);
+        let entries = out.as_array().cloned().unwrap_or_default();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["index"], 0);
+        assert!(entries[0]["error"]["message"].is_string());
+        assert_eq!(entries[1]["index"], 1);
+        assert!(entries[1]["error"]["message"].is_string());
+    }
+}
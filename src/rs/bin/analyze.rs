@@ -0,0 +1,135 @@
+//! Command-line wrapper over the native API: analyzes a file (or standard
+//! input) and prints its fields as pretty, key-sorted JSON.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::process::ExitCode;
+
+use upload_analyzer::{analyze_path, analyze_with_options, get_file_info, AnalysisOptions, Hasher, MetadataResult};
+
+/// Bytes read at a time when hashing a file for `--hashes`.
+const HASH_CHUNK_SIZE: usize = 1 << 20;
+
+const USAGE: &str = "\
+Usage: analyze [--hashes] [--deep] [--format-only] [FILE]
+
+Analyzes FILE, or standard input when FILE is omitted or `-`, and prints
+its metadata as JSON.
+
+Options:
+  --hashes       add Sha256, the file's SHA-256
+  --deep         deep scan: embedded URLs, RawManifest, FileList, ...
+  --format-only  only detect the format (Format, MimeType, Extension, ...)
+  -h, --help     print this help";
+
+#[derive(Default)]
+struct Args {
+    hashes: bool,
+    deep: bool,
+    format_only: bool,
+    path: Option<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Option<Args>, String> {
+    let mut parsed = Args::default();
+    for arg in args {
+        match arg.as_str() {
+            "--hashes" => parsed.hashes = true,
+            "--deep" => parsed.deep = true,
+            "--format-only" => parsed.format_only = true,
+            "-h" | "--help" => return Ok(None),
+            "-" => parsed.path = None,
+            flag if flag.starts_with('-') => return Err(format!("unknown option {}", flag)),
+            path if parsed.path.is_some() => return Err(format!("unexpected argument {}", path)),
+            path => parsed.path = Some(path.to_string()),
+        }
+    }
+    Ok(Some(parsed))
+}
+
+fn read_input(path: Option<&str>) -> Result<Vec<u8>, String> {
+    match path {
+        Some(path) => std::fs::read(path).map_err(|e| format!("{}: {}", path, e)),
+        None => {
+            let mut data = Vec::new();
+            std::io::stdin().read_to_end(&mut data).map_err(|e| format!("stdin: {}", e))?;
+            Ok(data)
+        }
+    }
+}
+
+/// SHA-256 of the file at `path`, hashed a chunk at a time.
+fn file_sha256(path: &str) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut hasher = Hasher::new();
+    let mut chunk = vec![0u8; HASH_CHUNK_SIZE];
+    loop {
+        match file.read(&mut chunk) {
+            Ok(0) => return Ok(hasher.finalize()),
+            Ok(n) => hasher.update(&chunk[..n]),
+            Err(e) => return Err(format!("{}: {}", path, e)),
+        }
+    }
+}
+
+fn run(args: &Args) -> MetadataResult {
+    let mut options = AnalysisOptions::default().with_deep_scan(args.deep);
+
+    // Files are analyzed through `analyze_path`, which only loads what the
+    // format needs; standard input cannot seek and is read whole
+    if let (Some(path), false) = (args.path.as_deref(), args.format_only) {
+        if args.hashes {
+            options = options.with_sha256(&file_sha256(path)?);
+        }
+        return analyze_path(Path::new(path), &options).map(|result| result.fields).map_err(|e| format!("{}: {}", path, e));
+    }
+
+    let data = read_input(args.path.as_deref())?;
+    let sha256 = args.hashes.then(|| {
+        let mut hasher = Hasher::new();
+        hasher.update(&data);
+        hasher.finalize()
+    });
+
+    if args.format_only {
+        let mut info = get_file_info(&data)?;
+        if let Some(sha256) = sha256 {
+            info.insert("Sha256".to_string(), sha256);
+        }
+        return Ok(info);
+    }
+
+    if let Some(sha256) = sha256 {
+        options = options.with_sha256(&sha256);
+    }
+    analyze_with_options(&data, &options).map(|result| result.fields).map_err(|e| e.to_string())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args = match parse_args(&args) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("analyze: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(&args) {
+        Ok(fields) => {
+            let sorted: BTreeMap<String, String> = fields.into_iter().collect();
+            println!("{}", serde_json::to_string_pretty(&sorted).unwrap_or_default());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("analyze: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Cooperative work budget for one analysis. WASM has no way to cancel a
+//! runaway call from outside, so the byte scans that crafted inputs can make
+//! expensive charge their work here and give up once the budget is spent.
+
+use std::cell::Cell;
+
+/// Operations (roughly, bytes scanned) per input byte one analysis may spend
+/// by default. Legitimate files cost a few dozen full passes at most; the
+/// inputs this guards against cost work quadratic in their size.
+const WORK_PER_BYTE: u64 = 128;
+/// Floor for small inputs, where per-call overhead dominates.
+const MIN_WORK_BUDGET: u64 = 256 * 1024 * 1024;
+
+/// Default budget for analyzing `len` bytes.
+pub fn default_budget(len: usize) -> u64 {
+    (len as u64).saturating_mul(WORK_PER_BYTE).max(MIN_WORK_BUDGET)
+}
+
+thread_local! {
+    static REMAINING: Cell<Option<u64>> = const { Cell::new(None) };
+    static EXHAUSTED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The budget of one analysis, spent through `charge` until `finish` checks
+/// it. A meter started while another is running joins the outer budget and
+/// never fails itself. Dropping it, even while unwinding, ends the budget.
+pub struct Meter {
+    limit: u64,
+    outer: bool,
+}
+
+impl Meter {
+    /// Starts metering with `limit` operations to spend.
+    pub fn start(limit: u64) -> Self {
+        let outer = REMAINING.get().is_none();
+        if outer {
+            REMAINING.set(Some(limit));
+            EXHAUSTED.set(false);
+        }
+        Meter { limit, outer }
+    }
+
+    /// Ends the budget: `Err` with a `ResourceLimitExceeded` message when the
+    /// work it metered ran out.
+    pub fn finish(self) -> Result<(), String> {
+        if self.outer && EXHAUSTED.get() {
+            return Err(format!(
+                "ResourceLimitExceeded: analysis stopped after {} operations",
+                self.limit
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Meter {
+    fn drop(&mut self) {
+        if self.outer {
+            REMAINING.set(None);
+        }
+    }
+}
+
+/// Spends `ops` operations. `false` means the budget is exhausted and the
+/// caller should stop scanning; always `true` without a `Meter` running.
+pub fn charge(ops: usize) -> bool {
+    let Some(remaining) = REMAINING.get() else {
+        return true;
+    };
+    match remaining.checked_sub(ops as u64) {
+        Some(left) => {
+            REMAINING.set(Some(left));
+            true
+        }
+        None => {
+            REMAINING.set(Some(0));
+            EXHAUSTED.set(true);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meter_budget() {
+        assert!(charge(usize::MAX));
+        let meter = Meter::start(10);
+        assert!(charge(4) && charge(6));
+        assert_eq!(meter.finish(), Ok(()));
+
+        let meter = Meter::start(10);
+        let nested = Meter::start(1_000);
+        assert!(charge(8));
+        assert_eq!(nested.finish(), Ok(()));
+        assert!(!charge(8));
+        assert!(meter.finish().is_err_and(|e| e.starts_with("ResourceLimitExceeded")));
+        assert!(charge(usize::MAX));
+    }
+}
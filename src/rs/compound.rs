@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use cfb::CompoundFile;
+use crate::{FileAnalyzer, FormatInfo, MetadataResult};
+
+/// OLE Compound File Binary signature, shared by MSI, legacy Office and
+/// Outlook `.msg` files.
+const CFB_SIGNATURE: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+/// Well-known root storage CLSIDs and the document type they identify.
+const CLSID_TYPES: [(&str, &str); 8] = [
+    ("{000C1084-0000-0000-C000-000000000046}", "MSI Package"),
+    ("{000C1086-0000-0000-C000-000000000046}", "MSI Patch"),
+    ("{000C1082-0000-0000-C000-000000000046}", "MSI Transform"),
+    ("{00020906-0000-0000-C000-000000000046}", "Word Document"),
+    ("{00020900-0000-0000-C000-000000000046}", "Word 6/95 Document"),
+    ("{00020820-0000-0000-C000-000000000046}", "Excel Workbook"),
+    ("{00020810-0000-0000-C000-000000000046}", "Excel 5/95 Workbook"),
+    ("{64818D10-4F9B-11CF-86EA-00AA00B929E8}", "PowerPoint Presentation"),
+];
+
+/// A compound file opened over an in-memory buffer.
+pub type CfbFile<'a> = CompoundFile<Cursor<&'a [u8]>>;
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "CompoundFile",
+    mime_type: "application/x-ole-storage",
+    extensions: &["cfb"],
+};
+
+/// Any compound file the MSI analyzer declined: `.doc`, `.xls`, `.msg` and
+/// other OLE containers.
+pub struct CompoundFileAnalyzer;
+
+impl FileAnalyzer for CompoundFileAnalyzer {
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        is_compound_file(data)
+    }
+
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
+        let cfb = CompoundFile::open(Cursor::new(data))
+            .map_err(|e| format!("Not a valid compound file: {}", e))?;
+
+        let mut meta = HashMap::new();
+        meta.insert("Format".into(), "CompoundFile".into());
+        insert_root_clsid(&cfb, &mut meta);
+        Ok(meta)
+    }
+}
+
+pub fn is_compound_file(data: &[u8]) -> bool {
+    data.starts_with(CFB_SIGNATURE)
+}
+
+/// The root storage CLSID in registry form (`{000C1084-0000-0000-C000-000000000046}`),
+/// or `None` when the writer left it nil.
+pub fn root_clsid(cfb: &CfbFile) -> Option<String> {
+    let root = cfb.root_entry();
+    let clsid = root.clsid();
+    if clsid.is_nil() {
+        return None;
+    }
+    Some(format!("{{{}}}", clsid.to_string().to_uppercase()))
+}
+
+/// `RootClsid` and, for a well-known CLSID, `CompoundFileType`. The CLSID is
+/// written by the authoring application, so it classifies MSI, MSP, MST and
+/// Office files without looking at their streams.
+pub fn insert_root_clsid(cfb: &CfbFile, meta: &mut HashMap<String, String>) {
+    let Some(clsid) = root_clsid(cfb) else {
+        return;
+    };
+    if let Some((_, kind)) = CLSID_TYPES.iter().find(|(known, _)| *known == clsid) {
+        meta.insert("CompoundFileType".into(), kind.to_string());
+    }
+    meta.insert("RootClsid".into(), clsid);
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// A compound file with the given root-level streams and, when set, root
+    /// CLSID (patched into the root directory entry, since building a `Uuid`
+    /// would need another dependency).
+    pub(crate) fn compound_file(streams: &[&str], clsid: Option<[u8; 16]>) -> Vec<u8> {
+        let Ok(mut cfb) = CompoundFile::create(Cursor::new(Vec::new())) else {
+            return Vec::new();
+        };
+        for name in streams {
+            if let Ok(mut stream) = cfb.create_stream(format!("/{}", name)) {
+                let _ = stream.write_all(b"data");
+            }
+        }
+        let _ = cfb.flush();
+        let mut data = cfb.into_inner().into_inner();
+
+        if let Some(clsid) = clsid {
+            let sector_size = 1usize << u16::from_le_bytes([data[0x1E], data[0x1F]]);
+            let first_directory_sector = u32::from_le_bytes([data[0x30], data[0x31], data[0x32], data[0x33]]) as usize;
+            let root_entry = (first_directory_sector + 1) * sector_size;
+            data[root_entry + 0x50..root_entry + 0x60].copy_from_slice(&clsid);
+        }
+        data
+    }
+
+    #[test]
+    fn test_compound_file_root_clsid() {
+        // {00020906-0000-0000-C000-000000000046} (Word document), little-endian fields
+        let word = [0x06, 0x09, 0x02, 0x00, 0, 0, 0, 0, 0xC0, 0, 0, 0, 0, 0, 0, 0x46];
+        let data = compound_file(&["WordDocument"], Some(word));
+
+        assert!(CompoundFileAnalyzer.sniff(&data));
+        let meta = CompoundFileAnalyzer.parse_metadata(&data).unwrap_or_default();
+        assert_eq!(meta.get("Format").map(String::as_str), Some("CompoundFile"));
+        assert_eq!(
+            meta.get("RootClsid").map(String::as_str),
+            Some("{00020906-0000-0000-C000-000000000046}")
+        );
+        assert_eq!(meta.get("CompoundFileType").map(String::as_str), Some("Word Document"));
+
+        let meta = CompoundFileAnalyzer.parse_metadata(&compound_file(&["Workbook"], None)).unwrap_or_default();
+        assert!(!meta.contains_key("RootClsid"));
+        assert!(!meta.contains_key("CompoundFileType"));
+    }
+}
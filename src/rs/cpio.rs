@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use crate::{FileAnalyzer, FormatInfo, MetadataResult};
+
+const NEWC_MAGIC: &[u8] = b"070701";
+const CRC_MAGIC: &[u8] = b"070702";
+const ODC_MAGIC: &[u8] = b"070707";
+
+const NEWC_HEADER_SIZE: usize = 110;
+const ODC_HEADER_SIZE: usize = 76;
+const TRAILER_NAME: &str = "TRAILER!!!";
+/// Number of entry paths reported in `FirstEntries`.
+const MAX_LISTED_ENTRIES: usize = 10;
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "CPIO",
+    mime_type: "application/x-cpio",
+    extensions: &["cpio"],
+};
+
+pub struct CPIOAnalyzer;
+
+impl FileAnalyzer for CPIOAnalyzer {
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        is_cpio_file(data)
+    }
+
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
+        parse_cpio_metadata(data)
+    }
+
+    fn file_list(&self, data: &[u8], limit: usize) -> Vec<String> {
+        let Some((_, read_entry)) = entry_reader(data) else {
+            return Vec::new();
+        };
+        let mut files = Vec::new();
+        let mut offset: usize = 0;
+        while files.len() < limit && data.get(offset..offset.saturating_add(6)) == Some(&data[..6]) {
+            let Some(entry) = read_entry(data, offset).filter(|entry| entry.name != TRAILER_NAME) else {
+                break;
+            };
+            files.push(entry.name.trim_start_matches("./").to_string());
+            offset = entry.next;
+        }
+        files
+    }
+}
+
+pub fn is_cpio_file(data: &[u8]) -> bool {
+    data.len() >= ODC_HEADER_SIZE
+        && (data.starts_with(NEWC_MAGIC) || data.starts_with(CRC_MAGIC) || data.starts_with(ODC_MAGIC))
+}
+
+struct CpioEntry<'a> {
+    name: &'a str,
+    file_size: usize,
+    /// Offset of the next header.
+    next: usize,
+}
+
+type EntryReader = fn(&[u8], usize) -> Option<CpioEntry<'_>>;
+
+fn parse_number(field: &[u8], radix: u32) -> Option<usize> {
+    let text = std::str::from_utf8(field).ok()?;
+    usize::from_str_radix(text, radix).ok()
+}
+
+fn align4(offset: usize) -> Option<usize> {
+    Some(offset.checked_add(3)? & !3)
+}
+
+/// "New" ASCII header (`070701`/`070702`): 8-digit hex fields, header+name and
+/// file data each padded to a multiple of four bytes.
+fn read_newc_entry(data: &[u8], offset: usize) -> Option<CpioEntry<'_>> {
+    let header = data.get(offset..offset.checked_add(NEWC_HEADER_SIZE)?)?;
+    let file_size = parse_number(&header[54..62], 16)?;
+    let name_size = parse_number(&header[94..102], 16)?;
+
+    let name_start = offset + NEWC_HEADER_SIZE;
+    let name = read_name(data, name_start, name_size)?;
+    let data_start = align4(name_start + name_size)?;
+    let next = align4(data_start.checked_add(file_size)?)?;
+
+    Some(CpioEntry { name, file_size, next })
+}
+
+/// Portable ASCII header (`070707`): 6/11-digit octal fields, no padding.
+fn read_odc_entry(data: &[u8], offset: usize) -> Option<CpioEntry<'_>> {
+    let header = data.get(offset..offset.checked_add(ODC_HEADER_SIZE)?)?;
+    let name_size = parse_number(&header[59..65], 8)?;
+    let file_size = parse_number(&header[65..76], 8)?;
+
+    let name_start = offset + ODC_HEADER_SIZE;
+    let name = read_name(data, name_start, name_size)?;
+    let next = (name_start + name_size).checked_add(file_size)?;
+
+    Some(CpioEntry { name, file_size, next })
+}
+
+/// The header variant of the archive and the reader for its entries.
+fn entry_reader(data: &[u8]) -> Option<(&'static str, EntryReader)> {
+    if data.starts_with(NEWC_MAGIC) {
+        Some(("newc", read_newc_entry))
+    } else if data.starts_with(CRC_MAGIC) {
+        Some(("crc", read_newc_entry))
+    } else if data.starts_with(ODC_MAGIC) {
+        Some(("odc", read_odc_entry))
+    } else {
+        None
+    }
+}
+
+/// `name_size` includes the terminating NUL.
+fn read_name(data: &[u8], start: usize, name_size: usize) -> Option<&str> {
+    let raw = data.get(start..start.checked_add(name_size)?)?;
+    let raw = raw.strip_suffix(&[0]).unwrap_or(raw);
+    std::str::from_utf8(raw).ok()
+}
+
+fn parse_cpio_metadata(data: &[u8]) -> MetadataResult {
+    let (variant, read_entry) = entry_reader(data).ok_or("Not a valid CPIO archive")?;
+    let magic = &data[..6];
+
+    let mut meta = HashMap::new();
+    meta.insert("Format".into(), "CPIO".into());
+    meta.insert("CpioFormat".into(), variant.into());
+
+    let mut offset: usize = 0;
+    let mut entries = 0usize;
+    let mut total_size = 0u64;
+    let mut first_entries = Vec::new();
+    let mut complete = false;
+
+    while data.get(offset..offset.saturating_add(6)) == Some(magic) {
+        let entry = read_entry(data, offset).ok_or("Truncated CPIO entry header")?;
+        if entry.name == TRAILER_NAME {
+            complete = true;
+            break;
+        }
+
+        entries += 1;
+        total_size += entry.file_size as u64;
+        if first_entries.len() < MAX_LISTED_ENTRIES {
+            first_entries.push(entry.name.trim_start_matches("./").to_string());
+        }
+        offset = entry.next;
+    }
+
+    meta.insert("EntryCount".into(), entries.to_string());
+    meta.insert("TotalFileSize".into(), total_size.to_string());
+    if !first_entries.is_empty() {
+        meta.insert("FirstEntries".into(), first_entries.join(", "));
+    }
+    if !complete {
+        meta.insert("MissingTrailer".into(), "true".into());
+    }
+
+    Ok(meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn newc_entry(name: &str, contents: &[u8]) -> Vec<u8> {
+        let mut entry = format!(
+            "070701{:08X}{:08X}{:08X}{:08X}{:08X}{:08X}{:08X}{:08X}{:08X}{:08X}{:08X}{:08X}{:08X}",
+            1, 0o100644, 0, 0, 1, 0, contents.len(), 0, 0, 0, 0, name.len() + 1, 0
+        )
+        .into_bytes();
+        entry.extend_from_slice(name.as_bytes());
+        entry.push(0);
+        entry.resize(align4(entry.len()).unwrap_or_default(), 0);
+        entry.extend_from_slice(contents);
+        entry.resize(align4(entry.len()).unwrap_or_default(), 0);
+        entry
+    }
+
+    #[test]
+    fn test_parse_newc_archive() {
+        let mut archive = newc_entry("./usr/bin/tool", b"#!/bin/sh\n");
+        archive.extend(newc_entry("./usr/share/doc/tool/README", b"hello"));
+        archive.extend(newc_entry(TRAILER_NAME, b""));
+
+        assert!(is_cpio_file(&archive));
+        let meta = parse_cpio_metadata(&archive).unwrap_or_default();
+        assert_eq!(meta.get("EntryCount").map(String::as_str), Some("2"));
+        assert_eq!(meta.get("TotalFileSize").map(String::as_str), Some("15"));
+        assert_eq!(
+            meta.get("FirstEntries").map(String::as_str),
+            Some("usr/bin/tool, usr/share/doc/tool/README")
+        );
+        assert!(!meta.contains_key("MissingTrailer"));
+        assert_eq!(CPIOAnalyzer.file_list(&archive, 1), ["usr/bin/tool"]);
+        assert_eq!(CPIOAnalyzer.file_list(&archive, 10).len(), 2);
+    }
+}
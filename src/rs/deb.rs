@@ -1,25 +1,53 @@
-use std::collections::HashMap;
-use std::io::Read;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufReader, Read, Write};
 use ar::Archive;
 use tar::Archive as TarArchive;
-use flate2::read::GzDecoder;
-use crate::{FileAnalyzer, MetadataResult};
+use flate2::read::MultiGzDecoder;
+use crate::{budget, FileAnalyzer, FormatInfo, MetadataResult};
+
+/// ar members carrying detached GPG signatures: `_gpgorigin` from debsigs,
+/// `_gpgbuilder`/`_gpgmaint` from dpkg-sig and debsigs roles.
+const SIGNATURE_MEMBERS: [&str; 3] = ["_gpgorigin", "_gpgbuilder", "_gpgmaint"];
+
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+
+/// The first ar member of every package. Its name is searched for in the
+/// leading bytes rather than parsed, whatever padding or long-name scheme
+/// the `ar` that wrote it used.
+const DEBIAN_BINARY: &[u8] = b"debian-binary";
+/// The magic, the first member header and a BSD long name fit in here.
+const SNIFF_WINDOW: usize = 128;
+
+const TAR_BLOCK_SIZE: usize = 512;
+/// Longest GNU long name (`L` member) kept; the rest is skipped.
+const MAX_LONG_NAME: usize = 4096;
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "DEB",
+    mime_type: "application/vnd.debian.binary-package",
+    extensions: &["deb", "ddeb"],
+};
 
 pub struct DEBAnalyzer;
 
 impl FileAnalyzer for DEBAnalyzer {
-    fn get_file_info(_data: &[u8]) -> HashMap<String, String> {
-        let mut info = HashMap::new();
-        info.insert("Format".to_string(), "DEB".to_string());
-        info
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        is_deb_file(data)
     }
 
-    fn parse_metadata(data: &[u8]) -> MetadataResult {
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
         let mut meta = HashMap::new();
         meta.insert("Format".into(), "DEB".into());
 
         let mut archive = Archive::new(data);
         let mut control_found = false;
+        let mut signatures = Vec::new();
+        let mut signature_size = 0u64;
+        let mut payload_size = None;
 
         while let Some(entry_result) = archive.next_entry() {
             let entry = entry_result.map_err(|e| format!("Failed to read ar entry: {}", e))?;
@@ -28,13 +56,26 @@ impl FileAnalyzer for DEBAnalyzer {
                 .unwrap_or("")
                 .trim_end_matches('/');
 
-            if name.starts_with("control.tar") {
+            if name.starts_with("data.tar") {
+                payload_size = Some(header.size());
+            }
+            if SIGNATURE_MEMBERS.contains(&name) {
+                signatures.push(name.to_string());
+                signature_size += header.size();
+                continue;
+            }
+
+            // Signatures follow data.tar, so keep walking once control is read
+            if name.starts_with("control.tar") && !control_found {
                 control_found = true;
 
                 // DEB control tarballs can be compressed with gzip (.gz), xz (.xz), etc.
                 // We'll prioritize .gz for now as it's the most common for control.
                 if name.ends_with(".gz") {
-                    let decoder = GzDecoder::new(entry);
+                    // Some builders (e.g. pigz, parallel gzip) write several
+                    // concatenated gzip members; a plain GzDecoder stops after
+                    // the first and truncates the tarball.
+                    let decoder = MultiGzDecoder::new(entry);
                     let mut tar = TarArchive::new(decoder);
 
                     for tar_entry_result in tar.entries().map_err(|e| format!("Failed to read tar entries: {}", e))? {
@@ -53,7 +94,6 @@ impl FileAnalyzer for DEBAnalyzer {
                 } else {
                     return Err(format!("Unsupported control archive compression: {}", name));
                 }
-                break;
             }
         }
 
@@ -61,8 +101,149 @@ impl FileAnalyzer for DEBAnalyzer {
             return Err("control.tar not found in DEB archive".to_string());
         }
 
+        // Installed-Size is in KiB
+        let installed = meta.get("Installed-Size").and_then(|size| size.parse::<u64>().ok());
+        if let (Some(payload_size), Some(installed)) = (payload_size, installed) {
+            meta.insert("CompressedSize".into(), payload_size.to_string());
+            meta.insert("UncompressedSize".into(), installed.saturating_mul(1024).to_string());
+        }
+
+        meta.insert("IsSigned".into(), (!signatures.is_empty()).to_string());
+        if !signatures.is_empty() {
+            meta.insert("SignatureMembers".into(), signatures.join(", "));
+            meta.insert("SignatureSize".into(), signature_size.to_string());
+        }
+
         Ok(meta)
     }
+
+    /// Paths in `data.tar`, read as the member decompresses so the payload is
+    /// never held in memory. zstd and bzip2 payloads are not listed.
+    fn file_list(&self, data: &[u8], limit: usize) -> Vec<String> {
+        let mut paths = TarPaths::new(limit);
+        let mut archive = Archive::new(data);
+        while let Some(Ok(mut entry)) = archive.next_entry() {
+            let name = String::from_utf8_lossy(entry.header().identifier()).trim_end_matches('/').to_string();
+            // Reaching the limit stops the copy with an error; the paths up
+            // to there stand either way
+            let _ = match name.as_str() {
+                "data.tar" => io::copy(&mut entry, &mut paths).map(drop),
+                "data.tar.gz" => io::copy(&mut MultiGzDecoder::new(entry), &mut paths).map(drop),
+                "data.tar.xz" => lzma_rs::xz_decompress(&mut BufReader::new(entry), &mut paths).map_err(io::Error::other),
+                _ => continue,
+            };
+            break;
+        }
+        paths.paths
+    }
+}
+
+/// Collects member paths from a tar stream written into it, skipping file
+/// contents instead of buffering them. Writes fail once it holds `limit`
+/// paths or the work budget is spent.
+struct TarPaths {
+    limit: usize,
+    paths: Vec<String>,
+    /// The header block read so far.
+    block: Vec<u8>,
+    /// Bytes of member data, with padding, still to skip.
+    remaining: u64,
+    /// Data of a GNU long name member being read, for the member after it.
+    long_name: Option<Vec<u8>>,
+    next_name: Option<String>,
+}
+
+impl TarPaths {
+    fn new(limit: usize) -> Self {
+        TarPaths { limit, paths: Vec::new(), block: Vec::new(), remaining: 0, long_name: None, next_name: None }
+    }
+
+    fn read_header(&mut self) -> io::Result<()> {
+        if !budget::charge(TAR_BLOCK_SIZE) {
+            return Err(io::Error::other("work budget spent"));
+        }
+        let block = std::mem::take(&mut self.block);
+        // The end-of-archive marker is two zero blocks
+        if block.iter().all(|&b| b == 0) {
+            return Ok(());
+        }
+        let size = tar_size(&block[124..136]);
+        self.remaining = size.div_ceil(TAR_BLOCK_SIZE as u64) * TAR_BLOCK_SIZE as u64;
+        match block[156] {
+            b'L' if size > 0 => self.long_name = Some(Vec::new()),
+            // pax and GNU long link headers describe the next member
+            b'L' | b'K' | b'x' | b'g' => {}
+            _ => {
+                let name = self.next_name.take().unwrap_or_else(|| ustar_name(&block));
+                let name = name.trim_start_matches("./");
+                if !name.is_empty() {
+                    self.paths.push(name.to_string());
+                }
+                if self.paths.len() >= self.limit {
+                    return Err(io::Error::other("file list limit reached"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Write for TarPaths {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while !rest.is_empty() {
+            if self.remaining > 0 {
+                let n = rest.len().min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+                if let Some(long_name) = self.long_name.as_mut() {
+                    let room = MAX_LONG_NAME.saturating_sub(long_name.len());
+                    long_name.extend_from_slice(&rest[..n.min(room)]);
+                }
+                self.remaining -= n as u64;
+                rest = &rest[n..];
+                if self.remaining == 0 {
+                    self.next_name = self.long_name.take().map(|name| c_string(&name));
+                }
+                continue;
+            }
+            let n = rest.len().min(TAR_BLOCK_SIZE - self.block.len());
+            self.block.extend_from_slice(&rest[..n]);
+            rest = &rest[n..];
+            if self.block.len() == TAR_BLOCK_SIZE {
+                self.read_header()?;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+/// Member size: octal digits, or big-endian binary after a set high bit
+/// (GNU, for sizes of 8 GiB and more).
+fn tar_size(field: &[u8]) -> u64 {
+    if field[0] & 0x80 != 0 {
+        return field[1..].iter().fold(0u64, |acc, &b| acc.saturating_mul(256).saturating_add(b as u64));
+    }
+    let digits = c_string(field);
+    u64::from_str_radix(digits.trim(), 8).unwrap_or(0)
+}
+
+/// `name`, behind the ustar `prefix` when there is one.
+fn ustar_name(block: &[u8]) -> String {
+    let name = c_string(&block[0..100]);
+    let prefix = c_string(&block[345..500]);
+    if &block[257..262] == b"ustar" && !prefix.is_empty() {
+        format!("{}/{}", prefix, name)
+    } else {
+        name
+    }
 }
 
 fn parse_control_file(content: &str, meta: &mut HashMap<String, String>) {
@@ -82,20 +263,226 @@ fn parse_control_file(content: &str, meta: &mut HashMap<String, String>) {
             }
         }
     }
+    insert_normalized_fields(meta);
+    insert_source_fields(meta);
 }
 
+/// Normalized forms of the control fields intake policy keys on:
+/// `PackageSection` without the archive area (`contrib/net` is `net`),
+/// `PackagePriority` with the deprecated `extra` as `optional`, `MultiArch`
+/// (`no` when unset, as in Debian policy), the `IsEssential` boolean and
+/// `IsDebugPackage` for automatic debug symbol packages (`.ddeb`).
+fn insert_normalized_fields(meta: &mut HashMap<String, String>) {
+    if let Some(section) = meta.get("Section") {
+        let section = section.rsplit('/').next().unwrap_or(section).to_lowercase();
+        meta.insert("PackageSection".into(), section);
+    }
+    if let Some(priority) = meta.get("Priority") {
+        let priority = match priority.to_lowercase().as_str() {
+            "extra" => "optional".to_string(),
+            other => other.to_string(),
+        };
+        meta.insert("PackagePriority".into(), priority);
+    }
+
+    let multi_arch = meta.get("Multi-Arch").map_or_else(|| "no".to_string(), |value| value.to_lowercase());
+    meta.insert("MultiArch".into(), multi_arch);
+    let essential = meta.get("Essential").is_some_and(|value| value.eq_ignore_ascii_case("yes"));
+    meta.insert("IsEssential".into(), essential.to_string());
+
+    let debug = meta.get("Package").is_some_and(|name| name.ends_with("-dbgsym"))
+        || meta.get("Auto-Built-Package").is_some_and(|value| value == "debug-symbols");
+    meta.insert("IsDebugPackage".into(), debug.to_string());
+}
+
+/// Splits `name (version)` as written in `Source`, or `name (= version)` as
+/// in a `Built-Using` relation, into the name and the version.
+fn split_versioned(value: &str) -> (&str, Option<&str>) {
+    let Some((name, version)) = value.split_once('(') else {
+        return (value.trim(), None);
+    };
+    let version = version.trim_end().trim_end_matches(')').trim().trim_start_matches('=').trim();
+    (name.trim(), Some(version).filter(|version| !version.is_empty()))
+}
+
+/// `SourcePackage` and `SourceVersion`, which default to the binary package's
+/// own name and version when `Source` omits them (Debian policy 5.6.1), and
+/// `BuiltUsing`, the `Built-Using` relations as a JSON object mapping each
+/// source package to its version.
+fn insert_source_fields(meta: &mut HashMap<String, String>) {
+    let source = meta.get("Source").map(|source| split_versioned(source));
+    let package = source.map(|(name, _)| name).or(meta.get("Package").map(String::as_str));
+    let version = source.and_then(|(_, version)| version).or(meta.get("Version").map(String::as_str));
+    let (package, version) = (package.map(str::to_string), version.map(str::to_string));
+
+    let built_using = meta.get("Built-Using").map(|relations| {
+        let relations: BTreeMap<&str, &str> = relations
+            .split(',')
+            .map(split_versioned)
+            .filter(|(name, _)| !name.is_empty())
+            .map(|(name, version)| (name, version.unwrap_or_default()))
+            .collect();
+        serde_json::to_string(&relations).unwrap_or_default()
+    });
+
+    if let Some(package) = package {
+        meta.insert("SourcePackage".into(), package);
+    }
+    if let Some(version) = version {
+        meta.insert("SourceVersion".into(), version);
+    }
+    if let Some(built_using) = built_using {
+        meta.insert("BuiltUsing".into(), built_using);
+    }
+}
+
+/// Magic-only check; the archive itself is read once, by `parse_metadata`.
 pub fn is_deb_file(data: &[u8]) -> bool {
-    // DEB files start with !<arch>\n
-    if data.len() < 8 || &data[0..8] != b"!<arch>\n" {
-        return false;
+    data.starts_with(AR_MAGIC)
+        && data[..data.len().min(SNIFF_WINDOW)].windows(DEBIAN_BINARY.len()).any(|window| window == DEBIAN_BINARY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// An uncompressed `control.tar` holding `./control`.
+    fn control_tar(control: &str) -> Vec<u8> {
+        let mut control_tar = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(control.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        let _ = control_tar.append_data(&mut header, "./control", control.as_bytes());
+        control_tar.into_inner().unwrap_or_default()
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        let _ = gz.write_all(data);
+        gz.finish().unwrap_or_default()
+    }
+
+    /// A package whose ar members end with `data.tar.xz` and, when given, a
+    /// `_gpgorigin` signature.
+    fn deb_package(control: &str, signature: Option<&[u8]>) -> Vec<u8> {
+        deb_archive(&gzip(&control_tar(control)), signature)
+    }
+
+    fn deb_archive(control_tar: &[u8], signature: Option<&[u8]>) -> Vec<u8> {
+        let mut deb = ar::Builder::new(Vec::new());
+        let mut append = |name: &str, contents: &[u8]| {
+            let header = ar::Header::new(name.as_bytes().to_vec(), contents.len() as u64);
+            let _ = deb.append(&header, contents);
+        };
+        append("debian-binary", b"2.0\n");
+        append("control.tar.gz", control_tar);
+        append("data.tar.xz", b"payload");
+        if let Some(signature) = signature {
+            append("_gpgorigin", signature);
+        }
+        deb.into_inner().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_multi_member_gzip_control() {
+        // Long enough that the control file spans the split between members
+        let control = format!("Package: hello\nVersion: 1.0\nDescription: {}\nSection: net\n", "x".repeat(600));
+        let tar = control_tar(&control);
+        let members = [gzip(&tar[..1024]), gzip(&tar[1024..])].concat();
+
+        let meta = DEBAnalyzer.parse_metadata(&deb_archive(&members, None)).unwrap_or_default();
+        assert_eq!(meta.get("Package").map(String::as_str), Some("hello"));
+        assert_eq!(meta.get("Section").map(String::as_str), Some("net"));
+    }
+
+    #[test]
+    fn test_signed_package() {
+        let control = "Package: hello\nVersion: 1.0\nInstalled-Size: 8\n";
+        let signed = deb_package(control, Some(&[0xA5; 566]));
+        assert!(is_deb_file(&signed));
+        // A static library is an ar archive too
+        assert!(!is_deb_file(b"!<arch>\n/               0           0     0     0       4         `\n"));
+
+        let meta = DEBAnalyzer.parse_metadata(&signed).unwrap_or_default();
+        assert_eq!(meta.get("Package").map(String::as_str), Some("hello"));
+        assert_eq!(meta.get("IsSigned").map(String::as_str), Some("true"));
+        assert_eq!(meta.get("SignatureMembers").map(String::as_str), Some("_gpgorigin"));
+        assert_eq!(meta.get("SignatureSize").map(String::as_str), Some("566"));
+        assert_eq!(meta.get("CompressedSize").map(String::as_str), Some("7"));
+        assert_eq!(meta.get("UncompressedSize").map(String::as_str), Some("8192"));
+
+        let meta = DEBAnalyzer.parse_metadata(&deb_package(control, None)).unwrap_or_default();
+        assert_eq!(meta.get("IsSigned").map(String::as_str), Some("false"));
+        assert!(!meta.contains_key("SignatureSize"));
+    }
+
+    #[test]
+    fn test_file_list() {
+        let mut data_tar = tar::Builder::new(Vec::new());
+        let long_path = format!("./usr/share/doc/hello/{}.txt", "x".repeat(120));
+        for path in ["./usr/bin/hello", "./usr/share/doc/hello/copyright", long_path.as_str()] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(700);
+            header.set_mode(0o644);
+            let _ = data_tar.append_data(&mut header, path, &[0x55; 700][..]);
+        }
+        let data_tar = gzip(&data_tar.into_inner().unwrap_or_default());
+
+        let mut deb = ar::Builder::new(Vec::new());
+        for (name, contents) in [("debian-binary", b"2.0\n".as_slice()), ("data.tar.gz", &data_tar)] {
+            let _ = deb.append(&ar::Header::new(name.as_bytes().to_vec(), contents.len() as u64), contents);
+        }
+        let deb = deb.into_inner().unwrap_or_default();
+
+        let files = DEBAnalyzer.file_list(&deb, 10);
+        assert_eq!(files, ["usr/bin/hello", "usr/share/doc/hello/copyright", &long_path[2..]]);
+        assert_eq!(DEBAnalyzer.file_list(&deb, 1), ["usr/bin/hello"]);
     }
 
-    // Also check for debian-binary member to be sure
-    let mut archive = Archive::new(data);
-    if let Some(Ok(entry)) = archive.next_entry() {
-        let identifier = entry.header().identifier();
-        return identifier == b"debian-binary" || identifier == b"debian-binary/   ";
+    #[test]
+    fn test_parse_control_file_normalized_fields() {
+        let control = "Package: curl\nVersion: 7.88.1-10\nSection: contrib/Net\nPriority: extra\nMulti-Arch: Same\n";
+        let mut meta = HashMap::new();
+        parse_control_file(control, &mut meta);
+
+        assert_eq!(meta.get("Section").map(String::as_str), Some("contrib/Net"));
+        assert_eq!(meta.get("PackageSection").map(String::as_str), Some("net"));
+        assert_eq!(meta.get("PackagePriority").map(String::as_str), Some("optional"));
+        assert_eq!(meta.get("MultiArch").map(String::as_str), Some("same"));
+        assert_eq!(meta.get("IsEssential").map(String::as_str), Some("false"));
+
+        let mut meta = HashMap::new();
+        parse_control_file("Package: dpkg\nEssential: yes\n", &mut meta);
+        assert_eq!(meta.get("MultiArch").map(String::as_str), Some("no"));
+        assert_eq!(meta.get("IsEssential").map(String::as_str), Some("true"));
+        assert_eq!(meta.get("IsDebugPackage").map(String::as_str), Some("false"));
+
+        for control in ["Package: hello-dbgsym\n", "Package: hello-dbg\nAuto-Built-Package: debug-symbols\n"] {
+            let mut meta = HashMap::new();
+            parse_control_file(control, &mut meta);
+            assert_eq!(meta.get("IsDebugPackage").map(String::as_str), Some("true"));
+        }
     }
 
-    false
+    #[test]
+    fn test_source_fields() {
+        let control = "Package: libfoo1\nVersion: 1.2-3+b1\nSource: foo (1.2-3)\n\
+                       Built-Using: gcc-12 (= 12.2.0-14), rustc-web (= 1.70.0+dfsg1-5)\n";
+        let mut meta = HashMap::new();
+        parse_control_file(control, &mut meta);
+        assert_eq!(meta.get("SourcePackage").map(String::as_str), Some("foo"));
+        assert_eq!(meta.get("SourceVersion").map(String::as_str), Some("1.2-3"));
+        assert_eq!(
+            meta.get("BuiltUsing").map(String::as_str),
+            Some(r#"{"gcc-12":"12.2.0-14","rustc-web":"1.70.0+dfsg1-5"}"#)
+        );
+
+        let mut meta = HashMap::new();
+        parse_control_file("Package: hello\nVersion: 2.10-3\n", &mut meta);
+        assert_eq!(meta.get("SourcePackage").map(String::as_str), Some("hello"));
+        assert_eq!(meta.get("SourceVersion").map(String::as_str), Some("2.10-3"));
+        assert!(!meta.contains_key("BuiltUsing"));
+    }
 }
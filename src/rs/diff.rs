@@ -0,0 +1,98 @@
+//! Field-by-field comparison of two analyses, e.g. two releases of the same
+//! product.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+
+use serde::Serialize;
+
+use crate::version::{compare_versions, VersionScheme};
+use crate::AnalysisResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// Direction of a change between two values that both parse as versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionChange {
+    Upgrade,
+    Downgrade,
+}
+
+/// One field that differs between two analyses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldDiff {
+    pub field: String,
+    pub kind: DiffKind,
+    /// The value in the first analysis; `None` when the field was added.
+    pub old: Option<String>,
+    /// The value in the second analysis; `None` when the field was removed.
+    pub new: Option<String>,
+    /// Set on `Changed` fields whose values are both versions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version_change: Option<VersionChange>,
+}
+
+/// Fields added, removed or changed from `a` to `b`, sorted by field name.
+pub fn diff(a: &AnalysisResult, b: &AnalysisResult) -> Vec<FieldDiff> {
+    diff_fields(&a.fields, &b.fields)
+}
+
+/// `diff` over two metadata maps. Values are compared as versions in the
+/// scheme of the first map's `Format` (RPM EVR, Debian, otherwise dotted), and
+/// those that compare equal (`1.2` and `1.2.0`) are not reported as changed.
+pub fn diff_fields(a: &HashMap<String, String>, b: &HashMap<String, String>) -> Vec<FieldDiff> {
+    let scheme = VersionScheme::for_format(a.get("Format").map_or("", String::as_str));
+    let fields: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let (old, new) = (a.get(field), b.get(field));
+            let (kind, version_change) = match (old, new) {
+                (None, _) => (DiffKind::Added, None),
+                (_, None) => (DiffKind::Removed, None),
+                (Some(old), Some(new)) if old == new => return None,
+                (Some(old), Some(new)) => match compare_versions(old, new, scheme) {
+                    Some(Ordering::Equal) => return None,
+                    Some(Ordering::Less) => (DiffKind::Changed, Some(VersionChange::Upgrade)),
+                    Some(Ordering::Greater) => (DiffKind::Changed, Some(VersionChange::Downgrade)),
+                    None => (DiffKind::Changed, None),
+                },
+            };
+            Some(FieldDiff { field: field.clone(), kind, old: old.cloned(), new: new.cloned(), version_change })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_diff_fields() {
+        let a = fields(&[("Format", "PE"), ("ProductVersion", "1.9.0"), ("FileVersion", "1.2"), ("IsDll", "false")]);
+        let b = fields(&[("Format", "PE"), ("ProductVersion", "1.10.0"), ("FileVersion", "1.2.0"), ("ASLR", "true")]);
+
+        let diffs = diff_fields(&a, &b);
+        let summary: Vec<(&str, DiffKind)> = diffs.iter().map(|d| (d.field.as_str(), d.kind)).collect();
+        assert_eq!(
+            summary,
+            [("ASLR", DiffKind::Added), ("IsDll", DiffKind::Removed), ("ProductVersion", DiffKind::Changed)]
+        );
+        assert_eq!(diffs[0].version_change, None);
+        assert_eq!(diffs[2].version_change, Some(VersionChange::Upgrade));
+        assert_eq!(diffs[2].old.as_deref(), Some("1.9.0"));
+        assert_eq!(diffs[2].new.as_deref(), Some("1.10.0"));
+    }
+}
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::io::Cursor;
-use crate::{FileAnalyzer, MetadataResult};
+use crate::provenance::{self, Source};
+use crate::{budget, locale, Confidence, FileAnalyzer, FormatInfo, MetadataResult};
 use plist::Value;
 
 // Constants for DMG file analysis
@@ -8,53 +9,85 @@ const DMG_KOLY_SIGNATURE: &[u8] = b"koly";
 const DMG_KOLY_OFFSET_SIZE: usize = 512;
 const MIN_DMG_SIZE: usize = 512;
 
+/// koly fields locating the XML property list that holds the `blkx` tables.
+const KOLY_XML_OFFSET: usize = 0xD8;
+const KOLY_XML_LENGTH: usize = 0xE0;
+
+/// A `mish` block table: its chunk count ends a 204-byte header, then come
+/// 40-byte chunk entries starting with their big-endian type.
+const MISH_SIGNATURE: &[u8] = b"mish";
+const MISH_CHUNK_COUNT_OFFSET: usize = 0xC8;
+const MISH_HEADER_SIZE: usize = 0xCC;
+const MISH_CHUNK_SIZE: usize = 40;
+
+/// `blkx` chunk types and the compression they stand for. Zero-fill, ignored,
+/// comment and terminator chunks say nothing about it.
+const CHUNK_COMPRESSIONS: [(u32, &str); 6] = [
+    (0x0000_0001, "uncompressed"),
+    (0x8000_0004, "adc"),
+    (0x8000_0005, "zlib"),
+    (0x8000_0006, "bzip2"),
+    (0x8000_0007, "lzfse"),
+    (0x8000_0008, "lzma"),
+];
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "DMG",
+    mime_type: "application/x-apple-diskimage",
+    extensions: &["dmg"],
+};
+
 pub struct DMGAnalyzer;
 
 impl FileAnalyzer for DMGAnalyzer {
-    fn get_file_info(_data: &[u8]) -> HashMap<String, String> {
-        let mut info = HashMap::new();
-        info.insert("Format".to_string(), "DMG".to_string());
-        info
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        is_dmg_file(data)
+    }
+
+    fn detection_confidence(&self, data: &[u8]) -> Confidence {
+        if has_valid_koly_header(data) {
+            Confidence::High
+        } else {
+            Confidence::Medium
+        }
     }
 
-    fn parse_metadata(data: &[u8]) -> MetadataResult {
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
         parse_dmg_metadata(data)
     }
+
+    fn raw_manifest(&self, data: &[u8]) -> Option<Vec<u8>> {
+        find_plist_in_dmg(data)
+    }
 }
 
+/// The koly magic in the last 512 bytes. `detection_confidence` grades it
+/// High when the rest of the trailer checks out, Medium on the magic alone.
 pub fn is_dmg_file(data: &[u8]) -> bool {
     if data.len() < MIN_DMG_SIZE {
         return false;
     }
 
-    if data.len() >= DMG_KOLY_OFFSET_SIZE {
-        let end_offset = data.len() - DMG_KOLY_OFFSET_SIZE;
+    let end_offset = data.len() - DMG_KOLY_OFFSET_SIZE;
+    &data[end_offset..end_offset + 4] == DMG_KOLY_SIGNATURE
+}
 
-        if &data[end_offset..end_offset + 4] == DMG_KOLY_SIGNATURE {
-            return true;
-        }
+/// Returns true when the koly trailer also carries the expected UDIF version (4)
+/// and header size (512), not just the magic bytes.
+fn has_valid_koly_header(data: &[u8]) -> bool {
+    if data.len() < DMG_KOLY_OFFSET_SIZE {
+        return false;
     }
 
-    if data.len() >= 4
-        && (data[0..4] == [0x78, 0x01, 0x73, 0x0D] ||
-           data[0..4] == [0x78, 0x9C, 0xEC, 0xBD] ||
-           data[0..4] == [0x78, 0x9C, 0x00, 0x00] ||
-           data[0..2] == [0x78, 0x01] ||
-           data[0..2] == [0x78, 0x5E] ||
-           data[0..2] == [0x78, 0x9C] ||
-           data[0..2] == [0x78, 0xDA] ||
-           data[0..2] == [0x1F, 0x8B] ||
-           data[0..4] == [0x42, 0x5A, 0x68, 0x39] ||
-           data[0..4] == [0x42, 0x5A, 0x68, 0x31])
-        && data.len() >= DMG_KOLY_OFFSET_SIZE
-    {
-        let end_offset = data.len() - DMG_KOLY_OFFSET_SIZE;
-        if &data[end_offset..end_offset + 4] == DMG_KOLY_SIGNATURE {
-            return true;
-        }
-    }
+    let koly = &data[data.len() - DMG_KOLY_OFFSET_SIZE..];
+    let version = u32::from_be_bytes([koly[4], koly[5], koly[6], koly[7]]);
+    let header_size = u32::from_be_bytes([koly[8], koly[9], koly[10], koly[11]]);
 
-    false
+    &koly[0..4] == DMG_KOLY_SIGNATURE && version == 4 && header_size == DMG_KOLY_OFFSET_SIZE as u32
 }
 
 fn parse_dmg_metadata(data: &[u8]) -> MetadataResult {
@@ -82,6 +115,10 @@ fn parse_dmg_metadata(data: &[u8]) -> MetadataResult {
 
         meta.insert("Compression".into(), compression.into());
     }
+    // The block tables name the codec outright, e.g. LZFSE in ULFO images
+    if let Some(compression) = blkx_compression(data) {
+        meta.insert("Compression".into(), compression.into());
+    }
 
     if data.len() >= 512 {
         let koly_offset = data.len() - 512;
@@ -109,12 +146,55 @@ fn parse_dmg_metadata(data: &[u8]) -> MetadataResult {
     Ok(meta)
 }
 
+fn read_u64_be(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(data.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+/// The compression of the image's data chunks according to the `blkx` block
+/// tables of the koly property list: the first compressed chunk type found,
+/// or `uncompressed` when every chunk is stored raw.
+fn blkx_compression(data: &[u8]) -> Option<&'static str> {
+    let koly = data.get(data.len().checked_sub(DMG_KOLY_OFFSET_SIZE)?..)?;
+    if !koly.starts_with(DMG_KOLY_SIGNATURE) {
+        return None;
+    }
+    let xml_offset = usize::try_from(read_u64_be(koly, KOLY_XML_OFFSET)?).ok()?;
+    let xml_length = usize::try_from(read_u64_be(koly, KOLY_XML_LENGTH)?).ok()?;
+    let xml = data.get(xml_offset..xml_offset.checked_add(xml_length)?)?;
+    if !budget::charge(xml.len()) {
+        return None;
+    }
+    let plist = Value::from_reader_xml(xml).ok()?;
+    let blkx = plist.as_dictionary()?.get("resource-fork")?.as_dictionary()?.get("blkx")?.as_array()?;
+
+    let mut compression = None;
+    for table in blkx.iter().filter_map(|entry| entry.as_dictionary()?.get("Data")?.as_data()) {
+        if !table.starts_with(MISH_SIGNATURE) {
+            continue;
+        }
+        let count = table.get(MISH_CHUNK_COUNT_OFFSET..MISH_HEADER_SIZE)?;
+        let count = u32::from_be_bytes(count.try_into().ok()?) as usize;
+        for chunk in table[MISH_HEADER_SIZE..].chunks_exact(MISH_CHUNK_SIZE).take(count) {
+            let chunk_type = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            match CHUNK_COMPRESSIONS.iter().find(|(known, _)| *known == chunk_type) {
+                Some((_, "uncompressed")) => {
+                    compression.get_or_insert("uncompressed");
+                }
+                Some((_, codec)) => return Some(codec),
+                None => {}
+            }
+        }
+    }
+    compression
+}
+
 fn extract_product_info(data: &[u8], meta: &mut HashMap<String, String>) {
     if let Some(plist_data) = find_plist_in_dmg(data) {
         parse_plist_properly(&plist_data, meta);
     }
 
     if !meta.contains_key("ProductName") || !meta.contains_key("ProductVersion") {
+        let tagging = provenance::Tagging::begin(Source::Heuristic, meta);
         let data_str = String::from_utf8_lossy(data);
         extract_plist_info(&data_str, meta);
         extract_version_strings(&data_str, meta);
@@ -124,11 +204,13 @@ fn extract_product_info(data: &[u8], meta: &mut HashMap<String, String>) {
         if !meta.contains_key("ProductName") {
             extract_app_names(data, meta);
         }
+        tagging.finish(meta);
     }
 
     create_field_aliases(meta);
 }
 
+#[allow(clippy::unnecessary_min_or_max)]
 fn find_plist_in_dmg(data: &[u8]) -> Option<Vec<u8>> {
     let data_str = String::from_utf8_lossy(data);
 
@@ -179,10 +261,13 @@ fn find_plist_in_region(data: &[u8]) -> Option<Vec<u8>> {
 
 #[inline]
 fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if !budget::charge(haystack.len()) {
+        return None;
+    }
     haystack.windows(needle.len()).position(|window| window == needle)
 }
 
-fn parse_plist_properly(plist_data: &[u8], meta: &mut HashMap<String, String>) {
+pub(crate) fn parse_plist_properly(plist_data: &[u8], meta: &mut HashMap<String, String>) {
     let value = Value::from_reader_xml(plist_data)
         .or_else(|_| {
             let cursor = Cursor::new(plist_data);
@@ -204,6 +289,7 @@ fn parse_plist_properly(plist_data: &[u8], meta: &mut HashMap<String, String>) {
             ("NSPrincipalClass", "PrincipalClass"),
             ("CFBundleIconFile", "IconFile"),
             ("LSMinimumSystemVersion", "MinimumSystemVersion"),
+            ("CFBundleDevelopmentRegion", "Language"),
         ];
 
         for (plist_key, meta_key) in &keys_to_extract {
@@ -219,6 +305,8 @@ fn parse_plist_properly(plist_data: &[u8], meta: &mut HashMap<String, String>) {
                             .collect::<Vec<_>>()
                             .join(" ");
                         meta.insert((*meta_key).to_string(), clean);
+                    } else if *meta_key == "Language" {
+                        meta.insert((*meta_key).to_string(), locale::language_from_region(value));
                     } else {
                         meta.insert((*meta_key).to_string(), value.to_string());
                     }
@@ -244,7 +332,10 @@ fn parse_plist_properly(plist_data: &[u8], meta: &mut HashMap<String, String>) {
                 if parts.len() >= 2 {
                     let company = parts[1];
                     if !company.is_empty() && company.chars().all(|c| c.is_alphanumeric()) {
-                        meta.insert("CompanyName".into(), capitalize_first(company));
+                        let company = capitalize_first(company);
+                        let tagging = provenance::Tagging::begin(Source::Heuristic, meta);
+                        meta.insert("CompanyName".into(), company);
+                        tagging.finish(meta);
                     }
                 }
             }
@@ -398,6 +489,15 @@ fn extract_bundle_info(data_str: &str, meta: &mut HashMap<String, String>) {
     }
 }
 
+/// `s[start..end]`, narrowed to the nearest char boundaries inside the range so
+/// byte offsets computed around a match cannot split a multi-byte character.
+fn char_window(s: &str, start: usize, end: usize) -> &str {
+    let end = end.min(s.len());
+    let end = (start..=end).rev().find(|&idx| s.is_char_boundary(idx)).unwrap_or(start);
+    let start = (start..=end).find(|&idx| s.is_char_boundary(idx)).unwrap_or(end);
+    s.get(start..end).unwrap_or_default()
+}
+
 fn extract_developer_info(data_str: &str, meta: &mut HashMap<String, String>) {
     let company_patterns = [
         "Copyright",
@@ -411,9 +511,7 @@ fn extract_developer_info(data_str: &str, meta: &mut HashMap<String, String>) {
 
     for pattern in &company_patterns {
         if let Some(pos) = data_str.find(pattern) {
-            let start = pos.saturating_sub(100).max(0);
-            let end = (pos + 100).min(data_str.len());
-            let context = &data_str[start..end];
+            let context = char_window(data_str, pos.saturating_sub(100), pos + 100);
 
             if pattern == &"Copyright" {
                 if let Some(copy_pos) = context.find("Copyright") {
@@ -435,6 +533,9 @@ fn extract_developer_info(data_str: &str, meta: &mut HashMap<String, String>) {
 }
 
 fn extract_app_names(data: &[u8], meta: &mut HashMap<String, String>) {
+    if !budget::charge(data.len()) {
+        return;
+    }
     let data_str = String::from_utf8_lossy(data);
 
     let skip_names = ["www", "html", "com", "http", "https", "ftp", "temp", "tmp",
@@ -442,10 +543,12 @@ fn extract_app_names(data: &[u8], meta: &mut HashMap<String, String>) {
                       "resources", "frameworks", "macos", "contents", "applications"];
 
     for match_pos in data_str.match_indices(".app") {
+        if !budget::charge(100) {
+            break;
+        }
         let pos = match_pos.0;
-        let start = pos.saturating_sub(100);
 
-        let before = &data_str[start..pos];
+        let before = char_window(&data_str, pos.saturating_sub(100), pos);
         if let Some(last_slash) = before.rfind('/') {
             let app_name = &before[last_slash + 1..];
             let app_name_lower = app_name.to_lowercase();
@@ -509,9 +612,11 @@ fn create_field_aliases(meta: &mut HashMap<String, String>) {
             meta.insert("ProductName".into(), sanitized.clone());
             if !meta.contains_key("ProgramName") {
                 meta.insert("ProgramName".into(), sanitized.clone());
+                provenance::derive("ProgramName", "ProductName");
             }
             if !meta.contains_key("FileDescription") {
                 meta.insert("FileDescription".into(), format!("{} Installer", sanitized));
+                provenance::derive("FileDescription", "ProductName");
             }
         }
     }
@@ -519,21 +624,20 @@ fn create_field_aliases(meta: &mut HashMap<String, String>) {
     if let Some(company) = meta.get("CompanyName").cloned() {
         if !meta.contains_key("Vendor") {
             meta.insert("Vendor".into(), company.clone());
+            provenance::derive("Vendor", "CompanyName");
         }
         if !meta.contains_key("Publisher") {
             meta.insert("Publisher".into(), company);
+            provenance::derive("Publisher", "CompanyName");
         }
     }
 
     if let Some(version) = meta.get("ProductVersion").cloned() {
-        if !meta.contains_key("FileVersion") {
-            meta.insert("FileVersion".into(), version.clone());
-        }
-        if !meta.contains_key("FileVersionNumber") {
-            meta.insert("FileVersionNumber".into(), version.clone());
-        }
-        if !meta.contains_key("ProductVersionNumber") {
-            meta.insert("ProductVersionNumber".into(), version);
+        for alias in ["FileVersion", "FileVersionNumber", "ProductVersionNumber"] {
+            if !meta.contains_key(alias) {
+                meta.insert(alias.into(), version.clone());
+                provenance::derive(alias, "ProductVersion");
+            }
         }
     }
 
@@ -560,3 +664,69 @@ fn sanitize_string(s: &str) -> String {
         .trim()
         .to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A UDIF image whose koly property list holds one `blkx` table with
+    /// chunks of `chunk_types`.
+    fn udif_image(chunk_types: &[u32]) -> Vec<u8> {
+        let mut mish = MISH_SIGNATURE.to_vec();
+        mish.resize(MISH_CHUNK_COUNT_OFFSET, 0);
+        mish.extend((chunk_types.len() as u32).to_be_bytes());
+        for chunk_type in chunk_types {
+            mish.extend(chunk_type.to_be_bytes());
+            mish.resize(mish.len() + MISH_CHUNK_SIZE - 4, 0);
+        }
+        let xml = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<plist version=\"1.0\"><dict>\
+             <key>resource-fork</key><dict><key>blkx</key><array><dict>\
+             <key>Data</key><data>{}</data><key>Name</key><string>disk image</string>\
+             </dict></array></dict></dict></plist>",
+            crate::base64::encode_base64(&mish)
+        );
+        let mut image = vec![0u8; 64];
+        let xml_offset = image.len();
+        image.extend(xml.as_bytes());
+        let mut koly = vec![0u8; DMG_KOLY_OFFSET_SIZE];
+        koly[..4].copy_from_slice(DMG_KOLY_SIGNATURE);
+        koly[KOLY_XML_OFFSET..KOLY_XML_OFFSET + 8].copy_from_slice(&(xml_offset as u64).to_be_bytes());
+        koly[KOLY_XML_LENGTH..KOLY_XML_LENGTH + 8].copy_from_slice(&(xml.len() as u64).to_be_bytes());
+        image.extend(koly);
+        image
+    }
+
+    #[test]
+    fn test_blkx_compression() {
+        let ulfo = udif_image(&[0x7FFF_FFFE, 0x0000_0002, 0x8000_0007, 0xFFFF_FFFF]);
+        let meta = parse_dmg_metadata(&ulfo).unwrap_or_default();
+        assert_eq!(meta.get("Compression").map(String::as_str), Some("lzfse"));
+
+        let ulmo = udif_image(&[0x0000_0001, 0x8000_0008]);
+        assert_eq!(blkx_compression(&ulmo), Some("lzma"));
+        assert_eq!(blkx_compression(&udif_image(&[0x0000_0000, 0x0000_0001])), Some("uncompressed"));
+        assert_eq!(blkx_compression(&udif_image(&[])), None);
+    }
+
+    #[test]
+    fn test_detection_confidence() {
+        let mut image = udif_image(&[]);
+        assert!(!is_dmg_file(&image[..image.len() - 1]));
+        assert_eq!(DMGAnalyzer.detection_confidence(&image), Confidence::Medium);
+
+        let koly = image.len() - DMG_KOLY_OFFSET_SIZE;
+        image[koly + 4..koly + 8].copy_from_slice(&4u32.to_be_bytes());
+        image[koly + 8..koly + 12].copy_from_slice(&(DMG_KOLY_OFFSET_SIZE as u32).to_be_bytes());
+        assert!(is_dmg_file(&image));
+        assert_eq!(DMGAnalyzer.detection_confidence(&image), Confidence::High);
+    }
+
+    #[test]
+    fn test_char_window_keeps_char_boundaries() {
+        let s = "éé/App";
+        assert_eq!(char_window(s, 1, 6), "é/A");
+        assert_eq!(char_window(s, 3, 100), "/App");
+        assert_eq!(char_window(s, 1, 1), "");
+    }
+}
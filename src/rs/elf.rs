@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+use goblin::elf::{
+    dynamic::DF_1_PIE,
+    header::{self, EI_CLASS, ELFCLASS64, ELFMAG, ET_CORE, ET_DYN, ET_EXEC, ET_REL, SELFMAG},
+    note::NT_GNU_PROPERTY_TYPE_0,
+    program_header::{PF_W, PF_X, PT_GNU_RELRO, PT_LOAD},
+    Elf,
+};
+use crate::{Confidence, FileAnalyzer, FormatInfo, MetadataResult};
+
+/// UPX writes its `l_info` block, tagged `UPX!`, right after the program
+/// headers of the stub.
+const UPX_MAGIC: &[u8] = b"UPX!";
+const UPX_MAGIC_WINDOW: usize = 1024;
+/// The stub maps the compressed image and the decompression target as two
+/// `PT_LOAD` segments.
+const UPX_LOAD_SEGMENTS: usize = 2;
+
+/// `GNU_PROPERTY_X86_FEATURE_1_AND` in a `.note.gnu.property` note: the CET
+/// features every object linked in was built for.
+const GNU_PROPERTY_X86_FEATURE_1_AND: u32 = 0xC000_0002;
+const CET_FEATURES: [(u32, &str); 2] = [(0x1, "IBT"), (0x2, "SHSTK")];
+
+/// Called when a `-fstack-protector` canary check fails.
+const STACK_CHK_FAIL: &str = "__stack_chk_fail";
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "ELF",
+    mime_type: "application/x-elf",
+    extensions: &["so", "elf", "o"],
+};
+
+pub struct ELFAnalyzer;
+
+impl FileAnalyzer for ELFAnalyzer {
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        is_elf_file(data)
+    }
+
+    fn detection_confidence(&self, data: &[u8]) -> Confidence {
+        if Elf::parse(data).is_ok() {
+            Confidence::High
+        } else {
+            Confidence::Medium
+        }
+    }
+
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
+        let elf = Elf::parse(data).map_err(|e| format!("Failed to parse ELF file: {}", e))?;
+        let mut meta = parse_elf_metadata(&elf);
+        insert_hardening_fields(&elf, data, &mut meta);
+        meta.insert("Entropy".into(), format!("{:.2}", entropy(data)));
+        if is_upx_packed(&elf, data) {
+            meta.insert("Packer".into(), "UPX".into());
+        }
+        Ok(meta)
+    }
+
+    /// The section header table normally sits at the very end of the file.
+    fn expected_size(&self, data: &[u8]) -> Option<usize> {
+        let Ok(header) = Elf::parse_header(data) else {
+            let header_size = if data.get(EI_CLASS) == Some(&ELFCLASS64) {
+                header::header64::SIZEOF_EHDR
+            } else {
+                header::header32::SIZEOF_EHDR
+            };
+            return (data.len() < header_size).then_some(header_size);
+        };
+        let table_end = |offset: u64, count: u16, entry_size: u16| {
+            offset.saturating_add(u64::from(count) * u64::from(entry_size))
+        };
+        let end = table_end(header.e_phoff, header.e_phnum, header.e_phentsize)
+            .max(table_end(header.e_shoff, header.e_shnum, header.e_shentsize));
+        usize::try_from(end).ok()
+    }
+}
+
+pub fn is_elf_file(data: &[u8]) -> bool {
+    data.len() >= SELFMAG && &data[..SELFMAG] == ELFMAG
+}
+
+fn parse_elf_metadata(elf: &Elf) -> HashMap<String, String> {
+    let mut meta = HashMap::new();
+    meta.insert("Format".into(), "ELF".into());
+
+    meta.insert("Architecture".into(), header::machine_to_str(elf.header.e_machine).into());
+    let class = if elf.header.e_ident[EI_CLASS] == ELFCLASS64 { "ELF64" } else { "ELF32" };
+    meta.insert("Class".into(), class.into());
+    let endianness = if elf.little_endian { "Little" } else { "Big" };
+    meta.insert("Endianness".into(), endianness.into());
+    meta.insert("FileType".into(), filetype_name(elf.header.e_type).into());
+    meta.insert("EntryPoint".into(), format!("0x{:X}", elf.entry));
+
+    if let Some(interpreter) = elf.interpreter {
+        meta.insert("Interpreter".into(), interpreter.to_string());
+    }
+    if let Some(soname) = elf.soname {
+        meta.insert("SOName".into(), soname.to_string());
+    }
+    if !elf.libraries.is_empty() {
+        meta.insert("NeededLibraries".into(), elf.libraries.join(", "));
+    }
+
+    meta.insert("LibcFlavor".into(), libc_flavor(elf).into());
+
+    meta.insert("SectionCount".into(), elf.section_headers.len().to_string());
+    meta.insert("SegmentCount".into(), elf.program_headers.len().to_string());
+    // Writable and executable at once defeats W^X
+    let wx = elf.program_headers.iter().any(|ph| ph.p_flags & (PF_W | PF_X) == PF_W | PF_X);
+    meta.insert("HasWxSegment".into(), wx.to_string());
+
+    meta
+}
+
+/// `HasCET` (with the `CETFeatures` present), `HasRelro`, `HasStackProtector`
+/// and, for executables, `IsPIE`.
+fn insert_hardening_fields(elf: &Elf, data: &[u8], meta: &mut HashMap<String, String>) {
+    let features = x86_features(elf, data);
+    let cet: Vec<&str> = CET_FEATURES.iter().filter(|(bit, _)| features & bit != 0).map(|(_, name)| *name).collect();
+    meta.insert("HasCET".into(), (cet.len() == CET_FEATURES.len()).to_string());
+    if !cet.is_empty() {
+        meta.insert("CETFeatures".into(), cet.join(", "));
+    }
+
+    let relro = elf.program_headers.iter().any(|ph| ph.p_type == PT_GNU_RELRO);
+    meta.insert("HasRelro".into(), relro.to_string());
+
+    let stack_protector = elf.dynsyms.iter().any(|sym| elf.dynstrtab.get_at(sym.st_name) == Some(STACK_CHK_FAIL))
+        || elf.syms.iter().any(|sym| elf.strtab.get_at(sym.st_name) == Some(STACK_CHK_FAIL));
+    meta.insert("HasStackProtector".into(), stack_protector.to_string());
+
+    // A PIE is ET_DYN like a shared library, but has an interpreter or DF_1_PIE
+    let flags_1 = elf.dynamic.as_ref().map_or(0, |dynamic| dynamic.info.flags_1);
+    match elf.header.e_type {
+        ET_EXEC => {
+            meta.insert("IsPIE".into(), "false".into());
+        }
+        ET_DYN if elf.interpreter.is_some() || flags_1 & DF_1_PIE != 0 => {
+            meta.insert("IsPIE".into(), "true".into());
+        }
+        _ => {}
+    }
+}
+
+/// The `GNU_PROPERTY_X86_FEATURE_1_AND` bits of the GNU property note, from
+/// the note segments or, in relocatable objects, the note sections.
+fn x86_features(elf: &Elf, data: &[u8]) -> u32 {
+    let align = if elf.is_64 { 8 } else { 4 };
+    let notes = elf.iter_note_headers(data).or_else(|| elf.iter_note_sections(data, Some(".note.gnu.property")));
+    notes
+        .into_iter()
+        .flatten()
+        .map_while(Result::ok)
+        .filter(|note| note.n_type == NT_GNU_PROPERTY_TYPE_0 && note.name == "GNU")
+        .find_map(|note| {
+            let read = |at: usize| {
+                let bytes = note.desc.get(at..at.checked_add(4)?)?;
+                let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+                Some(if elf.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+            };
+            // Properties: type, data size, then the data padded to `align`
+            let mut at = 0usize;
+            while let (Some(pr_type), Some(size)) = (read(at), read(at.checked_add(4)?)) {
+                if pr_type == GNU_PROPERTY_X86_FEATURE_1_AND {
+                    return read(at + 8);
+                }
+                at = at.checked_add(8)?.checked_add((size as usize).checked_next_multiple_of(align)?)?;
+            }
+            None
+        })
+        .unwrap_or(0)
+}
+
+/// Shannon entropy of `data` in bits per byte; compressed or encrypted
+/// content is close to 8.
+fn entropy(data: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            p * (1.0 / p).log2()
+        })
+        .sum()
+}
+
+fn is_upx_packed(elf: &Elf, data: &[u8]) -> bool {
+    let head = &data[..data.len().min(UPX_MAGIC_WINDOW)];
+    let loads = elf.program_headers.iter().filter(|ph| ph.p_type == PT_LOAD).count();
+    loads == UPX_LOAD_SEGMENTS && head.windows(UPX_MAGIC.len()).any(|window| window == UPX_MAGIC)
+}
+
+fn filetype_name(e_type: u16) -> &'static str {
+    match e_type {
+        ET_EXEC => "Executable",
+        ET_DYN => "Shared Object",
+        ET_REL => "Relocatable",
+        ET_CORE => "Core",
+        other => header::et_to_str(other),
+    }
+}
+
+/// glibc binaries reference `GLIBC_*` symbol versions in the dynamic string table;
+/// musl has no symbol versioning but loads through `ld-musl-<arch>.so.1`.
+fn libc_flavor(elf: &Elf) -> &'static str {
+    let interpreter = elf.interpreter.unwrap_or_default();
+    if interpreter.contains("ld-musl") || elf.libraries.iter().any(|lib| lib.starts_with("libc.musl")) {
+        return "musl";
+    }
+
+    let has_glibc_versions = elf
+        .dynstrtab
+        .to_vec()
+        .map(|strings| strings.iter().any(|s| s.starts_with("GLIBC_")))
+        .unwrap_or(false);
+    if has_glibc_versions {
+        "glibc"
+    } else {
+        "unknown"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_elf_file() {
+        assert!(is_elf_file(b"\x7fELF\x02\x01\x01\x00"));
+        assert!(!is_elf_file(b"\x7fEL"));
+        assert!(!is_elf_file(b"MZ\x90\x00"));
+    }
+
+    /// An x86-64 executable with program headers for `segments` (type and
+    /// flags), each covering `tail`, and no section headers.
+    fn elf_image(segments: &[(u32, u32)], tail: &[u8]) -> Vec<u8> {
+        let mut data = elf_header(segments.len());
+        let tail_start = 64 + 56 * segments.len() as u64;
+        for &(p_type, flags) in segments {
+            data.extend(p_type.to_le_bytes());
+            data.extend(flags.to_le_bytes());
+            let size = tail.len() as u64;
+            for word in [tail_start, 0x40_0000, 0x40_0000, size, size, 8] {
+                data.extend(word.to_le_bytes());
+            }
+        }
+        data.extend(tail);
+        data
+    }
+
+    /// The 64-byte header of an x86-64 executable with `phnum` program
+    /// headers right after it.
+    fn elf_header(phnum: usize) -> Vec<u8> {
+        let mut data = b"\x7fELF\x02\x01\x01".to_vec();
+        data.resize(16, 0);
+        data.extend(2u16.to_le_bytes()); // ET_EXEC
+        data.extend(62u16.to_le_bytes()); // EM_X86_64
+        data.extend(1u32.to_le_bytes());
+        data.extend(0x40_1000u64.to_le_bytes());
+        data.extend(64u64.to_le_bytes()); // e_phoff
+        data.extend(0u64.to_le_bytes()); // e_shoff
+        data.extend(0u32.to_le_bytes());
+        for half in [64u16, 56, phnum as u16, 64, 0, 0] {
+            data.extend(half.to_le_bytes());
+        }
+        data
+    }
+
+    /// A dynamically linked executable loaded through `interpreter`, with a
+    /// DT_NEEDED entry per `needed` library and `versions` (e.g. `GLIBC_2.34`)
+    /// in its dynamic string table.
+    fn dynamic_image(interpreter: Option<&str>, needed: &[&str], versions: &[&str]) -> Vec<u8> {
+        use goblin::elf::dynamic::{DT_NEEDED, DT_NULL, DT_STRSZ, DT_STRTAB};
+        use goblin::elf::program_header::{PT_DYNAMIC, PT_INTERP};
+
+        const BASE: u64 = 0x40_0000;
+        let phnum = 2 + usize::from(interpreter.is_some());
+        let data_start = 64 + 56 * phnum;
+
+        let interp = interpreter.map(|path| format!("{}\0", path).into_bytes()).unwrap_or_default();
+        let mut strtab = vec![0u8];
+        let mut entries = Vec::new();
+        for name in needed.iter().chain(versions) {
+            if needed.contains(name) {
+                entries.push((DT_NEEDED, strtab.len() as u64));
+            }
+            strtab.extend(name.as_bytes());
+            strtab.push(0);
+        }
+        let strtab_start = data_start + interp.len();
+        let dynamic_start = strtab_start + strtab.len();
+        entries.push((DT_STRTAB, BASE + strtab_start as u64));
+        entries.push((DT_STRSZ, strtab.len() as u64));
+        entries.push((DT_NULL, 0));
+        let dynamic = entries.iter().flat_map(|&(tag, value)| [tag, value]).flat_map(u64::to_le_bytes).collect::<Vec<u8>>();
+        let total = (dynamic_start + dynamic.len()) as u64;
+
+        let mut data = elf_header(phnum);
+        let mut segment = |p_type: u32, flags: u32, offset: u64, size: u64| {
+            data.extend(p_type.to_le_bytes());
+            data.extend(flags.to_le_bytes());
+            for word in [offset, BASE + offset, BASE + offset, size, size, 8] {
+                data.extend(word.to_le_bytes());
+            }
+        };
+        if !interp.is_empty() {
+            segment(PT_INTERP, 4, data_start as u64, interp.len() as u64);
+        }
+        segment(PT_LOAD, 5, 0, total);
+        segment(PT_DYNAMIC, 6, dynamic_start as u64, dynamic.len() as u64);
+        data.extend(interp);
+        data.extend(strtab);
+        data.extend(dynamic);
+        data
+    }
+
+    #[test]
+    fn test_libc_flavor() {
+        let flavor = |data: Vec<u8>| {
+            let meta = ELFAnalyzer.parse_metadata(&data).unwrap_or_default();
+            meta.get("LibcFlavor").cloned().unwrap_or_default()
+        };
+        let glibc = dynamic_image(Some("/lib64/ld-linux-x86-64.so.2"), &["libc.so.6"], &["GLIBC_2.34"]);
+        assert_eq!(flavor(glibc), "glibc");
+        let musl = dynamic_image(Some("/lib/ld-musl-x86_64.so.1"), &["libc.so"], &[]);
+        assert_eq!(flavor(musl), "musl");
+        // musl's loader doubles as its libc, so some binaries only name it
+        assert_eq!(flavor(dynamic_image(None, &["libc.musl-x86_64.so.1"], &[])), "musl");
+
+        // Neither heuristic matches Android's bionic or a static binary
+        let bionic = dynamic_image(Some("/system/bin/linker64"), &["libc.so", "libdl.so"], &["LIBC"]);
+        assert_eq!(flavor(bionic), "unknown");
+        assert_eq!(flavor(elf_image(&[(PT_LOAD, 5)], &[])), "unknown");
+    }
+
+    #[test]
+    fn test_segment_summary() {
+        let meta = ELFAnalyzer.parse_metadata(&elf_image(&[(PT_LOAD, PF_W | PF_X | 4)], &[])).unwrap_or_default();
+        assert_eq!(meta.get("SectionCount").map(String::as_str), Some("0"));
+        assert_eq!(meta.get("SegmentCount").map(String::as_str), Some("1"));
+        assert_eq!(meta.get("HasWxSegment").map(String::as_str), Some("true"));
+
+        let meta = ELFAnalyzer.parse_metadata(&elf_image(&[(PT_LOAD, PF_X | 4)], &[])).unwrap_or_default();
+        assert_eq!(meta.get("HasWxSegment").map(String::as_str), Some("false"));
+        assert!(!meta.contains_key("Packer"));
+    }
+
+    #[test]
+    fn test_upx_packed() {
+        let mut tail = b"\0\0\0\0UPX!".to_vec();
+        tail.extend((0..=255u8).cycle().take(4096));
+        let data = elf_image(&[(PT_LOAD, PF_X | 4), (PT_LOAD, PF_W | 4)], &tail);
+        let meta = ELFAnalyzer.parse_metadata(&data).unwrap_or_default();
+        assert_eq!(meta.get("Packer").map(String::as_str), Some("UPX"));
+        let entropy: f64 = meta.get("Entropy").and_then(|e| e.parse().ok()).unwrap_or_default();
+        assert!(entropy > 7.5 && entropy <= 8.0, "{}", entropy);
+
+        assert_eq!(format!("{:.2}", super::entropy(&[0x41; 64])), "0.00");
+    }
+
+    #[test]
+    fn test_hardening_fields() {
+        // NT_GNU_PROPERTY_TYPE_0 "GNU" note: X86_FEATURE_1_AND = IBT | SHSTK
+        let mut note = [4u32, 16, 5].iter().flat_map(|word| word.to_le_bytes()).collect::<Vec<u8>>();
+        note.extend(b"GNU\0");
+        for word in [GNU_PROPERTY_X86_FEATURE_1_AND, 4, 3, 0] {
+            note.extend(word.to_le_bytes());
+        }
+        let data = elf_image(&[(goblin::elf::program_header::PT_NOTE, 4), (PT_GNU_RELRO, 4)], &note);
+        let meta = ELFAnalyzer.parse_metadata(&data).unwrap_or_default();
+        let get = |key: &str| meta.get(key).map(String::as_str);
+        assert_eq!(get("HasCET"), Some("true"));
+        assert_eq!(get("CETFeatures"), Some("IBT, SHSTK"));
+        assert_eq!(get("HasRelro"), Some("true"));
+        assert_eq!(get("HasStackProtector"), Some("false"));
+        assert_eq!(get("IsPIE"), Some("false"));
+
+        let meta = ELFAnalyzer.parse_metadata(&elf_image(&[(PT_LOAD, 5)], &[])).unwrap_or_default();
+        assert_eq!(meta.get("HasCET").map(String::as_str), Some("false"));
+        assert_eq!(meta.get("HasRelro").map(String::as_str), Some("false"));
+        assert!(!meta.contains_key("CETFeatures"));
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use crate::{FileAnalyzer, FormatInfo, MetadataResult};
+
+/// `flatpak build-bundle` adds a `flatpak` entry first to the bundle's
+/// metadata dictionary, which starts the file, as a readable header.
+const FLATPAK_MAGIC: &[u8] = b"flatpak\0";
+
+/// GVariant dictionary entries holding a variant are 8-byte aligned.
+const GVARIANT_ALIGNMENT: usize = 8;
+
+/// `metadata` key file entries and the metadata keys they map to.
+const KEY_FILE_KEYS: [(&str, &str); 4] = [
+    ("name", "AppId"),
+    ("runtime", "Runtime"),
+    ("sdk", "Sdk"),
+    ("command", "Command"),
+];
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "Flatpak",
+    mime_type: "application/vnd.flatpak",
+    extensions: &["flatpak"],
+};
+
+/// Single-file Flatpak bundles: an OSTree static delta superblock, serialized
+/// as a GVariant whose `a{sv}` metadata carries the ref and the app's
+/// `metadata` key file.
+pub struct FlatpakAnalyzer;
+
+impl FileAnalyzer for FlatpakAnalyzer {
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        is_flatpak_file(data)
+    }
+
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
+        parse_flatpak_metadata(data)
+    }
+}
+
+pub fn is_flatpak_file(data: &[u8]) -> bool {
+    data.starts_with(FLATPAK_MAGIC)
+}
+
+/// Width of the framing offsets in a serialized GVariant container of `size`
+/// bytes.
+fn offset_size(size: usize) -> usize {
+    match size {
+        0 => 0,
+        1..=0xFF => 1,
+        0x100..=0xFFFF => 2,
+        0x1_0000..=0xFFFF_FFFF => 4,
+        _ => 8,
+    }
+}
+
+fn read_offset(data: &[u8], at: usize, width: usize) -> Option<usize> {
+    let bytes = data.get(at..at.checked_add(width)?)?;
+    let value = bytes.iter().rev().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+    usize::try_from(value).ok()
+}
+
+fn align(offset: usize) -> usize {
+    offset.div_ceil(GVARIANT_ALIGNMENT) * GVARIANT_ALIGNMENT
+}
+
+/// The first member of a serialized tuple, whose end is the tuple's last
+/// framing offset (they are stored in reverse order).
+fn first_tuple_member(data: &[u8]) -> Option<&[u8]> {
+    let width = offset_size(data.len());
+    let end = read_offset(data, data.len().checked_sub(width)?, width)?;
+    data.get(..end)
+}
+
+/// Key and string value of an `{sv}` dictionary entry; non-string values are
+/// skipped.
+fn string_entry(entry: &[u8]) -> Option<(String, String)> {
+    let width = offset_size(entry.len());
+    let value_end = entry.len().checked_sub(width)?;
+    let key_end = read_offset(entry, value_end, width)?;
+    let key = entry.get(..key_end)?.strip_suffix(b"\0")?;
+    let variant = entry.get(align(key_end)..value_end)?;
+    let type_start = variant.iter().rposition(|&b| b == 0)?;
+    if &variant[type_start + 1..] != b"s" {
+        return None;
+    }
+    let value = variant[..type_start].strip_suffix(b"\0")?;
+    Some((String::from_utf8_lossy(key).into_owned(), String::from_utf8_lossy(value).into_owned()))
+}
+
+/// String entries of the superblock's metadata dictionary: an array of
+/// variable-sized, 8-byte aligned `{sv}` entries.
+fn bundle_metadata(data: &[u8]) -> Option<HashMap<String, String>> {
+    let dict = first_tuple_member(data)?;
+    let width = offset_size(dict.len());
+    if width == 0 {
+        return Some(HashMap::new());
+    }
+    let table_start = read_offset(dict, dict.len() - width, width)?;
+    let table = dict.get(table_start..)?;
+    if table.len() % width != 0 {
+        return None;
+    }
+    let mut entries = HashMap::new();
+    let mut start = 0;
+    for i in 0..table.len() / width {
+        let end = read_offset(table, i * width, width)?;
+        entries.extend(string_entry(dict.get(start..end)?));
+        start = align(end);
+    }
+    Some(entries)
+}
+
+/// `app/org.gnome.Maps/x86_64/stable` into kind, id, arch and branch.
+fn insert_ref_fields(flatpak_ref: &str, meta: &mut HashMap<String, String>) {
+    let parts: Vec<&str> = flatpak_ref.split('/').collect();
+    let [kind, id, arch, branch] = parts[..] else {
+        return;
+    };
+    meta.insert("BundleKind".into(), kind.into());
+    meta.insert("AppId".into(), id.into());
+    meta.insert("Architecture".into(), arch.into());
+    meta.insert("Branch".into(), branch.into());
+}
+
+/// The `[Application]` (or `[Runtime]`) group of the `metadata` key file.
+fn parse_key_file(key_file: &str, meta: &mut HashMap<String, String>) {
+    let mut in_main_group = false;
+    for line in key_file.lines().map(str::trim) {
+        if let Some(group) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_main_group = group == "Application" || group == "Runtime";
+            continue;
+        }
+        if !in_main_group {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if let Some((_, field)) = KEY_FILE_KEYS.iter().find(|(name, _)| *name == key.trim()) {
+            meta.entry(field.to_string()).or_insert_with(|| value.trim().to_string());
+        }
+    }
+}
+
+fn parse_flatpak_metadata(data: &[u8]) -> MetadataResult {
+    if !is_flatpak_file(data) {
+        return Err("Invalid Flatpak bundle header".to_string());
+    }
+    let mut meta = HashMap::new();
+    meta.insert("Format".into(), "Flatpak".into());
+
+    // Truncated bundles lose the framing offsets at the end of the file
+    let Some(bundle) = bundle_metadata(data) else {
+        return Ok(meta);
+    };
+    if let Some(flatpak_ref) = bundle.get("ref") {
+        meta.insert("Ref".into(), flatpak_ref.clone());
+        insert_ref_fields(flatpak_ref, &mut meta);
+    }
+    if let Some(key_file) = bundle.get("metadata") {
+        parse_key_file(key_file, &mut meta);
+    }
+    for (key, field) in [("origin", "Origin"), ("runtime-repo", "RuntimeRepo")] {
+        if let Some(value) = bundle.get(key).filter(|value| !value.is_empty()) {
+            meta.insert(field.into(), value.clone());
+        }
+    }
+    Ok(meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `body` followed by little-endian framing `offsets` sized for the whole.
+    fn framed(mut body: Vec<u8>, offsets: &[usize]) -> Vec<u8> {
+        let width = [1, 2, 4]
+            .into_iter()
+            .find(|&width| offset_size(body.len() + offsets.len() * width) == width)
+            .unwrap_or(8);
+        for offset in offsets {
+            body.extend(&offset.to_le_bytes()[..width]);
+        }
+        body
+    }
+
+    fn string_variant_entry(key: &str, value: &str) -> Vec<u8> {
+        let mut entry = [key.as_bytes(), b"\0"].concat();
+        let key_end = entry.len();
+        entry.resize(align(key_end), 0);
+        entry.extend([value.as_bytes(), b"\0\0s"].concat());
+        framed(entry, &[key_end])
+    }
+
+    /// A bundle whose superblock holds `entries` and a placeholder for the
+    /// remaining (unparsed) members.
+    fn bundle(entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut dict = Vec::new();
+        let mut ends = Vec::new();
+        for entry in entries {
+            dict.resize(align(dict.len()), 0);
+            dict.extend(entry);
+            ends.push(dict.len());
+        }
+        let dict = framed(dict, &ends);
+        let dict_end = dict.len();
+        let mut superblock = dict;
+        superblock.resize(align(dict_end) + 8, 0); // t: timestamp
+        framed(superblock, &[dict_end])
+    }
+
+    #[test]
+    fn test_flatpak_metadata() {
+        let entries = [
+            framed([b"flatpak\0".as_slice(), &0xe589_0001u32.to_le_bytes(), b"\0u"].concat(), &[8]),
+            string_variant_entry("ref", "app/org.gnome.Maps/x86_64/stable"),
+            string_variant_entry(
+                "metadata",
+                "[Application]\nname=org.gnome.Maps\nruntime=org.gnome.Platform/x86_64/45\n\
+                 sdk=org.gnome.Sdk/x86_64/45\ncommand=gnome-maps\n\n[Context]\nshared=network;ipc;\n",
+            ),
+            string_variant_entry("runtime-repo", "https://dl.flathub.org/repo/flathub.flatpakrepo"),
+        ];
+        let data = bundle(&entries);
+        assert!(FlatpakAnalyzer.sniff(&data));
+
+        let meta = FlatpakAnalyzer.parse_metadata(&data).unwrap_or_default();
+        let get = |key: &str| meta.get(key).map(String::as_str);
+        assert_eq!(get("AppId"), Some("org.gnome.Maps"));
+        assert_eq!(get("BundleKind"), Some("app"));
+        assert_eq!(get("Architecture"), Some("x86_64"));
+        assert_eq!(get("Branch"), Some("stable"));
+        assert_eq!(get("Runtime"), Some("org.gnome.Platform/x86_64/45"));
+        assert_eq!(get("Sdk"), Some("org.gnome.Sdk/x86_64/45"));
+        assert_eq!(get("Command"), Some("gnome-maps"));
+        assert_eq!(get("RuntimeRepo"), Some("https://dl.flathub.org/repo/flathub.flatpakrepo"));
+
+        // A truncated bundle is still identified
+        let meta = FlatpakAnalyzer.parse_metadata(&data[..64]).unwrap_or_default();
+        assert_eq!(meta.get("Format").map(String::as_str), Some("Flatpak"));
+    }
+}
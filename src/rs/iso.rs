@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use crate::{Confidence, FileAnalyzer, FormatInfo, MetadataResult};
+
+// Volume descriptors start after the 32 KiB system area, one per 2048-byte sector.
+const ISO_DESCRIPTOR_START: usize = 0x8000;
+const ISO_SECTOR_SIZE: usize = 2048;
+const ISO_STANDARD_ID: &[u8] = b"CD001";
+const ISO_MAX_DESCRIPTORS: usize = 16;
+
+const DESCRIPTOR_PRIMARY: u8 = 1;
+const DESCRIPTOR_TERMINATOR: u8 = 255;
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "ISO9660",
+    mime_type: "application/x-iso9660-image",
+    extensions: &["iso"],
+};
+
+pub struct ISOAnalyzer;
+
+impl FileAnalyzer for ISOAnalyzer {
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        is_iso_file(data)
+    }
+
+    fn detection_confidence(&self, data: &[u8]) -> Confidence {
+        if find_primary_descriptor(data).is_some() {
+            Confidence::High
+        } else {
+            Confidence::Medium
+        }
+    }
+
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
+        parse_iso_metadata(data)
+    }
+
+    /// The system area and the volume descriptor set.
+    fn metadata_len(&self, _head: &[u8]) -> Option<usize> {
+        Some(ISO_DESCRIPTOR_START + ISO_MAX_DESCRIPTORS * ISO_SECTOR_SIZE)
+    }
+
+    fn expected_size(&self, data: &[u8]) -> Option<usize> {
+        match find_primary_descriptor(data) {
+            Some(pvd) => {
+                let volume_size = read_both_endian_u32(pvd, 80) as u64 * read_both_endian_u16(pvd, 128) as u64;
+                usize::try_from(volume_size).ok()
+            }
+            None => {
+                let first_descriptor_end = ISO_DESCRIPTOR_START + ISO_SECTOR_SIZE;
+                (data.len() < first_descriptor_end).then_some(first_descriptor_end)
+            }
+        }
+    }
+}
+
+pub fn is_iso_file(data: &[u8]) -> bool {
+    data.get(ISO_DESCRIPTOR_START + 1..ISO_DESCRIPTOR_START + 6) == Some(ISO_STANDARD_ID)
+}
+
+/// Walks the volume descriptor set up to the terminator and returns the
+/// primary volume descriptor sector.
+fn find_primary_descriptor(data: &[u8]) -> Option<&[u8]> {
+    (0..ISO_MAX_DESCRIPTORS)
+        .map(|idx| ISO_DESCRIPTOR_START + idx * ISO_SECTOR_SIZE)
+        .map_while(|start| data.get(start..start + ISO_SECTOR_SIZE))
+        .take_while(|sector| &sector[1..6] == ISO_STANDARD_ID && sector[0] != DESCRIPTOR_TERMINATOR)
+        .find(|sector| sector[0] == DESCRIPTOR_PRIMARY)
+}
+
+/// Descriptor text fields are fixed-width and space-padded.
+fn read_text(sector: &[u8], offset: usize, len: usize) -> Option<String> {
+    let text = String::from_utf8_lossy(&sector[offset..offset + len]).trim().to_string();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// Numeric fields are stored "both-endian"; the little-endian half comes first.
+fn read_both_endian_u32(sector: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([sector[offset], sector[offset + 1], sector[offset + 2], sector[offset + 3]])
+}
+
+fn read_both_endian_u16(sector: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([sector[offset], sector[offset + 1]])
+}
+
+/// Formats a 17-byte `YYYYMMDDHHMMSScc` + timezone timestamp as `YYYY-MM-DD HH:MM:SS`.
+fn read_datetime(sector: &[u8], offset: usize) -> Option<String> {
+    let digits = std::str::from_utf8(&sector[offset..offset + 14]).ok()?;
+    if !digits.bytes().all(|b| b.is_ascii_digit()) || digits.bytes().all(|b| b == b'0') {
+        return None;
+    }
+    Some(format!(
+        "{}-{}-{} {}:{}:{}",
+        &digits[0..4], &digits[4..6], &digits[6..8], &digits[8..10], &digits[10..12], &digits[12..14]
+    ))
+}
+
+fn parse_iso_metadata(data: &[u8]) -> MetadataResult {
+    let pvd = find_primary_descriptor(data)
+        .ok_or("ISO 9660 image has no primary volume descriptor")?;
+
+    let mut meta = HashMap::new();
+    meta.insert("Format".into(), "ISO9660".into());
+
+    let text_fields = [
+        (8, 32, "SystemIdentifier"),
+        (40, 32, "VolumeIdentifier"),
+        (190, 128, "VolumeSetIdentifier"),
+        (318, 128, "PublisherIdentifier"),
+        (446, 128, "DataPreparerIdentifier"),
+        (574, 128, "ApplicationIdentifier"),
+    ];
+    for (offset, len, key) in text_fields {
+        if let Some(value) = read_text(pvd, offset, len) {
+            meta.insert(key.into(), value);
+        }
+    }
+
+    let block_size = read_both_endian_u16(pvd, 128);
+    let space_size = read_both_endian_u32(pvd, 80);
+    meta.insert("VolumeSpaceSize".into(), space_size.to_string());
+    meta.insert("LogicalBlockSize".into(), block_size.to_string());
+    meta.insert("VolumeSize".into(), (space_size as u64 * block_size as u64).to_string());
+
+    if let Some(created) = read_datetime(pvd, 813) {
+        meta.insert("CreationDate".into(), created);
+    }
+
+    // Product aliases (compatible with PE fields)
+    if let Some(volume) = meta.get("VolumeIdentifier").cloned() {
+        meta.insert("ProductName".into(), volume);
+    }
+    if let Some(publisher) = meta.get("PublisherIdentifier").cloned() {
+        meta.insert("CompanyName".into(), publisher);
+    }
+
+    Ok(meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_iso_metadata() {
+        let mut image = vec![0u8; ISO_DESCRIPTOR_START + 2 * ISO_SECTOR_SIZE];
+        let pvd = &mut image[ISO_DESCRIPTOR_START..ISO_DESCRIPTOR_START + ISO_SECTOR_SIZE];
+        pvd[0] = DESCRIPTOR_PRIMARY;
+        pvd[1..6].copy_from_slice(ISO_STANDARD_ID);
+        pvd[40..72].copy_from_slice(b"UBUNTU_24_04                    ");
+        pvd[80..84].copy_from_slice(&1000u32.to_le_bytes());
+        pvd[128..130].copy_from_slice(&2048u16.to_le_bytes());
+        let term = &mut image[ISO_DESCRIPTOR_START + ISO_SECTOR_SIZE..];
+        term[0] = DESCRIPTOR_TERMINATOR;
+        term[1..6].copy_from_slice(ISO_STANDARD_ID);
+
+        assert!(is_iso_file(&image));
+        let meta = parse_iso_metadata(&image).unwrap_or_default();
+        assert_eq!(meta.get("VolumeIdentifier").map(String::as_str), Some("UBUNTU_24_04"));
+        assert_eq!(meta.get("VolumeSpaceSize").map(String::as_str), Some("1000"));
+        assert_eq!(meta.get("VolumeSize").map(String::as_str), Some("2048000"));
+        assert!(!meta.contains_key("CreationDate"));
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use crate::{FileAnalyzer, FormatInfo, MetadataResult};
+
+const CLASS_MAGIC: [u8; 4] = [0xCA, 0xFE, 0xBA, 0xBE];
+const CLASS_HEADER_SIZE: usize = 8;
+/// JDK 1.0.2 / 1.1 class files; anything lower is not a class file.
+const MIN_MAJOR_VERSION: u16 = 45;
+/// Minor version marking a class compiled with `--enable-preview`.
+const PREVIEW_MINOR_VERSION: u16 = 0xFFFF;
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "JavaClass",
+    mime_type: "application/java-vm",
+    extensions: &["class"],
+};
+
+pub struct JavaClassAnalyzer;
+
+impl FileAnalyzer for JavaClassAnalyzer {
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        is_java_class_file(data)
+    }
+
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
+        parse_class_metadata(data)
+    }
+}
+
+/// `CA FE BA BE` is shared with Mach-O fat binaries, where the next word is a
+/// small architecture count; class files carry `minor_version`/`major_version` there.
+pub fn is_java_class_file(data: &[u8]) -> bool {
+    data.len() >= CLASS_HEADER_SIZE
+        && data[0..4] == CLASS_MAGIC
+        && u16::from_be_bytes([data[6], data[7]]) >= MIN_MAJOR_VERSION
+}
+
+fn java_version(major: u16) -> String {
+    match major {
+        45 => "1.1".to_string(),
+        46 => "1.2".to_string(),
+        47 => "1.3".to_string(),
+        48 => "1.4".to_string(),
+        _ => (major - 44).to_string(),
+    }
+}
+
+fn parse_class_metadata(data: &[u8]) -> MetadataResult {
+    if !is_java_class_file(data) {
+        return Err("Not a valid Java class file".to_string());
+    }
+
+    let minor = u16::from_be_bytes([data[4], data[5]]);
+    let major = u16::from_be_bytes([data[6], data[7]]);
+
+    let mut meta = HashMap::new();
+    meta.insert("Format".into(), "JavaClass".into());
+    meta.insert("MajorVersion".into(), major.to_string());
+    meta.insert("MinorVersion".into(), minor.to_string());
+    meta.insert("JavaVersion".into(), java_version(major));
+    if minor == PREVIEW_MINOR_VERSION {
+        meta.insert("PreviewFeatures".into(), "true".into());
+    }
+
+    Ok(meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_java_class_vs_fat_header() {
+        // Java 8 class file: minor 0, major 52
+        let class = [0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x34];
+        // Mach-O universal binary with two slices
+        let fat = [0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x02];
+
+        assert!(is_java_class_file(&class));
+        assert!(!is_java_class_file(&fat));
+
+        let meta = parse_class_metadata(&class).unwrap_or_default();
+        assert_eq!(meta.get("JavaVersion").map(String::as_str), Some("8"));
+        assert_eq!(java_version(48), "1.4");
+        assert_eq!(java_version(65), "21");
+    }
+}
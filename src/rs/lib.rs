@@ -1,94 +1,1112 @@
+#[cfg(feature = "msi")]
 mod msi;
+#[cfg(feature = "msi")]
+mod compound;
+#[cfg(feature = "pe")]
 mod pe;
+#[cfg(feature = "pe")]
+mod authenticode;
+#[cfg(feature = "dmg")]
 mod dmg;
+#[cfg(feature = "deb")]
 mod deb;
+#[cfg(feature = "rpm")]
 mod rpm;
+#[cfg(feature = "snap")]
+mod snap;
+#[cfg(feature = "flatpak")]
+mod flatpak;
+#[cfg(feature = "cpio")]
+mod cpio;
+#[cfg(feature = "iso")]
+mod iso;
+#[cfg(feature = "elf")]
+mod elf;
+#[cfg(feature = "lnk")]
+mod lnk;
+#[cfg(feature = "msu")]
+mod msu;
+#[cfg(feature = "appinstaller")]
+mod appinstaller;
+#[cfg(feature = "java")]
+mod javaclass;
+#[cfg(feature = "macho")]
+mod macho;
+#[cfg(feature = "wasm-module")]
+mod wasm_mod;
+mod base64;
+#[cfg(feature = "wasm")]
+mod batch;
+mod budget;
+mod diff;
+mod digest;
+mod locale;
+mod options;
+mod progress;
+mod provenance;
+mod reader;
+mod result;
+mod schema;
+mod strings;
+mod version;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(any(feature = "pe", feature = "appinstaller"))]
+mod xml;
 
-use goblin::Object;
-use std::collections::HashMap;
-use wasm_bindgen::prelude::*;
+pub use diff::{diff, DiffKind, FieldDiff, VersionChange};
+pub use digest::Hasher;
+pub use options::AnalysisOptions;
+pub use progress::Callback as ProgressCallback;
+pub use reader::{analyze_path, analyze_reader, ReadSeek};
+pub use result::{AnalysisResult, AnalyzeError};
+pub use schema::output_schema;
+pub use version::{compare_versions, Version, VersionScheme};
+#[cfg(feature = "msi")]
+pub use msi::msi_properties;
+
+use std::collections::{BTreeMap, HashMap};
 
 // Type alias to reduce complexity and improve readability
 pub type MetadataResult = Result<HashMap<String, String>, String>;
 
+/// Serializes a metadata map as a JSON object with its keys sorted, so the
+/// same input always produces byte-identical output.
+pub fn metadata_to_json(meta: &HashMap<String, String>) -> String {
+    let sorted: BTreeMap<&String, &String> = meta.iter().collect();
+    serde_json::to_string(&sorted).unwrap_or_else(|_| "{}".to_string())
+}
+
+/// Static description of a file format, shared by detection output and `supported_formats`.
+#[derive(Debug)]
+pub struct FormatInfo {
+    pub name: &'static str,
+    /// MIME type derived from the detected format rather than the uploaded filename.
+    pub mime_type: &'static str,
+    /// Known file extensions (without the leading dot), most common first.
+    pub extensions: &'static [&'static str],
+}
+
+#[cfg(any(feature = "pe", feature = "elf", feature = "macho"))]
+const UNSUPPORTED_FORMAT: FormatInfo = FormatInfo {
+    name: "Unsupported",
+    mime_type: "application/octet-stream",
+    extensions: &["bin"],
+};
+
+const INVALID_FORMAT: FormatInfo = FormatInfo {
+    name: "Invalid binary",
+    mime_type: "application/octet-stream",
+    extensions: &["bin"],
+};
+
+/// How strongly the bytes matched the detected format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confidence {
+    High,
+    Medium,
+    Low,
+}
+
+impl Confidence {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Confidence::High => "High",
+            Confidence::Medium => "Medium",
+            Confidence::Low => "Low",
+        }
+    }
+}
+
 pub trait FileAnalyzer {
-    fn get_file_info(_data: &[u8]) -> HashMap<String, String>;
-    fn parse_metadata(data: &[u8]) -> MetadataResult;
+    fn format(&self) -> &'static FormatInfo;
+
+    /// Cheap check for whether `data` is in this analyzer's format.
+    fn sniff(&self, data: &[u8]) -> bool;
+
+    /// Variant of `sniff` used by `analyze_header`, where `data` may be truncated.
+    fn sniff_header(&self, data: &[u8]) -> bool {
+        self.sniff(data)
+    }
+
+    /// Structural evidence beyond the magic bytes is High, magic bytes alone are Medium.
+    fn detection_confidence(&self, _data: &[u8]) -> Confidence {
+        Confidence::High
+    }
+
+    fn get_file_info(&self, _data: &[u8]) -> HashMap<String, String> {
+        let mut info = HashMap::new();
+        info.insert("Format".to_string(), self.format().name.to_string());
+        info
+    }
+
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult;
+
+    /// Offsets of complete files embedded in `data` (e.g. an MSI inside a PE
+    /// bootstrapper) that are worth analyzing on their own.
+    fn embedded_offsets(&self, _data: &[u8]) -> Vec<usize> {
+        Vec::new()
+    }
+
+    /// The embedded document the analyzer reads its fields from (an
+    /// Info.plist, an application manifest), exactly as stored, for
+    /// `RawManifest`.
+    fn raw_manifest(&self, _data: &[u8]) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Paths of the first `limit` files in a container format's file tree,
+    /// in archive order, for `FileList`.
+    fn file_list(&self, _data: &[u8], _limit: usize) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Whether `file_list` paths are absolute install locations, so a leading
+    /// `/` does not escape the install root.
+    fn absolute_file_paths(&self) -> bool {
+        false
+    }
+
+    /// Bytes the format's own headers say the file occupies, when they can be
+    /// read. A value past the end of `data` marks a truncated upload.
+    fn expected_size(&self, _data: &[u8]) -> Option<usize> {
+        None
+    }
+
+    /// Leading bytes of the file that hold everything `parse_metadata` reads,
+    /// judged from `head`, so `analyze_reader` can load just those. A value
+    /// past the end of `head` asks again with that much; `None` means the
+    /// whole file is needed.
+    fn metadata_len(&self, _head: &[u8]) -> Option<usize> {
+        None
+    }
+
+    /// Start of a payload that runs to the end of the file and whose length
+    /// `parse_metadata` reports as `CompressedSize`, so it can be measured
+    /// against the whole file when only a prefix was analyzed.
+    fn payload_offset(&self, _data: &[u8]) -> Option<usize> {
+        None
+    }
+
+    /// Parses a prefix of the file. Analyzers whose trailer-independent fields
+    /// survive truncation can override this with a more lenient path.
+    fn parse_header(&self, data: &[u8]) -> MetadataResult {
+        self.parse_metadata(data)
+    }
+}
+
+/// Every analyzer compiled into this build, in detection order: cheap and
+/// unambiguous signatures first, the goblin-based PE parse last.
+fn analyzers() -> Vec<Box<dyn FileAnalyzer>> {
+    vec![
+        #[cfg(feature = "msi")]
+        Box::new(msi::MSIAnalyzer),
+        #[cfg(feature = "msi")]
+        Box::new(compound::CompoundFileAnalyzer),
+        #[cfg(feature = "dmg")]
+        Box::new(dmg::DMGAnalyzer),
+        #[cfg(feature = "deb")]
+        Box::new(deb::DEBAnalyzer),
+        #[cfg(feature = "rpm")]
+        Box::new(rpm::RPMAnalyzer),
+        #[cfg(feature = "snap")]
+        Box::new(snap::SnapAnalyzer),
+        #[cfg(feature = "flatpak")]
+        Box::new(flatpak::FlatpakAnalyzer),
+        #[cfg(feature = "cpio")]
+        Box::new(cpio::CPIOAnalyzer),
+        #[cfg(feature = "iso")]
+        Box::new(iso::ISOAnalyzer),
+        #[cfg(feature = "lnk")]
+        Box::new(lnk::LNKAnalyzer),
+        #[cfg(feature = "msu")]
+        Box::new(msu::MSUAnalyzer),
+        #[cfg(feature = "appinstaller")]
+        Box::new(appinstaller::AppInstallerAnalyzer),
+        #[cfg(feature = "elf")]
+        Box::new(elf::ELFAnalyzer),
+        #[cfg(feature = "wasm-module")]
+        Box::new(wasm_mod::WasmAnalyzer),
+        #[cfg(feature = "java")]
+        Box::new(javaclass::JavaClassAnalyzer),
+        #[cfg(feature = "macho")]
+        Box::new(macho::MachOAnalyzer),
+        #[cfg(feature = "pe")]
+        Box::new(pe::PEAnalyzer),
+    ]
+}
+
+/// Single source of truth for format detection, shared by every entry point.
+fn detect(data: &[u8]) -> Option<Box<dyn FileAnalyzer>> {
+    analyzers().into_iter().find(|analyzer| analyzer.sniff(data))
+}
+
+/// The size `analyzer` expects `data`, the start of a `file_len`-byte file,
+/// to have, when that is more than the file has.
+fn truncated_size(analyzer: &dyn FileAnalyzer, data: &[u8], file_len: usize) -> Option<usize> {
+    analyzer.expected_size(data).filter(|&size| size > file_len)
+}
+
+/// The analyzer whose header matches a file too short for its own `sniff`.
+fn detect_truncated(data: &[u8], file_len: usize) -> Option<Box<dyn FileAnalyzer>> {
+    analyzers()
+        .into_iter()
+        .find(|analyzer| analyzer.sniff_header(data) && truncated_size(analyzer.as_ref(), data, file_len).is_some())
+}
+
+/// Distinguishes binaries goblin understands but we don't analyze from garbage.
+#[cfg_attr(not(any(feature = "pe", feature = "elf", feature = "macho")), allow(unused_variables))]
+fn unknown_format(data: &[u8]) -> &'static FormatInfo {
+    #[cfg(any(feature = "pe", feature = "elf", feature = "macho"))]
+    if goblin::Object::parse(data).is_ok() {
+        return &UNSUPPORTED_FORMAT;
+    }
+    &INVALID_FORMAT
+}
+
+/// Shortest input any analyzer can recognize. Entry points reject anything
+/// smaller up front with `too_small_error`.
+const MIN_FILE_SIZE: usize = 4;
+
+/// Share of a deep-scan analysis' progress given to the format's own parse;
+/// the deep scan makes nine passes over the data to its one or two.
+const DEEP_SCAN_PARSE_SHARE: f64 = 0.2;
+/// Share of the deep scan given to hashing the file for `Sha256`, one cheap
+/// pass next to the string extractors' several.
+const DEEP_SCAN_HASH_SHARE: f64 = 0.1;
+/// Paths reported in `FileList`, so packages with tens of thousands of files
+/// stay a reasonable size.
+const MAX_FILE_LIST: usize = 200;
+
+fn too_small_error(len: usize) -> String {
+    format!("Empty or too small file ({} bytes)", len)
+}
+
+fn unsupported_format_error() -> String {
+    let names: Vec<&str> = analyzers().iter().map(|a| a.format().name).collect();
+    format!("Unsupported file format. Supported formats: {}.", names.join(", "))
 }
 
-#[wasm_bindgen(start)]
-pub fn init_panic_hook() {
-    console_error_panic_hook::set_once();
+fn insert_truncation_info(expected: usize, actual: usize, meta: &mut HashMap<String, String>) {
+    meta.insert("Truncated".to_string(), "true".to_string());
+    meta.insert("ExpectedSize".to_string(), expected.to_string());
+    meta.insert("ActualSize".to_string(), actual.to_string());
+}
+
+/// Result for a recognized file cut off before its structures could be parsed.
+fn truncated_metadata(format: &FormatInfo, expected: usize, actual: usize) -> HashMap<String, String> {
+    let mut meta = HashMap::new();
+    meta.insert("Format".to_string(), format.name.to_string());
+    insert_truncation_info(expected, actual, &mut meta);
+    insert_detection_info(format, Confidence::Low, &mut meta);
+    meta
+}
+
+fn insert_detection_info(
+    format: &FormatInfo,
+    confidence: Confidence,
+    meta: &mut HashMap<String, String>,
+) {
+    meta.insert("MimeType".to_string(), format.mime_type.to_string());
+    meta.insert("Extension".to_string(), format.extensions[0].to_string());
+    meta.insert("DetectionConfidence".to_string(), confidence.as_str().to_string());
+}
+
+/// (format it applies to, or "" for any format; canonical key; source key)
+type FieldSource = (&'static str, &'static str, &'static str);
+
+/// Fallback sources for the canonical `ProductName`/`ProductVersion`/`Publisher`
+/// trio, in order of preference.
+const PRODUCT_FIELD_SOURCES: [FieldSource; 12] = [
+    ("", "ProductName", "DisplayName"),
+    ("DEB", "ProductName", "Package"),
+    ("", "ProductVersion", "ProductVersionNumber"),
+    ("", "ProductVersion", "FileVersion"),
+    ("DEB", "ProductVersion", "Version"),
+    ("Snap", "ProductName", "Name"),
+    ("Snap", "ProductVersion", "Version"),
+    ("Flatpak", "ProductName", "AppId"),
+    ("", "Publisher", "Manufacturer"),
+    ("", "Publisher", "CompanyName"),
+    ("", "Publisher", "Vendor"),
+    ("DEB", "Publisher", "Maintainer"),
+];
+
+/// Fills in whichever of `ProductName`, `ProductVersion` and `Publisher` the
+/// analyzer left unset, so consumers need not know each format's own keys.
+fn insert_product_fields(format: &FormatInfo, meta: &mut HashMap<String, String>) {
+    for (source_format, canonical, source) in PRODUCT_FIELD_SOURCES {
+        if meta.contains_key(canonical) || !(source_format.is_empty() || source_format == format.name) {
+            continue;
+        }
+        if let Some(value) = meta.get(source).filter(|value| !value.is_empty()).cloned() {
+            meta.insert(canonical.to_string(), value);
+            provenance::derive(canonical, source);
+        }
+    }
 }
 
-fn parse_metadata(buf: &[u8]) -> MetadataResult {
-    if msi::is_msi_file(buf) {
-        return msi::MSIAnalyzer::parse_metadata(buf);
+/// High-level `Category` facet per format name. A PE is a `Windows Installer`
+/// instead when the analyzer flagged it with `IsInstaller`.
+const FORMAT_CATEGORIES: [(&str, &str); 17] = [
+    ("PE", "Executable"),
+    ("MSI", "Windows Installer"),
+    ("CompoundFile", "Document"),
+    ("DMG", "macOS Installer"),
+    ("DEB", "Linux Package"),
+    ("RPM", "Linux Package"),
+    ("Snap", "Linux Package"),
+    ("Flatpak", "Linux Package"),
+    ("CPIO", "Archive"),
+    ("ISO9660", "Archive"),
+    ("LNK", "Shortcut"),
+    ("MSU", "Windows Installer"),
+    ("AppInstaller", "Windows Installer"),
+    ("ELF", "Executable"),
+    ("WebAssembly", "Executable"),
+    ("JavaClass", "Executable"),
+    ("Mach-O", "Executable"),
+];
+
+fn insert_category(format: &FormatInfo, meta: &mut HashMap<String, String>) {
+    let category = if meta.get("IsInstaller").map(String::as_str) == Some("true") {
+        Some("Windows Installer")
+    } else {
+        FORMAT_CATEGORIES.iter().find(|(name, _)| *name == format.name).map(|(_, category)| *category)
+    };
+    if let Some(category) = category {
+        meta.insert("Category".to_string(), category.to_string());
     }
+}
 
+/// Detects the format of `data` and returns its metadata as an `AnalysisResult`.
+pub fn analyze(data: &[u8]) -> Result<AnalysisResult, AnalyzeError> {
+    analyze_with_options(data, &AnalysisOptions::default())
+}
 
-    if dmg::is_dmg_file(buf) {
-        return dmg::DMGAnalyzer::parse_metadata(buf);
+/// `analyze` with the opt-in behaviour described by `options`.
+pub fn analyze_with_options(
+    data: &[u8],
+    options: &AnalysisOptions,
+) -> Result<AnalysisResult, AnalyzeError> {
+    if data.len() < MIN_FILE_SIZE {
+        return Err(AnalyzeError::TooSmall(too_small_error(data.len())));
     }
+    let (analyzer, confidence) = match detect(data) {
+        Some(analyzer) => {
+            let confidence = analyzer.detection_confidence(data);
+            (analyzer, confidence)
+        }
+        None => {
+            let analyzer = detect_truncated(data, options.file_size(data.len()))
+                .ok_or_else(|| AnalyzeError::UnsupportedFormat(unsupported_format_error()))?;
+            (analyzer, Confidence::Low)
+        }
+    };
+    let meter = budget::Meter::start(options.work_budget(data.len()));
+    let fields = parse_metadata_with_options(data, options);
+    meter.finish().map_err(AnalyzeError::ResourceLimitExceeded)?;
+    let fields = fields.map_err(AnalyzeError::Malformed)?;
+
+    Ok(AnalysisResult { format: analyzer.format(), confidence, fields })
+}
+
+/// Detects the format of `buf` and extracts its metadata.
+pub fn parse_metadata(buf: &[u8]) -> MetadataResult {
+    let meter = budget::Meter::start(budget::default_budget(buf.len()));
+    let meta = parse_embedded_metadata(buf, 0, buf.len());
+    meter.finish()?;
+    meta
+}
+
+/// `parse_metadata` with the opt-in behaviour described by `options`.
+/// Fails with a `ResourceLimitExceeded` message once the work budget is spent.
+pub fn parse_metadata_with_options(buf: &[u8], options: &AnalysisOptions) -> MetadataResult {
+    let meter = budget::Meter::start(options.work_budget(buf.len()));
+    let meta = shaped_metadata(buf, options);
+    meter.finish()?;
+    meta
+}
 
-    if deb::is_deb_file(buf) {
-        return deb::DEBAnalyzer::parse_metadata(buf);
+/// `analyze_metadata` with its fields filtered, annotated and namespaced as
+/// `options` ask.
+fn shaped_metadata(buf: &[u8], options: &AnalysisOptions) -> MetadataResult {
+    let recording = options.include_sources.then(provenance::Recording::start);
+    let meta = analyze_metadata(buf, options);
+    let sources = recording.map(provenance::Recording::finish).unwrap_or_default();
+    let mut meta = meta?;
+    meta.retain(|key, _| options.wants_field(key));
+    if options.include_sources {
+        meta.insert("_sources".to_string(), provenance::sources_json(&meta, &sources));
+    }
+    if options.namespace_keys {
+        meta = namespace_keys(meta);
     }
+    Ok(meta)
+}
+
+/// The analysis and opt-in scans behind `parse_metadata_with_options`, before
+/// its fields are filtered and reshaped.
+fn analyze_metadata(buf: &[u8], options: &AnalysisOptions) -> MetadataResult {
+    let parse_share = if options.deep_scan { DEEP_SCAN_PARSE_SHARE } else { 1.0 };
+    let mut meta = {
+        let _stage = progress::stage(parse_share);
+        let _locale = locale::request_locale(options.locale.as_deref());
+        parse_embedded_metadata(buf, options.embedded_depth(), options.file_size(buf.len()))?
+    };
 
-    if rpm::is_rpm_file(buf) {
-        return rpm::RPMAnalyzer::parse_metadata(buf);
+    if let Some(sha256) = &options.sha256 {
+        meta.insert("Sha256".to_string(), sha256.to_ascii_lowercase());
+    }
+    insert_compression_ratio(&mut meta, options.compression_ratio_limit());
+    if options.deep_scan {
+        {
+            let _stage = progress::stage(1.0 - parse_share);
+            let hash = options.sha256.is_none() && options.wants_field("Sha256");
+            let hash_share = if hash { DEEP_SCAN_HASH_SHARE } else { 0.0 };
+            if hash {
+                let _stage = progress::stage(hash_share);
+                meta.insert("Sha256".to_string(), digest::file_sha256(buf));
+            }
+            let _stage = progress::stage(1.0 - hash_share);
+            strings::insert_deep_scan_fields(buf, options, &mut meta);
+        }
+        let analyzer = detect(buf);
+        if options.wants_field("RawManifest") {
+            if let Some(manifest) = analyzer.as_ref().and_then(|analyzer| analyzer.raw_manifest(buf)) {
+                meta.insert("RawManifest".to_string(), base64::encode_base64(&manifest));
+            }
+        }
+        if let Some(analyzer) = analyzer {
+            insert_file_list_fields(analyzer.as_ref(), buf, options, &mut meta);
+        }
     }
+    Ok(meta)
+}
 
-    let obj = Object::parse(buf).map_err(|e| format!("Failed to parse file: {}", e))?;
+/// `CompressionRatio` from the `CompressedSize` and `UncompressedSize` an
+/// analyzer read from the headers, and `SuspiciousCompressionRatio` when it
+/// exceeds `limit`, as decompression bombs do.
+fn insert_compression_ratio(meta: &mut HashMap<String, String>, limit: u64) {
+    let size = |key: &str| meta.get(key).and_then(|size| size.parse::<u64>().ok());
+    let (Some(compressed), Some(uncompressed)) = (size("CompressedSize"), size("UncompressedSize")) else {
+        return;
+    };
+    if compressed == 0 {
+        return;
+    }
+    let ratio = uncompressed as f64 / compressed as f64;
+    meta.insert("CompressionRatio".to_string(), format!("{:.1}", ratio));
+    meta.insert("SuspiciousCompressionRatio".to_string(), (ratio > limit as f64).to_string());
+}
 
-    match obj {
-        Object::PE(_) => pe::PEAnalyzer::parse_metadata(buf),
-        _ => Err("Unsupported file format. Supported formats: PE, MSI, DMG, DEB, RPM.".to_string())
+/// `FileList` and `HasPathTraversal`; the traversal check covers every path,
+/// not just the listed ones.
+fn insert_file_list_fields(
+    analyzer: &dyn FileAnalyzer,
+    buf: &[u8],
+    options: &AnalysisOptions,
+    meta: &mut HashMap<String, String>,
+) {
+    let check_traversal = options.wants_field("HasPathTraversal");
+    if !check_traversal && !options.wants_field("FileList") {
+        return;
+    }
+    let limit = if check_traversal { usize::MAX } else { MAX_FILE_LIST + 1 };
+    let mut files = analyzer.file_list(buf, limit);
+    if files.is_empty() {
+        return;
+    }
+    if check_traversal {
+        let absolute = analyzer.absolute_file_paths();
+        let traversal = files.iter().any(|path| is_path_traversal(path, absolute));
+        meta.insert("HasPathTraversal".to_string(), traversal.to_string());
+    }
+    if options.wants_field("FileList") {
+        if files.len() > MAX_FILE_LIST {
+            files.truncate(MAX_FILE_LIST);
+            meta.insert("FileListTruncated".to_string(), "true".to_string());
+        }
+        meta.insert("FileList".to_string(), files.join(", "));
     }
 }
 
-#[wasm_bindgen]
-pub fn analyze_file(data: &[u8]) -> String {
-    match parse_metadata(data) {
-        Ok(meta) => serde_json::to_string(&meta).unwrap_or_else(|_| "{}".to_string()),
-        Err(e) => format!("{{\"error\": \"{}\"}}", e)
+/// Whether extracting `path` could write outside the target directory
+/// (zip-slip): a `..` component, or unless the format stores `absolute`
+/// install paths, a leading separator or drive letter.
+fn is_path_traversal(path: &str, absolute: bool) -> bool {
+    if path.split(['/', '\\']).any(|component| component == "..") {
+        return true;
     }
+    let rooted = path.starts_with(['/', '\\']) || path.as_bytes().get(1) == Some(&b':');
+    rooted && !absolute
+}
+
+/// `parse_metadata_with_options`, calling `on_progress` with the fraction of
+/// the analysis done (0..=1) as its full-file passes (hashing, deep scan)
+/// advance, and with 1 once it is over.
+pub fn parse_metadata_with_progress(
+    buf: &[u8],
+    options: &AnalysisOptions,
+    on_progress: ProgressCallback,
+) -> MetadataResult {
+    let session = progress::Session::start(on_progress);
+    let meta = parse_metadata_with_options(buf, options);
+    session.finish();
+    meta
 }
 
-#[wasm_bindgen]
-pub fn get_file_info(data: &[u8]) -> String {
-    let mut info = if msi::is_msi_file(data) {
-        msi::MSIAnalyzer::get_file_info(data)
-    } else if dmg::is_dmg_file(data) {
-        dmg::DMGAnalyzer::get_file_info(data)
-    } else if deb::is_deb_file(data) {
-        deb::DEBAnalyzer::get_file_info(data)
-    } else if rpm::is_rpm_file(data) {
-        rpm::RPMAnalyzer::get_file_info(data)
-    } else if let Ok(obj) = Object::parse(data) {
-        match obj {
-            Object::PE(_) => pe::PEAnalyzer::get_file_info(data),
+/// Prefixes every key but `Format` and `_sources` with the lowercased format
+/// name (`pe.CompanyName`) and flattens the JSON-encoded `Embedded` object
+/// into `embedded.`-prefixed keys (`embedded.ProductName`, then
+/// `embedded.embedded.` one level further down).
+fn namespace_keys(meta: HashMap<String, String>) -> HashMap<String, String> {
+    let prefix = meta.get("Format").map(|format| format.to_lowercase()).unwrap_or_default();
+    let mut namespaced = HashMap::with_capacity(meta.len());
+    for (key, value) in meta {
+        match key.as_str() {
+            "Format" | "_sources" => {
+                namespaced.insert(key, value);
+            }
+            "Embedded" => insert_embedded_keys("embedded", &value, &mut namespaced),
             _ => {
-                let mut info = HashMap::new();
-                info.insert("Format".to_string(), "Unsupported".to_string());
-                info
+                namespaced.insert(format!("{}.{}", prefix, key), value);
             }
         }
-    } else {
-        let mut info = HashMap::new();
-        info.insert("Format".to_string(), "Invalid binary".to_string());
-        info
+    }
+    namespaced
+}
+
+fn insert_embedded_keys(prefix: &str, embedded_json: &str, meta: &mut HashMap<String, String>) {
+    let Ok(embedded) = serde_json::from_str::<HashMap<String, String>>(embedded_json) else {
+        return;
     };
+    for (key, value) in embedded {
+        if key == "Embedded" {
+            insert_embedded_keys(&format!("{}.embedded", prefix), &value, meta);
+        } else {
+            meta.insert(format!("{}.{}", prefix, key), value);
+        }
+    }
+}
+
+/// Analyzes `buf` and, while `depth` allows, the first embedded object its
+/// analyzer reports, nested as a JSON-encoded `Embedded` field. Offsets must be
+/// non-zero so every level works on a strictly smaller slice.
+///
+/// A recognized file shorter than its headers require is reported with
+/// `Truncated`, `ExpectedSize` and `ActualSize` rather than a parse error.
+/// `buf` may be the leading part of a `file_len`-byte file; sizes are checked
+/// against the whole file.
+fn parse_embedded_metadata(buf: &[u8], depth: usize, file_len: usize) -> MetadataResult {
+    if buf.len() < MIN_FILE_SIZE {
+        return Err(too_small_error(buf.len()));
+    }
+    let Some(analyzer) = detect(buf) else {
+        let analyzer = detect_truncated(buf, file_len).ok_or_else(unsupported_format_error)?;
+        let expected = truncated_size(analyzer.as_ref(), buf, file_len).unwrap_or_default();
+        return Ok(truncated_metadata(analyzer.format(), expected, file_len));
+    };
+    let truncated_to = truncated_size(analyzer.as_ref(), buf, file_len);
+    let mut meta = match (analyzer.parse_metadata(buf), truncated_to) {
+        (Ok(meta), _) => meta,
+        (Err(_), Some(expected)) => return Ok(truncated_metadata(analyzer.format(), expected, file_len)),
+        (Err(e), None) => return Err(e),
+    };
+    if let Some(expected) = truncated_to {
+        insert_truncation_info(expected, file_len, &mut meta);
+    }
+    // The payload runs on past `buf` to the end of the file
+    if let (Some(payload), true) = (analyzer.payload_offset(buf), meta.contains_key("CompressedSize")) {
+        meta.insert("CompressedSize".to_string(), file_len.saturating_sub(payload).to_string());
+    }
+    insert_product_fields(analyzer.format(), &mut meta);
+    version::insert_version_normalized(analyzer.format().name, &mut meta);
+    insert_category(analyzer.format(), &mut meta);
+    insert_detection_info(analyzer.format(), analyzer.detection_confidence(buf), &mut meta);
 
+    if depth > 0 {
+        let tagging = provenance::Tagging::begin(provenance::Source::Embedded, &meta);
+        let embedded = analyzer
+            .embedded_offsets(buf)
+            .into_iter()
+            .filter(|&offset| offset > 0 && offset < buf.len())
+            .find_map(|offset| {
+                let mut inner = parse_embedded_metadata(&buf[offset..], depth - 1, file_len.saturating_sub(offset)).ok()?;
+                inner.insert("Offset".to_string(), offset.to_string());
+                Some(inner)
+            });
+        if let Some(inner) = embedded {
+            meta.insert("Embedded".to_string(), metadata_to_json(&inner));
+        }
+        tagging.finish(&meta);
+    }
+    Ok(meta)
+}
+
+/// Analyzes only a prefix of a file; every result carries `Partial: "true"`.
+/// See `analyze_header` in the WASM bindings for which fields survive truncation.
+pub fn parse_header_metadata(buf: &[u8]) -> MetadataResult {
+    if buf.len() < MIN_FILE_SIZE {
+        return Err(too_small_error(buf.len()));
+    }
+    let analyzer = analyzers()
+        .into_iter()
+        .find(|analyzer| analyzer.sniff_header(buf))
+        .ok_or_else(unsupported_format_error)?;
+    let meter = budget::Meter::start(budget::default_budget(buf.len()));
+    let meta = analyzer.parse_header(buf);
+    meter.finish()?;
+    let mut meta = meta?;
+
+    meta.insert("Partial".to_string(), "true".to_string());
+    insert_product_fields(analyzer.format(), &mut meta);
+    version::insert_version_normalized(analyzer.format().name, &mut meta);
+    insert_category(analyzer.format(), &mut meta);
+    insert_detection_info(analyzer.format(), analyzer.detection_confidence(buf), &mut meta);
+    Ok(meta)
+}
+
+/// Basic type information (Format, MimeType, Extension, DetectionConfidence, Size).
+/// Never fails: unknown data is reported with `Low` confidence.
+pub fn get_file_info(data: &[u8]) -> MetadataResult {
+    let analyzer = if data.len() < MIN_FILE_SIZE { None } else { detect(data) };
+    let mut info = match analyzer {
+        Some(analyzer) => {
+            let mut info = analyzer.get_file_info(data);
+            insert_detection_info(analyzer.format(), analyzer.detection_confidence(data), &mut info);
+            info
+        }
+        None => {
+            let format = if data.len() < MIN_FILE_SIZE { &INVALID_FORMAT } else { unknown_format(data) };
+            let mut info = HashMap::new();
+            info.insert("Format".to_string(), format.name.to_string());
+            insert_detection_info(format, Confidence::Low, &mut info);
+            info
+        }
+    };
     info.insert("Size".to_string(), data.len().to_string());
 
-    serde_json::to_string(&info).unwrap_or_else(|_| "{}".to_string())
+    Ok(info)
+}
+
+/// The formats this build can analyze, in detection order.
+pub fn supported_formats() -> Vec<&'static FormatInfo> {
+    analyzers().iter().map(|analyzer| analyzer.format()).collect()
 }
 
-#[wasm_bindgen]
-pub fn analyze_pe_file(data: &[u8]) -> String {
-    match parse_metadata(data) {
-        Ok(meta) => serde_json::to_string(&meta).unwrap_or_else(|_| "{}".to_string()),
-        Err(e) => format!("{{\"error\": \"{}\"}}", e)
+/// Cargo features compiled into this build.
+pub fn enabled_features() -> Vec<&'static str> {
+    let features: [(&str, bool); 17] = [
+        ("wasm", cfg!(feature = "wasm")),
+        ("pe", cfg!(feature = "pe")),
+        ("msi", cfg!(feature = "msi")),
+        ("dmg", cfg!(feature = "dmg")),
+        ("deb", cfg!(feature = "deb")),
+        ("rpm", cfg!(feature = "rpm")),
+        ("cpio", cfg!(feature = "cpio")),
+        ("iso", cfg!(feature = "iso")),
+        ("elf", cfg!(feature = "elf")),
+        ("macho", cfg!(feature = "macho")),
+        ("wasm-module", cfg!(feature = "wasm-module")),
+        ("lnk", cfg!(feature = "lnk")),
+        ("java", cfg!(feature = "java")),
+        ("msu", cfg!(feature = "msu")),
+        ("appinstaller", cfg!(feature = "appinstaller")),
+        ("snap", cfg!(feature = "snap")),
+        ("flatpak", cfg!(feature = "flatpak")),
+    ];
+    features.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| *name).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// DOS stub + PE32 COFF/optional headers with no sections, enough for `Header::parse`.
+    fn minimal_pe_header() -> Vec<u8> {
+        let mut data = vec![0u8; 0x40 + 4 + 20 + 0xE0];
+        data[0..2].copy_from_slice(b"MZ");
+        data[0x3C..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        data[0x40..0x44].copy_from_slice(b"PE\0\0");
+        data[0x44..0x46].copy_from_slice(&0x014Cu16.to_le_bytes());
+        data[0x54..0x56].copy_from_slice(&0xE0u16.to_le_bytes());
+        data[0x58..0x5A].copy_from_slice(&0x010Bu16.to_le_bytes());
+        data[0x58 + 92..0x58 + 96].copy_from_slice(&16u32.to_le_bytes());
+        data
+    }
+
+    fn info_keys(data: &[u8]) -> Vec<String> {
+        let info = get_file_info(data).unwrap_or_default();
+        let mut keys: Vec<String> = info.into_keys().collect();
+        keys.sort();
+        keys
+    }
+
+    #[cfg(all(feature = "rpm", feature = "msi"))]
+    #[test]
+    fn test_detect() {
+        let mut rpm = vec![0; 128];
+        rpm[0..4].copy_from_slice(&[0xED, 0xAB, 0xEE, 0xDB]);
+        let analyzer = detect(&rpm);
+        assert_eq!(analyzer.as_ref().map(|a| a.format().name), Some("RPM"));
+        assert_eq!(analyzer.map(|a| a.detection_confidence(&rpm)), Some(Confidence::Medium));
+
+        let mut msi = vec![0; 512];
+        msi[0..8].copy_from_slice(&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1]);
+        assert_eq!(detect(&msi).map(|a| a.format().name), Some("MSI"));
+
+        // Java class files share the Mach-O fat magic
+        let class = [0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x00, 0x00, 0x34];
+        assert_ne!(detect(&class).map(|a| a.format().name), Some("Mach-O"));
+
+        assert!(detect(b"not a binary").is_none());
+        assert_eq!(unknown_format(b"not a binary").name, "Invalid binary");
+    }
+
+    #[cfg(feature = "pe")]
+    #[test]
+    fn test_analyze_returns_typed_result() {
+        let result = analyze(&minimal_pe_header());
+        assert_eq!(result.as_ref().map(|r| r.format.name).ok(), Some("PE"));
+        assert_eq!(result.as_ref().ok().and_then(|r| r.get("Architecture")), Some("x86"));
+        assert_eq!(result.as_ref().ok().and_then(|r| r.get("IsInstaller")), Some("false"));
+        assert_eq!(result.as_ref().ok().and_then(|r| r.get("Category")), Some("Executable"));
+
+        assert!(matches!(analyze(b"not a binary"), Err(AnalyzeError::UnsupportedFormat(_))));
+    }
+
+    #[test]
+    fn test_insert_product_fields() {
+        let deb = FormatInfo { name: "DEB", mime_type: "", extensions: &[] };
+        let mut meta: HashMap<String, String> = [
+            ("Package", "curl"),
+            ("Version", "7.88.1-10"),
+            ("Maintainer", "Debian Curl Maintainers"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        insert_product_fields(&deb, &mut meta);
+
+        assert_eq!(meta.get("ProductName").map(String::as_str), Some("curl"));
+        assert_eq!(meta.get("ProductVersion").map(String::as_str), Some("7.88.1-10"));
+        assert_eq!(meta.get("Publisher").map(String::as_str), Some("Debian Curl Maintainers"));
+
+        // A WebAssembly binary-format `Version` is not a product version
+        let wasm = FormatInfo { name: "WebAssembly", mime_type: "", extensions: &[] };
+        let mut meta = HashMap::from([("Version".to_string(), "1".to_string())]);
+        insert_product_fields(&wasm, &mut meta);
+        assert!(!meta.contains_key("ProductVersion"));
+    }
+
+    #[test]
+    fn test_insert_category() {
+        let pe = FormatInfo { name: "PE", mime_type: "", extensions: &[] };
+        let mut meta = HashMap::from([("IsInstaller".to_string(), "true".to_string())]);
+        insert_category(&pe, &mut meta);
+        assert_eq!(meta.get("Category").map(String::as_str), Some("Windows Installer"));
+
+        let rpm = FormatInfo { name: "RPM", mime_type: "", extensions: &[] };
+        let mut meta = HashMap::new();
+        insert_category(&rpm, &mut meta);
+        assert_eq!(meta.get("Category").map(String::as_str), Some("Linux Package"));
+
+        for format in supported_formats() {
+            assert!(FORMAT_CATEGORIES.iter().any(|(name, _)| *name == format.name), "{}", format.name);
+        }
+    }
+
+    #[test]
+    fn test_metadata_to_json_sorts_keys() {
+        let meta: HashMap<String, String> =
+            [("Version", "1.0"), ("Format", "PE"), ("Architecture", "x64")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+        assert_eq!(
+            metadata_to_json(&meta),
+            r#"{"Architecture":"x64","Format":"PE","Version":"1.0"}"#
+        );
+    }
+
+    #[test]
+    fn test_namespace_keys() {
+        let inner = r#"{"Format":"MSI","ProductName":"Inner","Offset":"512"}"#;
+        let meta: HashMap<String, String> =
+            [("Format", "PE"), ("ProductName", "Outer"), ("Embedded", inner)]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+        let meta = namespace_keys(meta);
+        let get = |key: &str| meta.get(key).map(String::as_str);
+
+        assert_eq!(meta.len(), 5);
+        assert_eq!(get("Format"), Some("PE"));
+        assert_eq!(get("pe.ProductName"), Some("Outer"));
+        assert_eq!(get("embedded.ProductName"), Some("Inner"));
+        assert_eq!(get("embedded.Format"), Some("MSI"));
+        assert_eq!(get("embedded.Offset"), Some("512"));
+    }
+
+    #[cfg(feature = "pe")]
+    #[test]
+    fn test_fields_allow_list() {
+        let options = AnalysisOptions::from_json(r#"{"deepScan": true, "fields": ["Architecture", "HeaderHexDump"]}"#)
+            .unwrap_or_default();
+        let meta = parse_metadata_with_options(&minimal_pe_header(), &options).unwrap_or_default();
+
+        let mut keys: Vec<&str> = meta.keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, ["Architecture", "Format", "HeaderHexDump"]);
+        assert_eq!(options.embedded_depth(), 0);
+    }
+
+    #[cfg(feature = "dmg")]
+    #[test]
+    fn test_raw_manifest_with_deep_scan() {
+        let plist = br#"<?xml version="1.0"?><plist version="1.0"><dict><key>CFBundleIdentifier</key><string>com.acme.app</string></dict></plist>"#;
+        let mut dmg = plist.to_vec();
+        dmg.resize(1024, 0);
+        dmg.extend_from_slice(b"koly");
+        dmg.resize(1024 + 512, 0);
+
+        let deep_scan = AnalysisOptions::default().with_deep_scan(true);
+        let meta = parse_metadata_with_options(&dmg, &deep_scan).unwrap_or_default();
+        assert_eq!(meta.get("Format").map(String::as_str), Some("DMG"));
+        assert_eq!(meta.get("RawManifest"), Some(&base64::encode_base64(plist)));
+
+        let meta = parse_metadata_with_options(&dmg, &AnalysisOptions::default()).unwrap_or_default();
+        assert!(!meta.contains_key("RawManifest"));
+    }
+
+    #[cfg(feature = "dmg")]
+    #[test]
+    fn test_parse_metadata_with_progress() {
+        let mut dmg = vec![b'a'; 2 * progress::REPORT_INTERVAL];
+        dmg.extend_from_slice(b"koly");
+        dmg.resize(dmg.len() + 508, 0);
+
+        let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let sink = std::rc::Rc::clone(&seen);
+        let deep_scan = AnalysisOptions::default().with_deep_scan(true);
+        let meta = parse_metadata_with_progress(&dmg, &deep_scan, Box::new(move |f| sink.borrow_mut().push(f)));
+        assert!(meta.is_ok());
+
+        let seen = seen.borrow();
+        assert!(seen.len() > 10, "{:?}", seen);
+        assert!(seen.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(seen.last(), Some(&1.0));
+    }
+
+    #[cfg(feature = "dmg")]
+    #[test]
+    fn test_field_sources() {
+        let mut dmg = b"<key>CFBundleName</key><string>Widget</string>".to_vec();
+        dmg.extend_from_slice(b"koly");
+        dmg.resize(dmg.len() + 508, 0);
+        let options = AnalysisOptions::default().with_include_sources(true);
+        let meta = parse_metadata_with_options(&dmg, &options).unwrap_or_default();
+        let sources: HashMap<String, String> =
+            serde_json::from_str(meta.get("_sources").map_or("{}", String::as_str)).unwrap_or_default();
+        assert_eq!(meta.get("ProductName").map(String::as_str), Some("Widget"));
+        assert_eq!(sources.get("ProductName").map(String::as_str), Some("heuristic"));
+        assert_eq!(sources.get("ProgramName").map(String::as_str), Some("heuristic"));
+        assert_eq!(sources.get("DMGVersion").map(String::as_str), Some("structured"));
+        assert!(!sources.contains_key("Format"));
+
+        let meta = parse_metadata_with_options(&dmg, &AnalysisOptions::default()).unwrap_or_default();
+        assert!(!meta.contains_key("_sources"));
+    }
+
+    #[cfg(feature = "dmg")]
+    #[test]
+    fn test_sha256_option_skips_hashing() {
+        let mut dmg = b"abc".to_vec();
+        dmg.extend_from_slice(b"koly");
+        dmg.resize(dmg.len() + 508, 0);
+        let deep_scan = AnalysisOptions::default().with_deep_scan(true).with_fields(&["Sha256"]);
+        let computed = parse_metadata_with_options(&dmg, &deep_scan).unwrap_or_default();
+        assert_eq!(computed.get("Sha256"), Some(&digest::file_sha256(&dmg)));
+
+        let options = AnalysisOptions::from_json(&format!(r#"{{"sha256": "{}"}}"#, "AB".repeat(32)));
+        let given = parse_metadata_with_options(&dmg, &options.unwrap_or_default()).unwrap_or_default();
+        assert_eq!(given.get("Sha256"), Some(&"ab".repeat(32)));
+        assert!(AnalysisOptions::from_json(r#"{"sha256": "abc"}"#).is_err());
+    }
+
+    #[cfg(feature = "pe")]
+    #[test]
+    fn test_parse_header_metadata_on_pe_prefix() {
+        let meta = parse_header_metadata(&minimal_pe_header()).unwrap_or_default();
+        assert_eq!(meta.get("Format").map(String::as_str), Some("PE"));
+        assert_eq!(meta.get("Architecture").map(String::as_str), Some("x86"));
+        assert_eq!(meta.get("IsGuiApp").map(String::as_str), Some("false"));
+        assert_eq!(meta.get("ASLR").map(String::as_str), Some("false"));
+        assert_eq!(meta.get("Partial").map(String::as_str), Some("true"));
+    }
+
+    #[test]
+    fn test_supported_formats_matches_unsupported_error() {
+        let names: Vec<&str> = supported_formats().iter().map(|f| f.name).collect();
+        let expected: Vec<&str> = analyzers().iter().map(|analyzer| analyzer.format().name).collect();
+
+        assert_eq!(names, expected);
+        assert!(unsupported_format_error().contains(&expected.join(", ")));
+    }
+
+    #[test]
+    fn test_insert_compression_ratio() {
+        let mut meta = HashMap::from([
+            ("CompressedSize".to_string(), "2048".to_string()),
+            ("UncompressedSize".to_string(), "4194304".to_string()),
+        ]);
+        insert_compression_ratio(&mut meta, 1000);
+        assert_eq!(meta.get("CompressionRatio").map(String::as_str), Some("2048.0"));
+        assert_eq!(meta.get("SuspiciousCompressionRatio").map(String::as_str), Some("true"));
+
+        insert_compression_ratio(&mut meta, 5000);
+        assert_eq!(meta.get("SuspiciousCompressionRatio").map(String::as_str), Some("false"));
+    }
+
+    #[test]
+    fn test_is_path_traversal() {
+        assert!(!is_path_traversal("usr/bin/tool", false));
+        assert!(!is_path_traversal("usr/lib/..hidden", false));
+        assert!(is_path_traversal("usr/../../etc/passwd", false));
+        assert!(is_path_traversal("..\\Windows\\evil.dll", false));
+        assert!(is_path_traversal("/etc/cron.d/job", false));
+        assert!(is_path_traversal("C:\\Windows\\evil.dll", false));
+        // RPM file lists are absolute by design
+        assert!(!is_path_traversal("/usr/bin/tool", true));
+        assert!(is_path_traversal("/usr/../etc/shadow", true));
+    }
+
+    #[cfg(all(feature = "pe", feature = "rpm"))]
+    #[test]
+    fn test_emitted_fields_are_registered() {
+        let mut rpm = vec![0; 96];
+        rpm[0..4].copy_from_slice(&[0xED, 0xAB, 0xEE, 0xDB]);
+        rpm.extend_from_slice(&[0x8E, 0xAD, 0xE8, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        rpm.extend_from_slice(&[0x8E, 0xAD, 0xE8, 0x01, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 4]);
+        rpm.extend_from_slice(&[0, 0, 0x03, 0xE8, 0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0, 1]);
+        rpm.extend_from_slice(b"foo\0");
+        let options = AnalysisOptions::default().with_deep_scan(true);
+
+        for data in [minimal_pe_header(), rpm] {
+            let meta = parse_metadata_with_options(&data, &options).unwrap_or_default();
+            let format = meta.get("Format").cloned().unwrap_or_default();
+            assert!(!format.is_empty());
+            for key in meta.keys() {
+                assert!(schema::is_registered(&format, key), "{} {}", format, key);
+            }
+        }
+    }
+
+    #[cfg(feature = "rpm")]
+    #[test]
+    fn test_truncated_upload_reports_sizes() {
+        let mut rpm = vec![0; 128];
+        rpm[0..4].copy_from_slice(&[0xED, 0xAB, 0xEE, 0xDB]);
+        rpm[96..100].copy_from_slice(&[0x8E, 0xAD, 0xE8, 0x01]);
+        // Signature header with no index entries and a 4 KiB store
+        rpm[108..112].copy_from_slice(&4096u32.to_be_bytes());
+
+        let meta = parse_metadata(&rpm).unwrap_or_default();
+        assert_eq!(meta.get("Format").map(String::as_str), Some("RPM"));
+        assert_eq!(meta.get("Truncated").map(String::as_str), Some("true"));
+        assert_eq!(meta.get("ExpectedSize").map(String::as_str), Some("4224"));
+        assert_eq!(meta.get("ActualSize").map(String::as_str), Some("128"));
+    }
+
+    #[test]
+    fn test_short_prefixes_never_panic() {
+        let mut koly = vec![0u8; 1024];
+        koly[0..4].copy_from_slice(b"koly");
+        koly[512..516].copy_from_slice(b"koly");
+        let seeds: Vec<Vec<u8>> = vec![
+            minimal_pe_header(),
+            [&[0xED, 0xAB, 0xEE, 0xDB][..], &[0xFF; 92], &[0x8E, 0xAD, 0xE8, 0x01], &[0xFF; 64]].concat(),
+            [&[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1][..], &[0xFF; 600]].concat(),
+            [&b"!<arch>\ndebian-binary   "[..], &[b'9'; 100]].concat(),
+            [&b"070701"[..], &[b'F'; 200]].concat(),
+            [&[0x7F, b'E', b'L', b'F', 2, 1, 1][..], &[0xFF; 120]].concat(),
+            [&b"\0asm\x01\0\0\0"[..], &[0xFF; 64]].concat(),
+            [&[0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 0x34][..], &[0xFF; 64]].concat(),
+            [&[0xCF, 0xFA, 0xED, 0xFE][..], &[0xFF; 64]].concat(),
+            [&[0x4C, 0, 0, 0, 0x01, 0x14, 0x02, 0][..], &[0xFF; 120]].concat(),
+            koly,
+        ];
+
+        for seed in &seeds {
+            for len in 0..=seed.len() {
+                let data = &seed[..len];
+                let results = [parse_metadata(data), parse_header_metadata(data)];
+                if len < MIN_FILE_SIZE {
+                    assert!(results.iter().all(|r| r.as_ref().is_err_and(|e| e.starts_with("Empty or too small"))));
+                    assert!(matches!(analyze(data), Err(AnalyzeError::TooSmall(_))));
+                }
+                assert!(get_file_info(data).is_ok());
+            }
+        }
+    }
+
+    /// Property test without a dependency: every analyzer, fed random bytes
+    /// behind its own signature, returns instead of panicking.
+    #[test]
+    fn test_analyzers_never_panic_on_random_input() {
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let signatures: [&[u8]; 12] = [
+            b"MZ",
+            &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1],
+            b"!<arch>\n",
+            &[0xED, 0xAB, 0xEE, 0xDB],
+            b"070701",
+            b"070707",
+            &[0x7F, b'E', b'L', b'F'],
+            &[0xCF, 0xFA, 0xED, 0xFE],
+            b"\0asm\x01\0\0\0",
+            &[0x4C, 0, 0, 0, 0x01, 0x14, 0x02, 0],
+            &[0xCA, 0xFE, 0xBA, 0xBE],
+            b"koly",
+        ];
+
+        for round in 0..240 {
+            let len = (next() % 2048) as usize;
+            // Plenty of zero bytes so length and offset fields are often small
+            let mut data: Vec<u8> = (0..len).map(|_| next()).map(|r| if r % 3 == 0 { 0 } else { r as u8 }).collect();
+            let signature = signatures[round % signatures.len()];
+            if data.len() >= 512 && signature == b"koly" {
+                let trailer = data.len() - 512;
+                data[trailer..trailer + 4].copy_from_slice(signature);
+            } else if data.len() >= signature.len() {
+                data[..signature.len()].copy_from_slice(signature);
+            }
+
+            for analyzer in analyzers() {
+                let _ = analyzer.parse_metadata(&data);
+                let _ = analyzer.parse_header(&data);
+                let _ = analyzer.expected_size(&data);
+                let _ = analyzer.embedded_offsets(&data);
+                let _ = analyzer.detection_confidence(&data);
+            }
+            let _ = parse_metadata_with_options(&data, &AnalysisOptions::default().with_deep_scan(true));
+        }
+    }
+
+    #[test]
+    fn test_get_file_info_uses_capitalized_keys() {
+        let mut rpm = vec![0; 128];
+        rpm[0..4].copy_from_slice(&[0xED, 0xAB, 0xEE, 0xDB]);
+
+        for data in [rpm.as_slice(), b"not a binary".as_slice(), &[]] {
+            assert_eq!(info_keys(data), vec!["DetectionConfidence", "Extension", "Format", "MimeType", "Size"]);
+        }
     }
 }
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use crate::{FileAnalyzer, FormatInfo, MetadataResult};
+
+// ShellLinkHeader (MS-SHLLINK 2.1)
+const LNK_HEADER_SIZE: usize = 0x4C;
+const LNK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x0000_0001;
+const HAS_LINK_INFO: u32 = 0x0000_0002;
+const HAS_NAME: u32 = 0x0000_0004;
+const HAS_RELATIVE_PATH: u32 = 0x0000_0008;
+const HAS_WORKING_DIR: u32 = 0x0000_0010;
+const HAS_ARGUMENTS: u32 = 0x0000_0020;
+const HAS_ICON_LOCATION: u32 = 0x0000_0040;
+const IS_UNICODE: u32 = 0x0000_0080;
+
+const LINK_FLAGS: [(u32, &str); 8] = [
+    (HAS_LINK_TARGET_ID_LIST, "HasLinkTargetIDList"),
+    (HAS_LINK_INFO, "HasLinkInfo"),
+    (HAS_NAME, "HasName"),
+    (HAS_RELATIVE_PATH, "HasRelativePath"),
+    (HAS_WORKING_DIR, "HasWorkingDir"),
+    (HAS_ARGUMENTS, "HasArguments"),
+    (HAS_ICON_LOCATION, "HasIconLocation"),
+    (IS_UNICODE, "IsUnicode"),
+];
+
+const FILE_ATTRIBUTES: [(u32, &str); 8] = [
+    (0x0001, "ReadOnly"),
+    (0x0002, "Hidden"),
+    (0x0004, "System"),
+    (0x0010, "Directory"),
+    (0x0020, "Archive"),
+    (0x0080, "Normal"),
+    (0x0100, "Temporary"),
+    (0x0800, "Compressed"),
+];
+
+// LinkInfo flag: VolumeID and LocalBasePath are present.
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x0000_0001;
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "LNK",
+    mime_type: "application/x-ms-shortcut",
+    extensions: &["lnk"],
+};
+
+pub struct LNKAnalyzer;
+
+impl FileAnalyzer for LNKAnalyzer {
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        is_lnk_file(data)
+    }
+
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
+        parse_lnk_metadata(data)
+    }
+}
+
+pub fn is_lnk_file(data: &[u8]) -> bool {
+    data.len() >= LNK_HEADER_SIZE
+        && read_u32(data, 0) == Some(LNK_HEADER_SIZE as u32)
+        && data[4..20] == LNK_CLSID
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+/// Reads a NUL-terminated ANSI string (decoded as Latin-1).
+fn read_ansi_z(data: &[u8], offset: usize) -> Option<String> {
+    let bytes = data.get(offset..)?;
+    let end = bytes.iter().position(|&b| b == 0)?;
+    Some(bytes[..end].iter().map(|&b| b as char).collect())
+}
+
+/// Reads a NUL-terminated UTF-16LE string.
+fn read_unicode_z(data: &[u8], offset: usize) -> Option<String> {
+    let units: Vec<u16> = data
+        .get(offset..)?
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&u| u != 0)
+        .collect();
+    Some(String::from_utf16_lossy(&units))
+}
+
+/// Resolves the local target path from a LinkInfo structure.
+fn read_link_info_target(info: &[u8]) -> Option<String> {
+    let header_size = read_u32(info, 4)? as usize;
+    let flags = read_u32(info, 8)?;
+    if flags & VOLUME_ID_AND_LOCAL_BASE_PATH == 0 {
+        return None;
+    }
+
+    let (base, suffix) = if header_size >= 0x24 {
+        let base = read_u32(info, 0x1C)? as usize;
+        let suffix = read_u32(info, 0x20)? as usize;
+        (read_unicode_z(info, base)?, read_unicode_z(info, suffix).unwrap_or_default())
+    } else {
+        let base = read_u32(info, 0x10)? as usize;
+        let suffix = read_u32(info, 0x18)? as usize;
+        (read_ansi_z(info, base)?, read_ansi_z(info, suffix).unwrap_or_default())
+    };
+
+    Some(format!("{}{}", base, suffix))
+}
+
+/// Reads one StringData entry (a u16 character count followed by the characters),
+/// advancing `pos` past it.
+fn read_string_data(data: &[u8], pos: &mut usize, unicode: bool) -> Option<String> {
+    let count = read_u16(data, *pos)? as usize;
+    let start = *pos + 2;
+    let byte_len = if unicode { count * 2 } else { count };
+    let bytes = data.get(start..start + byte_len)?;
+    *pos = start + byte_len;
+
+    if unicode {
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+        Some(String::from_utf16_lossy(&units))
+    } else {
+        Some(bytes.iter().map(|&b| b as char).collect())
+    }
+}
+
+fn parse_lnk_metadata(data: &[u8]) -> MetadataResult {
+    if !is_lnk_file(data) {
+        return Err("Not a valid Windows shortcut".to_string());
+    }
+
+    let mut meta = HashMap::new();
+    meta.insert("Format".into(), "LNK".into());
+
+    let flags = read_u32(data, 20).unwrap_or(0);
+    meta.insert("LinkFlags".into(), format!("0x{:08X}", flags));
+    let flag_names: Vec<&str> = LINK_FLAGS
+        .iter()
+        .filter(|(flag, _)| flags & flag != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    if !flag_names.is_empty() {
+        meta.insert("LinkFlagNames".into(), flag_names.join(" | "));
+    }
+
+    let attributes = read_u32(data, 24).unwrap_or(0);
+    let attribute_names: Vec<&str> = FILE_ATTRIBUTES
+        .iter()
+        .filter(|(attr, _)| attributes & attr != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    if !attribute_names.is_empty() {
+        meta.insert("TargetAttributes".into(), attribute_names.join(" | "));
+    }
+    meta.insert("TargetFileSize".into(), read_u32(data, 52).unwrap_or(0).to_string());
+
+    let mut pos = LNK_HEADER_SIZE;
+    if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+        let id_list_size = read_u16(data, pos).ok_or("Truncated LinkTargetIDList")? as usize;
+        pos += 2 + id_list_size;
+    }
+
+    if flags & HAS_LINK_INFO != 0 {
+        let info_size = read_u32(data, pos).ok_or("Truncated LinkInfo")? as usize;
+        let info = pos
+            .checked_add(info_size)
+            .and_then(|end| data.get(pos..end))
+            .ok_or("Truncated LinkInfo")?;
+        if let Some(target) = read_link_info_target(info) {
+            meta.insert("LinkTarget".into(), target);
+        }
+        pos += info_size;
+    }
+
+    let unicode = flags & IS_UNICODE != 0;
+    let string_fields = [
+        (HAS_NAME, "Description"),
+        (HAS_RELATIVE_PATH, "RelativePath"),
+        (HAS_WORKING_DIR, "WorkingDirectory"),
+        (HAS_ARGUMENTS, "CommandLineArgs"),
+        (HAS_ICON_LOCATION, "IconLocation"),
+    ];
+    for (flag, key) in string_fields {
+        if flags & flag == 0 {
+            continue;
+        }
+        match read_string_data(data, &mut pos, unicode) {
+            Some(value) if !value.is_empty() => {
+                meta.insert(key.into(), value);
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+
+    if !meta.contains_key("LinkTarget") {
+        if let Some(relative) = meta.get("RelativePath").cloned() {
+            meta.insert("LinkTarget".into(), relative);
+        }
+    }
+
+    Ok(meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(s: &str) -> Vec<u8> {
+        let mut out = (s.encode_utf16().count() as u16).to_le_bytes().to_vec();
+        out.extend(s.encode_utf16().flat_map(u16::to_le_bytes));
+        out
+    }
+
+    #[test]
+    fn test_parse_lnk_metadata() {
+        let mut lnk = vec![0u8; LNK_HEADER_SIZE];
+        lnk[0..4].copy_from_slice(&(LNK_HEADER_SIZE as u32).to_le_bytes());
+        lnk[4..20].copy_from_slice(&LNK_CLSID);
+        let flags = HAS_RELATIVE_PATH | HAS_ARGUMENTS | IS_UNICODE;
+        lnk[20..24].copy_from_slice(&flags.to_le_bytes());
+        lnk.extend(utf16(r"..\Windows\System32\WindowsPowerShell\v1.0\powershell.exe"));
+        lnk.extend(utf16("-nop -w hidden -c IEX"));
+
+        assert!(is_lnk_file(&lnk));
+        let meta = parse_lnk_metadata(&lnk).unwrap_or_default();
+        assert_eq!(meta.get("CommandLineArgs").map(String::as_str), Some("-nop -w hidden -c IEX"));
+        assert_eq!(
+            meta.get("LinkTarget").map(String::as_str),
+            Some(r"..\Windows\System32\WindowsPowerShell\v1.0\powershell.exe")
+        );
+        assert!(!is_lnk_file(b"L\0\0\0"));
+    }
+}
@@ -0,0 +1,170 @@
+//! Normalization of the per-format locale fields into a BCP-47 `Language` tag,
+//! and the locale callers asked localized fields to be reported in.
+
+use std::cell::RefCell;
+
+thread_local! {
+    static REQUESTED_LOCALE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// `locale` (e.g. `de_DE.UTF-8`) as the locale analyzers report localized
+/// fields in, until the returned scope drops; `None` keeps each format's
+/// default.
+pub fn request_locale(locale: Option<&str>) -> LocaleScope {
+    LocaleScope { previous: REQUESTED_LOCALE.replace(locale.map(str::to_string)) }
+}
+
+/// Restores the locale requested before it when dropped, so a nested request
+/// or a panic never leaves its locale behind.
+pub struct LocaleScope {
+    previous: Option<String>,
+}
+
+impl Drop for LocaleScope {
+    fn drop(&mut self) {
+        REQUESTED_LOCALE.set(self.previous.take());
+    }
+}
+
+/// The locale set by the live `request_locale` scope, if any.
+#[cfg(feature = "rpm")]
+pub fn requested_locale() -> Option<String> {
+    REQUESTED_LOCALE.with_borrow(Clone::clone)
+}
+
+/// Windows LCIDs (as used by PE version resources and MSI `ProductLanguage`)
+/// for the most common installer languages.
+#[cfg(any(feature = "pe", feature = "msi"))]
+const LCID_TAGS: [(u16, &str); 80] = [
+    (0x0401, "ar-SA"),
+    (0x0402, "bg-BG"),
+    (0x0403, "ca-ES"),
+    (0x0404, "zh-TW"),
+    (0x0405, "cs-CZ"),
+    (0x0406, "da-DK"),
+    (0x0407, "de-DE"),
+    (0x0408, "el-GR"),
+    (0x0409, "en-US"),
+    (0x040A, "es-ES"),
+    (0x040B, "fi-FI"),
+    (0x040C, "fr-FR"),
+    (0x040D, "he-IL"),
+    (0x040E, "hu-HU"),
+    (0x040F, "is-IS"),
+    (0x0410, "it-IT"),
+    (0x0411, "ja-JP"),
+    (0x0412, "ko-KR"),
+    (0x0413, "nl-NL"),
+    (0x0414, "nb-NO"),
+    (0x0415, "pl-PL"),
+    (0x0416, "pt-BR"),
+    (0x0418, "ro-RO"),
+    (0x0419, "ru-RU"),
+    (0x041A, "hr-HR"),
+    (0x041B, "sk-SK"),
+    (0x041C, "sq-AL"),
+    (0x041D, "sv-SE"),
+    (0x041E, "th-TH"),
+    (0x041F, "tr-TR"),
+    (0x0421, "id-ID"),
+    (0x0422, "uk-UA"),
+    (0x0423, "be-BY"),
+    (0x0424, "sl-SI"),
+    (0x0425, "et-EE"),
+    (0x0426, "lv-LV"),
+    (0x0427, "lt-LT"),
+    (0x0429, "fa-IR"),
+    (0x042A, "vi-VN"),
+    (0x042D, "eu-ES"),
+    (0x042F, "mk-MK"),
+    (0x0436, "af-ZA"),
+    (0x0437, "ka-GE"),
+    (0x0439, "hi-IN"),
+    (0x043E, "ms-MY"),
+    (0x043F, "kk-KZ"),
+    (0x0456, "gl-ES"),
+    (0x0804, "zh-CN"),
+    (0x0807, "de-CH"),
+    (0x0809, "en-GB"),
+    (0x080A, "es-MX"),
+    (0x080C, "fr-BE"),
+    (0x0810, "it-CH"),
+    (0x0813, "nl-BE"),
+    (0x0814, "nn-NO"),
+    (0x0816, "pt-PT"),
+    (0x081D, "sv-FI"),
+    (0x0C01, "ar-EG"),
+    (0x0C04, "zh-HK"),
+    (0x0C07, "de-AT"),
+    (0x0C09, "en-AU"),
+    (0x0C0A, "es-ES"),
+    (0x0C0C, "fr-CA"),
+    (0x1004, "zh-SG"),
+    (0x1007, "de-LU"),
+    (0x1009, "en-CA"),
+    (0x100C, "fr-CH"),
+    (0x1404, "zh-MO"),
+    (0x1409, "en-NZ"),
+    (0x140C, "fr-LU"),
+    (0x1809, "en-IE"),
+    (0x1C09, "en-ZA"),
+    (0x240A, "es-CO"),
+    (0x241A, "sr-Latn-RS"),
+    (0x2C0A, "es-AR"),
+    (0x340A, "es-CL"),
+    (0x3801, "ar-AE"),
+    (0x4009, "en-IN"),
+    (0x4809, "en-SG"),
+    (0x540A, "es-US"),
+];
+
+/// BCP-47 tag for a Windows LCID. `None` for language-neutral identifiers
+/// (0x0000, 0x0400, 0x007F) and for LCIDs missing from the table, which have
+/// no tag to report.
+#[cfg(any(feature = "pe", feature = "msi"))]
+pub fn language_from_lcid(lcid: u16) -> Option<String> {
+    LCID_TAGS.iter().find(|(id, _)| *id == lcid).map(|(_, tag)| tag.to_string())
+}
+
+/// Normalizes a `CFBundleDevelopmentRegion` value: legacy English names such
+/// as `English` become `en`, and `en_US` becomes `en-US`.
+#[cfg(feature = "dmg")]
+pub fn language_from_region(region: &str) -> String {
+    let legacy = [
+        ("English", "en"),
+        ("French", "fr"),
+        ("German", "de"),
+        ("Japanese", "ja"),
+        ("Spanish", "es"),
+        ("Italian", "it"),
+        ("Dutch", "nl"),
+    ];
+    match legacy.iter().find(|(name, _)| name.eq_ignore_ascii_case(region)) {
+        Some((_, tag)) => tag.to_string(),
+        None => region.replace('_', "-"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(any(feature = "pe", feature = "msi", feature = "dmg"))]
+    use super::*;
+
+    #[cfg(any(feature = "pe", feature = "msi"))]
+    #[test]
+    fn test_language_from_lcid() {
+        assert_eq!(language_from_lcid(1033).as_deref(), Some("en-US"));
+        assert_eq!(language_from_lcid(0x0C07).as_deref(), Some("de-AT"));
+        assert_eq!(language_from_lcid(0x0C0C).as_deref(), Some("fr-CA"));
+        // Not a BCP-47 tag, so unknown LCIDs give no language at all
+        assert_eq!(language_from_lcid(0x0491), None);
+        assert_eq!(language_from_lcid(0), None);
+    }
+
+    #[cfg(feature = "dmg")]
+    #[test]
+    fn test_language_from_region() {
+        assert_eq!(language_from_region("English"), "en");
+        assert_eq!(language_from_region("pt_BR"), "pt-BR");
+    }
+}
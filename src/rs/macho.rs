@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+use goblin::mach::{
+    constants::cputype::{get_arch_name_from_types, CPU_SUBTYPE_MASK},
+    fat::FAT_MAGIC,
+    header::{self, MH_BUNDLE, MH_CORE, MH_DSYM, MH_DYLIB, MH_DYLINKER, MH_EXECUTE, MH_KEXT_BUNDLE, MH_OBJECT},
+    load_command::{
+        CommandVariant, PLATFORM_BRIDGEOS, PLATFORM_DRIVERKIT, PLATFORM_IOS, PLATFORM_IOSSIMULATOR,
+        PLATFORM_MACCATALYST, PLATFORM_MACOS, PLATFORM_TVOS, PLATFORM_TVOSSIMULATOR, PLATFORM_VISIONOS,
+        PLATFORM_VISIONOSSIMULATOR, PLATFORM_WATCHOS, PLATFORM_WATCHOSSIMULATOR,
+    },
+    Mach, MachO, MultiArch, SingleArch,
+};
+use crate::{Confidence, FileAnalyzer, FormatInfo, MetadataResult};
+#[cfg(feature = "dmg")]
+use crate::dmg;
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "Mach-O",
+    mime_type: "application/x-mach-binary",
+    extensions: &["macho", "dylib", "bundle", "o"],
+};
+
+const CSMAGIC_EMBEDDED_SIGNATURE: u32 = 0xFADE_0CC0;
+const CSMAGIC_CODEDIRECTORY: u32 = 0xFADE_0C02;
+const CSSLOT_CODEDIRECTORY: u32 = 0;
+/// First CodeDirectory version that carries `teamOffset`.
+const CS_SUPPORTSTEAMID: u32 = 0x0002_0200;
+
+const MACHO_MAGICS: [u32; 5] = [
+    header::MH_MAGIC,
+    header::MH_CIGAM,
+    header::MH_MAGIC_64,
+    header::MH_CIGAM_64,
+    FAT_MAGIC,
+];
+
+/// Fat headers with more slices than this are rejected, as file(1) does: Java
+/// class files share `CA FE BA BE` and store `minor_version`/`major_version`
+/// (major >= 45) where a fat header keeps `nfat_arch`.
+const MAX_FAT_ARCHES: u32 = 20;
+
+pub struct MachOAnalyzer;
+
+impl FileAnalyzer for MachOAnalyzer {
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        is_macho_file(data)
+    }
+
+    fn detection_confidence(&self, data: &[u8]) -> Confidence {
+        if Mach::parse(data).is_ok() {
+            Confidence::High
+        } else {
+            Confidence::Medium
+        }
+    }
+
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
+        match Mach::parse(data).map_err(|e| format!("Failed to parse Mach-O file: {}", e))? {
+            Mach::Binary(macho) => Ok(parse_macho_metadata(&macho, data)),
+            Mach::Fat(multi) => parse_fat_metadata(&multi, data),
+        }
+    }
+
+    fn raw_manifest(&self, data: &[u8]) -> Option<Vec<u8>> {
+        let plist = match Mach::parse(data).ok()? {
+            Mach::Binary(macho) => find_info_plist(&macho),
+            Mach::Fat(multi) => (0..multi.narches).find_map(|idx| match multi.get(idx) {
+                Ok(SingleArch::MachO(macho)) => find_info_plist(&macho),
+                _ => None,
+            }),
+        };
+        plist.map(<[u8]>::to_vec)
+    }
+}
+
+pub fn is_macho_file(data: &[u8]) -> bool {
+    if data.len() < 4 {
+        return false;
+    }
+    let magic = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+    if magic == FAT_MAGIC {
+        return is_plausible_fat_header(data);
+    }
+    MACHO_MAGICS.contains(&magic)
+}
+
+fn is_plausible_fat_header(data: &[u8]) -> bool {
+    match data.get(4..8) {
+        Some(word) => {
+            let nfat_arch = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+            (1..=MAX_FAT_ARCHES).contains(&nfat_arch)
+        }
+        None => false,
+    }
+}
+
+fn arch_name(cputype: u32, cpusubtype: u32) -> String {
+    get_arch_name_from_types(cputype, cpusubtype)
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("0x{:08X}", cputype))
+}
+
+/// Universal binaries: list every slice and describe the first Mach-O slice in detail.
+fn parse_fat_metadata(multi: &MultiArch, data: &[u8]) -> MetadataResult {
+    let arches = multi.arches().map_err(|e| format!("Failed to read fat architectures: {}", e))?;
+
+    let mut meta = (0..arches.len())
+        .find_map(|idx| match multi.get(idx) {
+            Ok(SingleArch::MachO(macho)) => Some(parse_macho_metadata(&macho, arches[idx].slice(data))),
+            _ => None,
+        })
+        .unwrap_or_default();
+    meta.insert("Format".into(), "Mach-O".into());
+
+    let mut names = Vec::with_capacity(arches.len());
+    for (idx, arch) in arches.iter().enumerate() {
+        let name = arch_name(arch.cputype, arch.cpusubtype & !CPU_SUBTYPE_MASK);
+        meta.insert(format!("Slice_{}_Architecture", idx), name.clone());
+        meta.insert(format!("Slice_{}_Offset", idx), arch.offset.to_string());
+        meta.insert(format!("Slice_{}_Size", idx), arch.size.to_string());
+        names.push(name);
+    }
+
+    meta.insert("IsUniversal".into(), "true".into());
+    meta.insert("SliceCount".into(), arches.len().to_string());
+    meta.insert("Architecture".into(), "Universal".into());
+    meta.insert("Architectures".into(), names.join(", "));
+
+    Ok(meta)
+}
+
+/// `data` is the bytes of this (thin) Mach-O image; load command offsets are relative to it.
+fn parse_macho_metadata(macho: &MachO, data: &[u8]) -> HashMap<String, String> {
+    let mut meta = HashMap::new();
+    meta.insert("Format".into(), "Mach-O".into());
+
+    let cputype = macho.header.cputype();
+    let cpusubtype = macho.header.cpusubtype();
+    meta.insert("Architecture".into(), arch_name(cputype, cpusubtype));
+    meta.insert("CpuType".into(), format!("0x{:08X}", cputype));
+    meta.insert("CpuSubType".into(), format!("0x{:08X}", cpusubtype));
+
+    meta.insert("FileType".into(), filetype_name(macho.header.filetype).into());
+    meta.insert("Flags".into(), format!("0x{:08X}", macho.header.flags));
+
+    let flag_names = flag_names(macho.header.flags);
+    if !flag_names.is_empty() {
+        meta.insert("FlagNames".into(), flag_names.join(" | "));
+    }
+
+    meta.insert("NumberOfLoadCommands".into(), macho.header.ncmds.to_string());
+
+    let has_text = macho.segments.iter().any(|segment| segment.name().ok() == Some("__TEXT"));
+    if has_text {
+        meta.insert("HasTextSegment".into(), "true".into());
+    }
+
+    #[cfg(feature = "dmg")]
+    if let Some(plist) = find_info_plist(macho) {
+        dmg::parse_plist_properly(plist, &mut meta);
+    }
+
+    let mut linked = Vec::new();
+    for lc in &macho.load_commands {
+        match &lc.command {
+            CommandVariant::IdDylib(cmd) => {
+                if let Some(name) = macho.name {
+                    meta.insert("InstallName".into(), name.to_string());
+                }
+                meta.insert("CurrentVersion".into(), packed_version(cmd.dylib.current_version));
+                meta.insert("CompatibilityVersion".into(), packed_version(cmd.dylib.compatibility_version));
+            }
+            CommandVariant::LoadDylib(cmd) | CommandVariant::LoadWeakDylib(cmd) => {
+                if let Some(name) = c_string_at(data, lc.offset + cmd.dylib.name as usize) {
+                    linked.push(name);
+                }
+            }
+            CommandVariant::CodeSignature(cmd) => {
+                meta.insert("IsSigned".into(), "true".into());
+                let start = cmd.dataoff as usize;
+                let blob = start
+                    .checked_add(cmd.datasize as usize)
+                    .and_then(|end| data.get(start..end));
+                if let Some(blob) = blob {
+                    extract_code_signature(blob, &mut meta);
+                }
+            }
+            CommandVariant::BuildVersion(cmd) => {
+                meta.insert("MinimumSystemVersion".into(), packed_version(cmd.minos));
+                meta.insert("Platform".into(), platform_name(cmd.platform));
+            }
+            CommandVariant::VersionMinMacosx(cmd) => insert_version_min(&mut meta, "macOS", cmd.version),
+            CommandVariant::VersionMinIphoneos(cmd) => insert_version_min(&mut meta, "iOS", cmd.version),
+            CommandVariant::VersionMinTvos(cmd) => insert_version_min(&mut meta, "tvOS", cmd.version),
+            CommandVariant::VersionMinWatchos(cmd) => insert_version_min(&mut meta, "watchOS", cmd.version),
+            _ => {}
+        }
+    }
+    if !linked.is_empty() {
+        meta.insert("LinkedLibraries".into(), linked.join(", "));
+    }
+
+    meta
+}
+
+/// Legacy `LC_VERSION_MIN_*` commands; `LC_BUILD_VERSION` wins when both are present.
+fn insert_version_min(meta: &mut HashMap<String, String>, platform: &str, version: u32) {
+    if !meta.contains_key("Platform") {
+        meta.insert("MinimumSystemVersion".into(), packed_version(version));
+        meta.insert("Platform".into(), platform.into());
+    }
+}
+
+fn platform_name(platform: u32) -> String {
+    match platform {
+        PLATFORM_MACOS => "macOS".into(),
+        PLATFORM_IOS => "iOS".into(),
+        PLATFORM_TVOS => "tvOS".into(),
+        PLATFORM_WATCHOS => "watchOS".into(),
+        PLATFORM_BRIDGEOS => "bridgeOS".into(),
+        PLATFORM_MACCATALYST => "Mac Catalyst".into(),
+        PLATFORM_IOSSIMULATOR => "iOS Simulator".into(),
+        PLATFORM_TVOSSIMULATOR => "tvOS Simulator".into(),
+        PLATFORM_WATCHOSSIMULATOR => "watchOS Simulator".into(),
+        PLATFORM_DRIVERKIT => "DriverKit".into(),
+        PLATFORM_VISIONOS => "visionOS".into(),
+        PLATFORM_VISIONOSSIMULATOR => "visionOS Simulator".into(),
+        other => format!("Unknown ({})", other),
+    }
+}
+
+/// Command-line tools embed their Info.plist in `__TEXT,__info_plist`.
+fn find_info_plist<'a>(macho: &MachO<'a>) -> Option<&'a [u8]> {
+    macho
+        .segments
+        .iter()
+        .filter(|segment| segment.name().ok() == Some("__TEXT"))
+        .filter_map(|segment| segment.sections().ok())
+        .flatten()
+        .find(|(section, _)| section.name().ok() == Some("__info_plist"))
+        .map(|(_, bytes)| bytes)
+}
+
+fn read_be_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Walks the embedded signature SuperBlob (big-endian) to its CodeDirectory and reads
+/// the signing identifier and, for CodeDirectory versions that carry one, the Team ID.
+fn extract_code_signature(blob: &[u8], meta: &mut HashMap<String, String>) {
+    if read_be_u32(blob, 0) != Some(CSMAGIC_EMBEDDED_SIGNATURE) {
+        return;
+    }
+    let count = read_be_u32(blob, 8).unwrap_or(0) as usize;
+
+    let directory = (0..count)
+        .filter_map(|idx| {
+            let entry = 12 + idx * 8;
+            Some((read_be_u32(blob, entry)?, read_be_u32(blob, entry + 4)? as usize))
+        })
+        .find(|(slot, _)| *slot == CSSLOT_CODEDIRECTORY)
+        .and_then(|(_, offset)| blob.get(offset..));
+
+    let cd = match directory {
+        Some(cd) if read_be_u32(cd, 0) == Some(CSMAGIC_CODEDIRECTORY) => cd,
+        _ => return,
+    };
+
+    if let Some(ident) = read_be_u32(cd, 20).and_then(|off| c_string_at(cd, off as usize)) {
+        meta.insert("SigningIdentifier".into(), ident.to_string());
+    }
+
+    let version = read_be_u32(cd, 8).unwrap_or(0);
+    if version >= CS_SUPPORTSTEAMID {
+        let team = read_be_u32(cd, 48)
+            .filter(|off| *off != 0)
+            .and_then(|off| c_string_at(cd, off as usize));
+        if let Some(team) = team {
+            meta.insert("TeamIdentifier".into(), team.to_string());
+        }
+    }
+}
+
+/// Reads a NUL-terminated UTF-8 string starting at `offset`.
+fn c_string_at(data: &[u8], offset: usize) -> Option<&str> {
+    let bytes = data.get(offset..)?;
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    std::str::from_utf8(&bytes[..end]).ok().filter(|s| !s.is_empty())
+}
+
+/// Dylib and OS versions are packed as `xxxx.yy.zz` in nibbles.
+fn packed_version(version: u32) -> String {
+    format!("{}.{}.{}", version >> 16, (version >> 8) & 0xff, version & 0xff)
+}
+
+fn filetype_name(filetype: u32) -> &'static str {
+    match filetype {
+        MH_EXECUTE => "Executable",
+        MH_DYLIB => "Dylib",
+        MH_BUNDLE => "Bundle",
+        MH_OBJECT => "Object",
+        MH_CORE => "Core",
+        MH_DYLINKER => "Dylinker",
+        MH_DSYM => "dSYM",
+        MH_KEXT_BUNDLE => "Kext Bundle",
+        other => header::filetype_to_str(other),
+    }
+}
+
+fn flag_names(flags: u32) -> Vec<&'static str> {
+    (0..32)
+        .map(|bit| 1u32 << bit)
+        .filter(|flag| flags & flag != 0)
+        .map(header::flag_to_str)
+        .filter(|name| *name != "UNKNOWN FLAG")
+        .map(str::trim)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flag_names() {
+        let flags = header::MH_NOUNDEFS | header::MH_DYLDLINK | header::MH_PIE;
+        assert_eq!(flag_names(flags), vec!["MH_NOUNDEFS", "MH_DYLDLINK", "MH_PIE"]);
+    }
+
+    #[test]
+    fn test_packed_version() {
+        assert_eq!(packed_version(0x0001_0203), "1.2.3");
+        assert_eq!(packed_version(0x04B1_0000), "1201.0.0");
+    }
+
+    #[test]
+    fn test_version_min_does_not_override_build_version() {
+        let mut meta = HashMap::new();
+        meta.insert("Platform".to_string(), platform_name(PLATFORM_MACCATALYST));
+        meta.insert("MinimumSystemVersion".to_string(), packed_version(0x000D_0100));
+        insert_version_min(&mut meta, "macOS", 0x000A_0F00);
+        assert_eq!(meta.get("Platform").map(String::as_str), Some("Mac Catalyst"));
+        assert_eq!(meta.get("MinimumSystemVersion").map(String::as_str), Some("13.1.0"));
+
+        let mut meta = HashMap::new();
+        insert_version_min(&mut meta, "macOS", 0x000A_0F00);
+        assert_eq!(meta.get("MinimumSystemVersion").map(String::as_str), Some("10.15.0"));
+    }
+
+    #[test]
+    fn test_c_string_at() {
+        let data = b"xx/usr/lib/libSystem.B.dylib\0\0";
+        assert_eq!(c_string_at(data, 2), Some("/usr/lib/libSystem.B.dylib"));
+        assert_eq!(c_string_at(data, 29), None);
+        assert_eq!(c_string_at(data, 100), None);
+    }
+
+    #[test]
+    fn test_extract_code_signature() {
+        let mut cd = Vec::new();
+        for word in [CSMAGIC_CODEDIRECTORY, 0, CS_SUPPORTSTEAMID, 0, 0, 56, 0, 0, 0, 0, 0, 0, 69] {
+            cd.extend_from_slice(&word.to_be_bytes());
+        }
+        cd.extend_from_slice(b"\0\0\0\0com.example\0\0ABCDE12345\0");
+
+        let mut blob = Vec::new();
+        for word in [CSMAGIC_EMBEDDED_SIGNATURE, 0, 1, CSSLOT_CODEDIRECTORY, 20] {
+            blob.extend_from_slice(&word.to_be_bytes());
+        }
+        blob.extend_from_slice(&cd);
+
+        let mut meta = HashMap::new();
+        extract_code_signature(&blob, &mut meta);
+        assert_eq!(meta.get("SigningIdentifier").map(String::as_str), Some("com.example"));
+        assert_eq!(meta.get("TeamIdentifier").map(String::as_str), Some("ABCDE12345"));
+    }
+
+    #[test]
+    fn test_is_macho_file() {
+        assert!(is_macho_file(&[0xCF, 0xFA, 0xED, 0xFE, 0, 0, 0, 0]));
+        assert!(is_macho_file(&[0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 2]));
+        // Java 8 class file: same magic, minor 0 / major 52
+        assert!(!is_macho_file(&[0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 0x34]));
+        assert!(!is_macho_file(&[0xCA, 0xFE, 0xBA, 0xBE]));
+        assert!(!is_macho_file(b"MZ\x90\x00"));
+    }
+}
@@ -1,28 +1,88 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::{Cursor, Read};
 use cfb::CompoundFile;
-use crate::{FileAnalyzer, MetadataResult};
+use crate::provenance::{self, Source};
+use crate::compound::{self, CfbFile};
+use crate::{budget, digest, locale, Confidence, FileAnalyzer, FormatInfo, MetadataResult};
 
 // Constants for MSI file analysis
 const MSI_SIGNATURE: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
 const MIN_MSI_SIGNATURE_SIZE: usize = 8;
+// Compound File Binary header layout
+const CFB_HEADER_SIZE: usize = 512;
+const CFB_HEADER_DIFAT_ENTRIES: usize = 109;
+const CFB_MAX_REGULAR_SECTOR: u32 = 0xFFFF_FFFA;
+// Root storage CLSIDs of Windows Installer packages, patches and transforms
+const INSTALLER_CLSIDS: [&str; 3] = [
+    "{000C1084-0000-0000-C000-000000000046}",
+    "{000C1086-0000-0000-C000-000000000046}",
+    "{000C1082-0000-0000-C000-000000000046}",
+];
+// Table streams only an installer database has (decoded names)
+const INSTALLER_STREAMS: [&str; 3] = ["!_Tables", "!_StringPool", "!_StringData"];
+// Prefix character of encoded table stream names
+const TABLE_STREAM_MARKER: u32 = 0x4840;
 const MIN_METADATA_STRING_LEN: usize = 3;
 const MAX_METADATA_STRING_LEN: usize = 100;
 
-// Type alias to reduce complexity
-type CfbFile<'a> = CompoundFile<Cursor<&'a [u8]>>;
+// SummaryInformation Word Count (PID 15, VT_I4); bit 3 marks a package that
+// installs without elevated privileges.
+const PID_WORD_COUNT: u32 = 15;
+const VT_I4: u16 = 3;
+const ELEVATION_NOT_REQUIRED: u32 = 0x08;
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "MSI",
+    mime_type: "application/x-msi",
+    extensions: &["msi"],
+};
 
 pub struct MSIAnalyzer;
 
 impl FileAnalyzer for MSIAnalyzer {
-    fn get_file_info(_data: &[u8]) -> HashMap<String, String> {
-        let mut info = HashMap::new();
-        info.insert("Format".to_string(), "MSI".to_string());
-        info
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        is_msi_file(data) && is_installer_database(data)
+    }
+
+    fn detection_confidence(&self, data: &[u8]) -> Confidence {
+        if has_string_pool(data) {
+            Confidence::High
+        } else {
+            Confidence::Medium
+        }
     }
 
-    fn parse_metadata(data: &[u8]) -> MetadataResult {
-        parse_msi_metadata(data)
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
+        let mut meta = parse_msi_metadata(data)?;
+        if let Some(valid) = meta.get("ProductVersion").map(|version| is_valid_product_version(version)) {
+            meta.insert("ProductVersionValid".into(), valid.to_string());
+        }
+        Ok(meta)
+    }
+
+    /// Lower bound from the compound-file header: the header sector plus every
+    /// FAT sector it lists and the first directory sector.
+    fn expected_size(&self, data: &[u8]) -> Option<usize> {
+        if data.len() < CFB_HEADER_SIZE {
+            return Some(CFB_HEADER_SIZE);
+        }
+        let sector_shift = get_u16(data, 0x1E);
+        if !(9..=12).contains(&sector_shift) {
+            return None;
+        }
+        let sector_size = 1usize << sector_shift;
+        let first_directory_sector = get_u32(data, 0x30);
+        let last_sector = (0..CFB_HEADER_DIFAT_ENTRIES)
+            .map(|idx| get_u32(data, 0x4C + idx * 4))
+            .chain([first_directory_sector])
+            .filter(|&sector| sector < CFB_MAX_REGULAR_SECTOR)
+            .max()?;
+        // Sector N starts after the header sector, at (N + 1) * sector_size
+        Some((last_sector as usize + 2) * sector_size)
     }
 }
 
@@ -30,6 +90,47 @@ pub fn is_msi_file(data: &[u8]) -> bool {
     data.len() >= MIN_MSI_SIGNATURE_SIZE && &data[0..MIN_MSI_SIGNATURE_SIZE] == MSI_SIGNATURE
 }
 
+/// False only for a readable compound file that is positively something else
+/// (a `.doc`, `.xls`, `.msg`...): neither an installer root CLSID nor any
+/// installer table stream. Unreadable ones stay MSI so the heuristic fallback
+/// still gets to run on damaged packages.
+fn is_installer_database(data: &[u8]) -> bool {
+    let Ok(cfb) = CompoundFile::open(Cursor::new(data)) else {
+        return true;
+    };
+    if compound::root_clsid(&cfb).is_some_and(|clsid| INSTALLER_CLSIDS.contains(&clsid.as_str())) {
+        return true;
+    }
+    let Ok(storage) = cfb.read_storage("/") else {
+        return true;
+    };
+    storage.into_iter().any(|entry| INSTALLER_STREAMS.contains(&decode_msi_stream_name(entry.name()).as_str()))
+}
+
+/// Returns true when the compound file carries both `_StringPool` and `_StringData`
+/// streams, which distinguishes a real MSI from any other OLE container.
+fn has_string_pool(data: &[u8]) -> bool {
+    let cfb = match CompoundFile::open(Cursor::new(data)) {
+        Ok(cfb) => cfb,
+        Err(_) => return false,
+    };
+    let storage = match cfb.read_storage("/") {
+        Ok(storage) => storage,
+        Err(_) => return false,
+    };
+
+    let mut has_pool = false;
+    let mut has_data = false;
+    for entry in storage {
+        match decode_msi_stream_name(entry.name()).as_str() {
+            "!_StringPool" => has_pool = true,
+            "!_StringData" => has_data = true,
+            _ => {}
+        }
+    }
+    has_pool && has_data
+}
+
 struct MsiTableReader<'a> {
     data: &'a [u8],
     row_size: usize,
@@ -59,6 +160,7 @@ fn parse_msi_metadata(buf: &[u8]) -> MetadataResult {
             return Ok(meta);
         }
     };
+    compound::insert_root_clsid(&cfb, &mut meta);
 
     // 1. Extract String Pool
     let mut string_pool = None;
@@ -75,11 +177,11 @@ fn parse_msi_metadata(buf: &[u8]) -> MetadataResult {
 
     for entry in &storage_entries {
         let decoded_name = decode_msi_stream_name(entry.name());
-        if decoded_name == "!StringPool" {
+        if decoded_name == "!_StringPool" {
             if let Ok(mut stream) = cfb.open_stream(entry.path()) {
                 let _ = stream.read_to_end(&mut pool_data);
             }
-        } else if decoded_name == "!StringData" {
+        } else if decoded_name == "!_StringData" {
             if let Ok(mut stream) = cfb.open_stream(entry.path()) {
                 let _ = stream.read_to_end(&mut data_data);
             }
@@ -91,6 +193,7 @@ fn parse_msi_metadata(buf: &[u8]) -> MetadataResult {
     }
 
     // 2. Extract Property Table
+    let mut properties = Vec::new();
     if let Some(ref pool) = string_pool {
         let idx_size = pool.index_size;
         for entry in &storage_entries {
@@ -102,17 +205,7 @@ fn parse_msi_metadata(buf: &[u8]) -> MetadataResult {
                     if let Ok(mut stream) = cfb.open_stream(entry.path()) {
                         let mut prop_data = Vec::new();
                         if stream.read_to_end(&mut prop_data).is_ok() {
-                            let row_size = idx_size * 2;
-                            let reader = MsiTableReader::new(&prop_data, row_size);
-                            for row in reader.rows() {
-                                let key_idx = read_idx(row, 0, idx_size);
-                                let val_idx = read_idx(row, idx_size, idx_size);
-                                if let (Some(key), Some(val)) = (pool.get(key_idx), pool.get(val_idx)) {
-                                    if !key.is_empty() && !val.is_empty() {
-                                        meta.insert(key.clone(), val.clone());
-                                    }
-                                }
-                            }
+                            properties = read_property_table(&prop_data, pool);
                         }
                     }
                 }
@@ -176,8 +269,32 @@ fn parse_msi_metadata(buf: &[u8]) -> MetadataResult {
         }
     }
 
+    let privileged = properties.iter().any(|(key, value)| key == "Privileged" && !value.is_empty());
+    if !properties.is_empty() {
+        let table: BTreeMap<&str, &str> =
+            properties.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect();
+        meta.insert("Properties".into(), serde_json::to_string(&table).unwrap_or_default());
+        if let Some(language) = table
+            .get("ProductLanguage")
+            .and_then(|lcid| lcid.trim().parse().ok())
+            .and_then(locale::language_from_lcid)
+        {
+            meta.insert("Language".into(), language);
+        }
+        meta.insert(
+            "InstallScope".into(),
+            install_scope(table.get("ALLUSERS").copied(), table.get("MSIINSTALLPERUSER").copied()).into(),
+        );
+        insert_arp_fields(&table, &mut meta);
+        meta.extend(properties);
+    }
+
     // 3. Extract Summary Information (Standard OLE)
-    extract_summary_info_enhanced(&mut cfb, &mut meta);
+    if let Some(word_count) = extract_summary_info_enhanced(&mut cfb, &mut meta) {
+        let per_machine = meta.get("InstallScope").map(String::as_str) == Some("PerMachine");
+        let requires_elevation = privileged || per_machine || word_count & ELEVATION_NOT_REQUIRED == 0;
+        meta.insert("RequiresElevation".into(), requires_elevation.to_string());
+    }
 
     // 4. Manual Fallbacks
 
@@ -189,6 +306,104 @@ fn parse_msi_metadata(buf: &[u8]) -> MetadataResult {
     Ok(meta)
 }
 
+/// Reads the `Property` table (two string columns). MSI tables are stored
+/// column by column: every key index first, then every value index.
+fn read_property_table(data: &[u8], pool: &MsiStringPool) -> Vec<(String, String)> {
+    let idx_size = pool.index_size;
+    let n_rows = data.len() / (idx_size * 2);
+    (0..n_rows)
+        .filter_map(|row| {
+            let key = pool.get(read_idx(data, row * idx_size, idx_size))?;
+            let value = pool.get(read_idx(data, (n_rows + row) * idx_size, idx_size))?;
+            (!key.is_empty() && !value.is_empty()).then(|| (key.clone(), value.clone()))
+        })
+        .collect()
+}
+
+/// Add/Remove Programs properties and the fields they are reported as.
+const ARP_FIELDS: [(&str, &str); 6] = [
+    ("ARPHELPLINK", "HelpLink"),
+    ("ARPHELPTELEPHONE", "HelpTelephone"),
+    ("ARPURLINFOABOUT", "AboutUrl"),
+    ("ARPURLUPDATEINFO", "UpdateUrl"),
+    ("ARPCONTACT", "Contact"),
+    // Kilobytes, as shown by Add/Remove Programs
+    ("ARPSIZE", "EstimatedSize"),
+];
+
+/// Promotes the Add/Remove Programs properties out of the Property table.
+/// `ARPNOREMOVE` only matters by presence, so it becomes `NoRemove: "true"`.
+fn insert_arp_fields(table: &BTreeMap<&str, &str>, meta: &mut HashMap<String, String>) {
+    for (property, field) in ARP_FIELDS {
+        if let Some(value) = table.get(property).map(|value| value.trim()).filter(|value| !value.is_empty()) {
+            meta.insert(field.into(), value.into());
+        }
+    }
+    if table.contains_key("ARPNOREMOVE") {
+        meta.insert("NoRemove".into(), "true".into());
+    }
+}
+
+/// Interprets `ALLUSERS`: unset or `0` installs per-user, `1` per-machine, and
+/// `2` is a dual-purpose package that goes per-machine unless `MSIINSTALLPERUSER=1`.
+fn install_scope(all_users: Option<&str>, install_per_user: Option<&str>) -> &'static str {
+    match all_users.map(str::trim) {
+        Some("1") => "PerMachine",
+        Some("2") if install_per_user.map(str::trim) == Some("1") => "PerUser",
+        Some("2") => "PerMachine",
+        _ => "PerUser",
+    }
+}
+
+/// Upper bounds of the `major.minor.build` fields of an MSI `ProductVersion`,
+/// plus the optional fourth field Windows Installer ignores.
+const PRODUCT_VERSION_LIMITS: [u32; 4] = [255, 255, 65535, 65535];
+
+/// Whether `version` is a well-formed MSI `ProductVersion`. A failure usually
+/// means the heuristic fallback scraped some other dotted number.
+fn is_valid_product_version(version: &str) -> bool {
+    let fields: Vec<&str> = version.trim().split('.').collect();
+    (3..=PRODUCT_VERSION_LIMITS.len()).contains(&fields.len())
+        && fields.iter().zip(PRODUCT_VERSION_LIMITS).all(|(field, limit)| {
+            !field.is_empty()
+                && field.bytes().all(|b| b.is_ascii_digit())
+                && field.parse::<u32>().is_ok_and(|value| value <= limit)
+        })
+}
+
+/// The complete MSI `Property` table, including vendor-specific properties.
+/// Empty when `data` is not an MSI with a readable string pool.
+pub fn msi_properties(data: &[u8]) -> HashMap<String, String> {
+    let Ok(mut cfb) = CompoundFile::open(Cursor::new(data)) else {
+        return HashMap::new();
+    };
+    let storage_entries: Vec<_> = match cfb.read_storage("/") {
+        Ok(storage) => storage.collect(),
+        Err(_) => return HashMap::new(),
+    };
+
+    let mut streams: HashMap<String, Vec<u8>> = HashMap::new();
+    for entry in &storage_entries {
+        let name = decode_msi_stream_name(entry.name());
+        let name = name.trim_start_matches('!').trim_start_matches('\u{0005}');
+        if matches!(name, "_StringPool" | "_StringData" | "Property") {
+            let mut stream_data = Vec::new();
+            if let Ok(mut stream) = cfb.open_stream(entry.path()) {
+                let _ = stream.read_to_end(&mut stream_data);
+            }
+            streams.insert(name.to_string(), stream_data);
+        }
+    }
+
+    match (streams.get("_StringPool"), streams.get("_StringData"), streams.get("Property")) {
+        (Some(pool_data), Some(string_data), Some(prop_data)) if !pool_data.is_empty() => {
+            let pool = MsiStringPool::from_streams(pool_data, string_data);
+            read_property_table(prop_data, &pool).into_iter().collect()
+        }
+        _ => HashMap::new(),
+    }
+}
+
 fn read_idx(data: &[u8], offset: usize, size: usize) -> usize {
     if data.len() < offset + size {
         return 0;
@@ -204,40 +419,41 @@ fn read_idx(data: &[u8], offset: usize, size: usize) -> usize {
     }
 }
 
-fn extract_summary_info_enhanced(cfb: &mut CfbFile, meta: &mut HashMap<String, String>) {
-    if let Ok(mut stream) = cfb.open_stream("\u{0005}SummaryInformation") {
-        let mut buffer = Vec::new();
-        if stream.read_to_end(&mut buffer).is_ok() {
-            extract_ole_properties(&buffer, meta);
-        }
-    }
+/// Returns the summary `Word Count` (PID 15) when present.
+fn extract_summary_info_enhanced(cfb: &mut CfbFile, meta: &mut HashMap<String, String>) -> Option<u32> {
+    let mut stream = cfb.open_stream("\u{0005}SummaryInformation").ok()?;
+    let mut buffer = Vec::new();
+    stream.read_to_end(&mut buffer).ok()?;
+    extract_ole_properties(&buffer, meta)
 }
 
 fn get_u32(buf: &[u8], offset: usize) -> u32 {
-    if offset + 4 > buf.len() { return 0; }
+    if offset.saturating_add(4) > buf.len() { return 0; }
     u32::from_le_bytes([buf[offset], buf[offset+1], buf[offset+2], buf[offset+3]])
 }
 
 fn get_u16(buf: &[u8], offset: usize) -> u16 {
-    if offset + 2 > buf.len() { return 0; }
+    if offset.saturating_add(2) > buf.len() { return 0; }
     u16::from_le_bytes([buf[offset], buf[offset+1]])
 }
 
-fn extract_ole_properties(buffer: &[u8], meta: &mut HashMap<String, String>) {
+fn extract_ole_properties(buffer: &[u8], meta: &mut HashMap<String, String>) -> Option<u32> {
     if buffer.len() < 48 || get_u16(buffer, 0) != 0xFFFE {
-        return;
+        return None;
     }
 
     let num_sections = get_u32(buffer, 24);
-    if num_sections == 0 { return; }
+    if num_sections == 0 { return None; }
 
     let section_offset = get_u32(buffer, 44) as usize;
-    if section_offset + 8 > buffer.len() { return; }
+    if section_offset.saturating_add(8) > buffer.len() { return None; }
 
     let section_size = get_u32(buffer, section_offset) as usize;
     let prop_count = get_u32(buffer, section_offset + 4) as usize;
 
-    if section_offset + section_size > buffer.len() { return; }
+    if section_offset.saturating_add(section_size) > buffer.len() { return None; }
+
+    let mut word_count = None;
 
     let entry_base = section_offset + 8;
     for i in 0..prop_count {
@@ -246,37 +462,35 @@ fn extract_ole_properties(buffer: &[u8], meta: &mut HashMap<String, String>) {
 
         let pid = get_u32(buffer, entry_offset);
         let prop_offset = get_u32(buffer, entry_offset + 4) as usize;
-        let abs_prop_offset = section_offset + prop_offset;
+        let abs_prop_offset = section_offset.saturating_add(prop_offset);
 
-        if abs_prop_offset + 4 > buffer.len() { continue; }
+        if abs_prop_offset.saturating_add(4) > buffer.len() { continue; }
 
         let prop_type = get_u16(buffer, abs_prop_offset);
 
         match pid {
+            PID_WORD_COUNT if prop_type == VT_I4 => {
+                word_count = Some(get_u32(buffer, abs_prop_offset + 4));
+            }
             2 | 3 | 4 | 5 | 6 | 9 => {
                 let s = if prop_type == 30 { // VT_LPSTR
                     let str_len = get_u32(buffer, abs_prop_offset + 4) as usize;
                     let str_start = abs_prop_offset + 8;
-                    if str_start + str_len <= buffer.len() {
-                        String::from_utf8_lossy(&buffer[str_start..str_start + str_len])
-                            .trim_matches(char::from(0))
-                            .to_string()
-                    } else {
-                        continue;
+                    match buffer.get(str_start..str_start.saturating_add(str_len)) {
+                        Some(bytes) => String::from_utf8_lossy(bytes).trim_matches(char::from(0)).to_string(),
+                        None => continue,
                     }
                 } else if prop_type == 31 { // VT_LPWSTR (UTF-16)
                     let str_chars = get_u32(buffer, abs_prop_offset + 4) as usize;
                     let str_start = abs_prop_offset + 8;
-                    if str_start + (str_chars * 2) <= buffer.len() {
-                        let utf16_data: Vec<u16> = buffer[str_start..str_start + (str_chars * 2)]
-                            .chunks_exact(2)
-                            .map(|c| u16::from_le_bytes([c[0], c[1]]))
-                            .collect();
-                        String::from_utf16_lossy(&utf16_data)
-                            .trim_matches(char::from(0))
-                            .to_string()
-                    } else {
-                        continue;
+                    let str_end = str_start.saturating_add(str_chars.saturating_mul(2));
+                    match buffer.get(str_start..str_end) {
+                        Some(bytes) => {
+                            let utf16_data: Vec<u16> =
+                                bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+                            String::from_utf16_lossy(&utf16_data).trim_matches(char::from(0)).to_string()
+                        }
+                        None => continue,
                     }
                 } else {
                     continue;
@@ -294,6 +508,14 @@ fn extract_ole_properties(buffer: &[u8], meta: &mut HashMap<String, String>) {
                     _ => continue,
                 };
 
+                let s = if pid == 9 {
+                    let package_code = normalize_guid(&s).unwrap_or(s);
+                    meta.insert("PackageId".into(), package_id(&package_code));
+                    package_code
+                } else {
+                    s
+                };
+
                 // Only overwrite if it's a primary summary field or if structured extraction was empty
                 if pid == 2 || pid == 5 || pid == 6 || pid == 9 || !meta.contains_key(key) {
                     meta.insert(key.into(), s);
@@ -302,6 +524,8 @@ fn extract_ole_properties(buffer: &[u8], meta: &mut HashMap<String, String>) {
             _ => {}
         }
     }
+
+    word_count
 }
 
 fn is_valid_metadata_string(s: &str) -> bool {
@@ -348,14 +572,21 @@ fn is_valid_metadata_string(s: &str) -> bool {
 }
 
 fn extract_msi_properties(buf: &[u8], meta: &mut HashMap<String, String>) {
+    let tagging = provenance::Tagging::begin(Source::Heuristic, meta);
     let buf_str = String::from_utf8_lossy(buf);
 
-    if let Some(product_code) = extract_guid(&buf_str, "ProductCode") {
-        meta.insert("ProductCode".into(), product_code);
+    if !meta.contains_key("ProductCode") {
+        if let Some(product_code) = extract_guid(&buf_str, "ProductCode") {
+            meta.insert("ProductCode".into(), product_code);
+        }
     }
 
-    if let Some(upgrade_code) = extract_guid(&buf_str, "UpgradeCode") {
-        meta.insert("UpgradeCode".into(), upgrade_code);
+    if !meta.contains_key("UpgradeCode") {
+        let upgrade_code = extract_guid(&buf_str, "UpgradeCode")
+            .filter(|code| meta.get("ProductCode") != Some(code));
+        if let Some(upgrade_code) = upgrade_code {
+            meta.insert("UpgradeCode".into(), upgrade_code);
+        }
     }
 
     if !meta.contains_key("ProductVersion") {
@@ -383,6 +614,7 @@ fn extract_msi_properties(buf: &[u8], meta: &mut HashMap<String, String>) {
     } else if buf_str.contains("Advanced Installer") {
         meta.insert("InstallerFramework".into(), "Advanced Installer".into());
     }
+    tagging.finish(meta);
 }
 
 fn extract_property_value(buf: &[u8], property_name: &[u8]) -> Option<String> {
@@ -415,63 +647,160 @@ fn extract_property_value(buf: &[u8], property_name: &[u8]) -> Option<String> {
 
 #[inline]
 fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if !budget::charge(haystack.len()) {
+        return None;
+    }
     haystack.windows(needle.len()).position(|window| window == needle)
 }
 
-fn extract_guid(data: &str, _prefix: &str) -> Option<String> {
-    let guid_pattern = regex_like_guid_search(data.as_bytes());
-    guid_pattern
+/// Canonical `{XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX}` form of a GUID written
+/// with or without braces, in any case.
+fn normalize_guid(value: &str) -> Option<String> {
+    let value = value.trim();
+    let bare = value.strip_prefix('{').and_then(|v| v.strip_suffix('}')).unwrap_or(value);
+    let braced = format!("{{{}}}", bare);
+    regex_like_guid_search(braced.as_bytes())
+        .filter(|(offset, _)| *offset == 0 && braced.len() == 38)
+        .map(|(_, guid)| guid)
 }
 
-fn regex_like_guid_search(data: &[u8]) -> Option<String> {
-    for i in 0..data.len().saturating_sub(38) {
-        if data[i] == b'{' && data[i + 37] == b'}'
-            && data[i + 9] == b'-' && data[i + 14] == b'-' &&
-               data[i + 19] == b'-' && data[i + 24] == b'-' {
-                if let Ok(guid) = std::str::from_utf8(&data[i..i+38]) {
-                    if guid.chars().all(|c| c.is_ascii_hexdigit() || c == '{' || c == '}' || c == '-') {
-                        return Some(guid.to_uppercase());
-                    }
-                }
-            }
-    }
-    None
+/// Short stable identifier for a package build: the first 16 hex digits of
+/// the SHA-256 of its normalized `PackageCode`.
+fn package_id(package_code: &str) -> String {
+    let mut id = digest::to_hex(&digest::sha256(package_code.as_bytes()));
+    id.truncate(16);
+    id
 }
 
-fn extract_version_pattern(data: &str) -> Option<String> {
+/// How far past its label a GUID value may start.
+const GUID_LABEL_WINDOW: usize = 64;
+
+/// The GUID that follows a `label` (e.g. `ProductCode`) most closely, so
+/// unrelated GUIDs elsewhere in the file are not mistaken for its value.
+fn extract_guid(data: &str, label: &str) -> Option<String> {
     let bytes = data.as_bytes();
-    let mut versions = Vec::new();
+    data.match_indices(label)
+        .take_while(|_| budget::charge(GUID_LABEL_WINDOW + 38))
+        .filter_map(|(pos, _)| {
+            let start = pos + label.len();
+            let window = &bytes[start..(start + GUID_LABEL_WINDOW + 38).min(bytes.len())];
+            regex_like_guid_search(window).filter(|(offset, _)| *offset <= GUID_LABEL_WINDOW)
+        })
+        .min_by_key(|(offset, _)| *offset)
+        .map(|(_, guid)| guid)
+}
 
-    for i in 0..bytes.len().saturating_sub(5) {
-        if bytes[i].is_ascii_digit() && bytes[i + 1] == b'.' && bytes[i + 2].is_ascii_digit() {
-            let mut version = String::new();
-            let mut j = i;
+/// First brace-delimited GUID in `data`, uppercased, with its offset.
+fn regex_like_guid_search(data: &[u8]) -> Option<(usize, String)> {
+    data.windows(38).enumerate().find_map(|(i, candidate)| {
+        let well_formed = candidate[0] == b'{'
+            && candidate[37] == b'}'
+            && candidate[1..37].iter().enumerate().all(|(j, &b)| match j + 1 {
+                9 | 14 | 19 | 24 => b == b'-',
+                _ => b.is_ascii_hexdigit(),
+            });
+        well_formed.then(|| (i, String::from_utf8_lossy(candidate).to_uppercase()))
+    })
+}
 
-            while j < bytes.len() && (bytes[j].is_ascii_digit() || bytes[j] == b'.') {
-                version.push(bytes[j] as char);
-                j += 1;
+/// Labels that precede the product version in property tables and plists.
+const VERSION_MARKERS: [&str; 2] = ["ProductVersion", "CFBundleShortVersionString"];
+/// How far from a marker a version still counts as its value.
+const VERSION_MARKER_WINDOW: usize = 256;
 
-                if version.len() > 20 {
-                    break;
-                }
-            }
+/// Every dotted version (2 to 4 numeric parts) in `bytes`, with its offset.
+fn version_candidates(bytes: &[u8]) -> Vec<(usize, String)> {
+    let mut versions = Vec::new();
+    let mut i = 0;
 
-            let parts: Vec<&str> = version.split('.').collect();
-            if parts.len() >= 2 && parts.len() <= 4
-                && parts.iter().all(|p| !p.is_empty() && p.parse::<u32>().is_ok()) {
-                    versions.push(version);
-                }
+    while i + 2 < bytes.len() {
+        if !(bytes[i].is_ascii_digit() && bytes[i + 1] == b'.' && bytes[i + 2].is_ascii_digit()) {
+            i += 1;
+            continue;
         }
+        let end = bytes[i..]
+            .iter()
+            .position(|&b| !(b.is_ascii_digit() || b == b'.'))
+            .map_or(bytes.len(), |len| i + len);
+        let version = String::from_utf8_lossy(&bytes[i..end.min(i + 20)]).into_owned();
+
+        let parts: Vec<&str> = version.split('.').collect();
+        if (2..=4).contains(&parts.len()) && parts.iter().all(|p| !p.is_empty() && p.parse::<u32>().is_ok()) {
+            versions.push((i, version));
+        }
+        // Skip the rest of the run so "1.2.3" does not also yield "2.3"
+        i = end;
     }
 
-    // Prefer versions with more dots and not starting with "3." if others exist (common build numbers vs product version)
-    versions.sort_by_key(|v| {
-        let dots = v.chars().filter(|&c| c == '.').count();
-        let is_low_major = v.starts_with("3."); // Heuristic: many MSIs have internal version 3.x
-        (dots, !is_low_major)
-    });
+    versions
+}
 
-    versions.pop()
+/// Picks the version closest to a `ProductVersion`-style marker, falling back
+/// to the most dotted version when no marker has one nearby.
+fn extract_version_pattern(data: &str) -> Option<String> {
+    if !budget::charge(data.len()) {
+        return None;
+    }
+    let versions = version_candidates(data.as_bytes());
+    let markers: Vec<usize> = VERSION_MARKERS
+        .iter()
+        .flat_map(|marker| data.match_indices(marker).map(|(pos, _)| pos + marker.len()))
+        .collect();
+    if !budget::charge(versions.len().saturating_mul(markers.len())) {
+        return None;
+    }
+
+    let nearest = versions
+        .iter()
+        .filter_map(|(pos, version)| {
+            let distance = markers.iter().map(|&marker| pos.abs_diff(marker)).min()?;
+            (distance <= VERSION_MARKER_WINDOW).then_some((distance, version))
+        })
+        .min_by_key(|(distance, _)| *distance);
+    if let Some((_, version)) = nearest {
+        return Some(version.clone());
+    }
+
+    versions
+        .into_iter()
+        .map(|(_, version)| version)
+        .max_by_key(|version| version.matches('.').count())
+}
+
+/// Set in the high word of the `_StringPool` codepage when string indices are 3 bytes wide.
+const LONG_STRING_REFS: u16 = 0x8000;
+const CODEPAGE_UTF16: u32 = 1200;
+const CODEPAGE_WINDOWS_1252: u32 = 1252;
+
+/// Windows-1252 characters for bytes 0x80..=0x9F; the rest match Latin-1.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{FFFD}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{FFFD}', '\u{017D}', '\u{FFFD}',
+    '\u{FFFD}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{FFFD}', '\u{017E}', '\u{0178}',
+];
+
+/// Decodes one `_StringData` entry. UTF-8 (codepage 65001, or plain ASCII
+/// under any codepage) is taken as is; other single-byte codepages are only
+/// mapped for Windows-1252.
+fn decode_pool_string(bytes: &[u8], codepage: u32) -> String {
+    if codepage == CODEPAGE_UTF16 {
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+        return String::from_utf16_lossy(&units);
+    }
+    if let Ok(s) = std::str::from_utf8(bytes) {
+        return s.to_string();
+    }
+    if codepage == CODEPAGE_WINDOWS_1252 {
+        return bytes
+            .iter()
+            .map(|&b| match b {
+                0x80..=0x9F => WINDOWS_1252_HIGH[(b - 0x80) as usize],
+                _ => b as char,
+            })
+            .collect();
+    }
+    String::from_utf8_lossy(bytes).into_owned()
 }
 
 struct MsiStringPool {
@@ -480,22 +809,37 @@ struct MsiStringPool {
 }
 
 impl MsiStringPool {
+    /// `_StringPool` starts with the codepage (the top bit of its high word
+    /// flags 3-byte string indices), followed by a `(length, refcount)` pair of
+    /// u16s per string. Lengths are byte counts in `_StringData`, whatever the
+    /// codepage.
     fn from_streams(pool_data: &[u8], string_data: &[u8]) -> Self {
         if pool_data.len() < 4 {
             return Self { strings: Vec::new(), index_size: 2 };
         }
 
-        let codepage = u16::from_le_bytes([pool_data[0], pool_data[1]]);
-        let flags = u16::from_le_bytes([pool_data[2], pool_data[3]]);
-        let index_size = if (flags & 0x8000) != 0 { 3 } else { 2 };
+        let high_word = u16::from_le_bytes([pool_data[2], pool_data[3]]);
+        let index_size = if (high_word & LONG_STRING_REFS) != 0 { 3 } else { 2 };
+        let codepage = u32::from(u16::from_le_bytes([pool_data[0], pool_data[1]]))
+            | (u32::from(high_word & !LONG_STRING_REFS) << 16);
 
         let n_entries = (pool_data.len() - 4) / 4;
         let mut strings = Vec::with_capacity(n_entries);
         let mut current_offset = 0;
-
-        for i in 0..n_entries {
-            let offset = 4 + (i * 4);
-            let length = u16::from_le_bytes([pool_data[offset + 2], pool_data[offset + 3]]) as usize;
+        let mut entries = pool_data[4..].chunks_exact(4);
+
+        while let Some(entry) = entries.next() {
+            let mut length = u16::from_le_bytes([entry[0], entry[1]]) as usize;
+            let refcount = u16::from_le_bytes([entry[2], entry[3]]);
+
+            // A zero length with a non-zero refcount marks a string of 64 KiB
+            // or more: the next entry holds its full 32-bit length, and both
+            // entries together make up a single string id.
+            if length == 0 && refcount != 0 {
+                length = entries
+                    .next()
+                    .map_or(0, |next| u32::from_le_bytes([next[0], next[1], next[2], next[3]]) as usize);
+            }
 
             if length == 0 {
                 strings.push(String::new());
@@ -503,21 +847,12 @@ impl MsiStringPool {
             }
 
             let end = current_offset + length;
-            if end <= string_data.len() {
-                let s_bytes = &string_data[current_offset..end];
-                let s = if codepage == 1200 {
-                    let utf16_data: Vec<u16> = s_bytes.chunks_exact(2)
-                        .map(|c| u16::from_le_bytes([c[0], c[1]]))
-                        .collect();
-                    String::from_utf16_lossy(&utf16_data).to_string()
-                } else {
-                    String::from_utf8_lossy(s_bytes).to_string()
-                };
-                strings.push(s);
-            } else {
-                strings.push(String::new());
-            }
-            current_offset += length;
+            let s = string_data
+                .get(current_offset..end)
+                .map(|s_bytes| decode_pool_string(s_bytes, codepage))
+                .unwrap_or_default();
+            strings.push(s);
+            current_offset = end;
         }
 
         Self { strings, index_size }
@@ -539,15 +874,14 @@ fn decode_msi_stream_name(name: &str) -> String {
     let mut decoded = String::new();
     for c in name.chars() {
         let n = c as u32;
-        if (0x3800..0x4840).contains(&n) {
+        if n == TABLE_STREAM_MARKER {
+            decoded.push('!');
+        } else if (0x3800..0x4800).contains(&n) {
             let n = n - 0x3800;
-            let char1 = (n & 0x3F) as u8;
-            let char2 = ((n >> 6) & 0x3F) as u8;
-
-            decoded.push(decode_char(char1));
-            if char2 != 0 {
-                decoded.push(decode_char(char2));
-            }
+            decoded.push(decode_char((n & 0x3F) as u8));
+            decoded.push(decode_char(((n >> 6) & 0x3F) as u8));
+        } else if (0x4800..0x4840).contains(&n) {
+            decoded.push(decode_char((n - 0x4800) as u8));
         } else {
             decoded.push(c);
         }
@@ -577,17 +911,158 @@ mod msi_tests {
 
         let mangled = "\u{3EF3}\u{3E58}\u{3ECE}\u{409D}";
         assert_eq!(decode_msi_stream_name(mangled), "Property");
+        assert_eq!(decode_msi_stream_name(&encode_msi_stream_name("!_StringPool")), "!_StringPool");
+        assert_eq!(decode_msi_stream_name(&encode_msi_stream_name("!Binary0")), "!Binary0");
+    }
+
+    /// Inverse of `decode_msi_stream_name` for building test packages.
+    fn encode_msi_stream_name(name: &str) -> String {
+        let (marker, name) = match name.strip_prefix('!') {
+            Some(rest) => (char::from_u32(TABLE_STREAM_MARKER), rest),
+            None => (None, name),
+        };
+        let codes: Vec<u32> = name
+            .chars()
+            .filter_map(|c| (0u8..64).find(|&code| decode_char(code) == c))
+            .map(u32::from)
+            .collect();
+        let encoded = codes.chunks(2).filter_map(|pair| match pair {
+            [a, b] => char::from_u32(0x3800 + a + (b << 6)),
+            [a] => char::from_u32(0x4800 + a),
+            _ => None,
+        });
+        marker.into_iter().chain(encoded).collect()
+    }
+
+    #[test]
+    fn test_office_compound_file_is_not_msi() {
+        let word = [0x06, 0x09, 0x02, 0x00, 0, 0, 0, 0, 0xC0, 0, 0, 0, 0, 0, 0, 0x46];
+        let doc = compound::tests::compound_file(&["WordDocument", "\u{5}SummaryInformation"], Some(word));
+        assert!(is_msi_file(&doc));
+        assert!(!MSIAnalyzer.sniff(&doc));
+
+        let tables = encode_msi_stream_name("!_Tables");
+        let msi = compound::tests::compound_file(&[tables.as_str()], None);
+        assert!(MSIAnalyzer.sniff(&msi));
+
+        let msi_clsid = [0x84, 0x10, 0x0C, 0x00, 0, 0, 0, 0, 0xC0, 0, 0, 0, 0, 0, 0, 0x46];
+        let msi = compound::tests::compound_file(&[], Some(msi_clsid));
+        assert!(MSIAnalyzer.sniff(&msi));
+        let meta = parse_msi_metadata(&msi).unwrap_or_default();
+        assert_eq!(meta.get("RootClsid").map(String::as_str), Some(INSTALLER_CLSIDS[0]));
+        assert_eq!(meta.get("CompoundFileType").map(String::as_str), Some("MSI Package"));
+    }
+
+    #[test]
+    fn test_read_property_table_is_column_major() {
+        let pool = MsiStringPool {
+            strings: ["ALLUSERS", "1", "REBOOT", "ReallySuppress"].map(String::from).to_vec(),
+            index_size: 2,
+        };
+        // Keys (1, 3) followed by values (2, 4)
+        let table = [1, 0, 3, 0, 2, 0, 4, 0];
+
+        assert_eq!(
+            read_property_table(&table, &pool),
+            vec![
+                ("ALLUSERS".to_string(), "1".to_string()),
+                ("REBOOT".to_string(), "ReallySuppress".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalize_guid_and_package_id() {
+        let canonical = "{A1B2C3D4-0000-4000-8000-00000000000F}";
+        for raw in ["a1b2c3d4-0000-4000-8000-00000000000f", " {A1B2C3D4-0000-4000-8000-00000000000f} "] {
+            assert_eq!(normalize_guid(raw).as_deref(), Some(canonical));
+        }
+        assert_eq!(normalize_guid("not-a-guid"), None);
+        assert_eq!(package_id(canonical).len(), 16);
+        assert_eq!(package_id(canonical), package_id(&normalize_guid("a1b2c3d4-0000-4000-8000-00000000000f").unwrap_or_default()));
+    }
+
+    #[test]
+    fn test_insert_arp_fields() {
+        let table: BTreeMap<&str, &str> =
+            [("ARPHELPLINK", "https://example.com/help"), ("ARPSIZE", " 2048 "), ("ARPNOREMOVE", "1")].into();
+        let mut meta = HashMap::new();
+        insert_arp_fields(&table, &mut meta);
+
+        assert_eq!(meta.get("HelpLink").map(String::as_str), Some("https://example.com/help"));
+        assert_eq!(meta.get("EstimatedSize").map(String::as_str), Some("2048"));
+        assert_eq!(meta.get("NoRemove").map(String::as_str), Some("true"));
+        assert!(!meta.contains_key("AboutUrl"));
+    }
+
+    #[test]
+    fn test_is_valid_product_version() {
+        assert!(is_valid_product_version("1.2.3"));
+        assert!(is_valid_product_version("255.255.65535.7"));
+        assert!(!is_valid_product_version("1.2"));
+        assert!(!is_valid_product_version("256.0.0"));
+        assert!(!is_valid_product_version("1.0.70000"));
+        assert!(!is_valid_product_version("1.0.0-beta"));
+        assert!(!is_valid_product_version("10.0.17763.1.5"));
+    }
+
+    #[test]
+    fn test_install_scope() {
+        assert_eq!(install_scope(None, None), "PerUser");
+        assert_eq!(install_scope(Some("0"), None), "PerUser");
+        assert_eq!(install_scope(Some("1"), Some("1")), "PerMachine");
+        assert_eq!(install_scope(Some("2"), None), "PerMachine");
+        assert_eq!(install_scope(Some("2"), Some("1")), "PerUser");
+    }
+
+    #[test]
+    fn test_extract_ole_properties_word_count() {
+        let mut buffer = vec![0u8; 72];
+        buffer[0..2].copy_from_slice(&0xFFFEu16.to_le_bytes());
+        buffer[24..28].copy_from_slice(&1u32.to_le_bytes());
+        buffer[44..48].copy_from_slice(&48u32.to_le_bytes());
+        // Section: size, one property (PID 15 at section offset 16) holding VT_I4 10
+        buffer[48..52].copy_from_slice(&24u32.to_le_bytes());
+        buffer[52..56].copy_from_slice(&1u32.to_le_bytes());
+        buffer[56..60].copy_from_slice(&PID_WORD_COUNT.to_le_bytes());
+        buffer[60..64].copy_from_slice(&16u32.to_le_bytes());
+        buffer[64..66].copy_from_slice(&VT_I4.to_le_bytes());
+        buffer[68..72].copy_from_slice(&10u32.to_le_bytes());
+
+        let word_count = extract_ole_properties(&buffer, &mut HashMap::new());
+        assert_eq!(word_count, Some(10));
+        assert_ne!(word_count.unwrap_or_default() & ELEVATION_NOT_REQUIRED, 0);
+    }
+
+    #[test]
+    fn test_extract_version_pattern_prefers_marked_version() {
+        let data = "Build 10.0.17763.1 ... ProductVersion\u{0}3.2.1\u{0}Other 4.5.6.7";
+        assert_eq!(extract_version_pattern(data).as_deref(), Some("3.2.1"));
+        assert_eq!(extract_version_pattern("v1.2 and 3.4.5").as_deref(), Some("3.4.5"));
+        assert_eq!(extract_version_pattern("no version here"), None);
+    }
+
+    #[test]
+    fn test_extract_guid_uses_label() {
+        let data = "{00000000-0000-0000-0000-000000000001}\u{0}ProductCode\u{0}\
+                    {a1b2c3d4-0000-0000-0000-000000000002}UpgradeCode{A1B2C3D4-0000-0000-0000-000000000003}";
+        assert_eq!(
+            extract_guid(data, "ProductCode").as_deref(),
+            Some("{A1B2C3D4-0000-0000-0000-000000000002}")
+        );
+        assert_eq!(
+            extract_guid(data, "UpgradeCode").as_deref(),
+            Some("{A1B2C3D4-0000-0000-0000-000000000003}")
+        );
+        assert_eq!(extract_guid(data, "PackageCode"), None);
     }
 
     #[test]
     fn test_msi_string_pool() {
-        // Header: 0, 0, 0, 0 (n_entries, flags)
-        // Entry 1: refcount 1, len 5
-        // Entry 2: refcount 1, len 5
         let pool = vec![
-            0, 0, 0, 0, // Header (n_entries=0, flags=0) - n_entries is calculated from pool_data.len()
-            1, 0, 5, 0, // Entry 1: refcount 1, len 5
-            1, 0, 5, 0, // Entry 2: refcount 1, len 5
+            0, 0, 0, 0, // Header (codepage 0, 2-byte indices) - entry count comes from pool_data.len()
+            5, 0, 1, 0, // Entry 1: len 5, refcount 1
+            5, 0, 1, 0, // Entry 2: len 5, refcount 1
         ];
         let data = b"Test1Test2";
         let sp = MsiStringPool::from_streams(&pool, data);
@@ -597,4 +1072,35 @@ mod msi_tests {
         assert!(sp.get(0).is_none());
         assert!(sp.get(3).is_none());
     }
+
+    #[test]
+    fn test_msi_string_pool_long_string() {
+        let long = "x".repeat(70_000);
+        let mut pool = vec![0, 0, 0, 0];
+        pool.extend_from_slice(&[0, 0, 1, 0]); // Long string marker...
+        pool.extend_from_slice(&(long.len() as u32).to_le_bytes()); // ...and its 32-bit length
+        pool.extend_from_slice(&[2, 0, 1, 0]);
+        let data = [long.as_bytes(), b"OK"].concat();
+
+        let sp = MsiStringPool::from_streams(&pool, &data);
+        assert_eq!(sp.get(1).map(String::len), Some(70_000));
+        assert_eq!(sp.get(2).map(String::as_str), Some("OK"));
+        assert!(sp.get(3).is_none());
+    }
+
+    #[test]
+    fn test_msi_string_pool_codepages() {
+        // Windows-1252 pool with 3-byte indices: "Café" then "OK"
+        let pool = [0xE4, 0x04, 0x00, 0x80, 4, 0, 1, 0, 2, 0, 1, 0];
+        let sp = MsiStringPool::from_streams(&pool, b"Caf\xE9OK");
+        assert_eq!(sp.index_size, 3);
+        assert_eq!(sp.get(1).map(String::as_str), Some("Café"));
+        assert_eq!(sp.get(2).map(String::as_str), Some("OK"));
+
+        // UTF-16 pool: lengths are still byte counts
+        let pool = [0xB0, 0x04, 0x00, 0x00, 4, 0, 1, 0, 2, 0, 1, 0];
+        let sp = MsiStringPool::from_streams(&pool, b"\xE9\x00A\x00B\x00");
+        assert_eq!(sp.get(1).map(String::as_str), Some("éA"));
+        assert_eq!(sp.get(2).map(String::as_str), Some("B"));
+    }
 }
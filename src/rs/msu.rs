@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+use flate2::{Decompress, FlushDecompress};
+use crate::{budget, FileAnalyzer, FormatInfo, MetadataResult};
+
+// Cabinet file format (MS-CAB): CFHEADER, CFFOLDER, CFFILE and CFDATA
+const CAB_SIGNATURE: &[u8] = b"MSCF";
+const CAB_HEADER_SIZE: usize = 36;
+const CAB_FLAG_PREV_CABINET: u16 = 0x0001;
+const CAB_FLAG_NEXT_CABINET: u16 = 0x0002;
+const CAB_FLAG_RESERVE_PRESENT: u16 = 0x0004;
+const CFFOLDER_SIZE: usize = 8;
+const CFFILE_SIZE: usize = 16;
+const CFDATA_SIZE: usize = 8;
+const COMPRESSION_MASK: u16 = 0x000F;
+const COMPRESSION_NONE: u16 = 0;
+const COMPRESSION_MSZIP: u16 = 1;
+const MSZIP_SIGNATURE: &[u8] = b"CK";
+/// MSZIP blocks may refer back to the previous 32 KiB of output.
+const MSZIP_WINDOW: usize = 32 * 1024;
+
+/// Largest properties file worth decompressing a folder for.
+const MAX_PROPERTIES_SIZE: usize = 64 * 1024;
+
+/// Suffix of the text file describing the update, next to the update's own
+/// `Windows10.0-KB5034441-x64.cab`.
+const PROPERTIES_SUFFIX: &str = "-pkgProperties.txt";
+
+/// `pkgProperties.txt` keys and the metadata keys they map to.
+const PROPERTY_KEYS: [(&str, &str); 9] = [
+    ("KB Article Number", "KbNumber"),
+    ("Product Name", "TargetOS"),
+    ("Processor Architecture", "Architecture"),
+    ("Package Type", "PackageType"),
+    ("Installation Type", "InstallationType"),
+    ("Applies to", "AppliesTo"),
+    ("Company", "Publisher"),
+    ("File Version", "ProductVersion"),
+    ("Support Link", "SupportLink"),
+];
+
+/// Architecture suffixes of update file names (`...-KB5034441-x64.cab`).
+const NAME_ARCHITECTURES: [(&str, &str); 4] = [("-x64", "amd64"), ("-x86", "x86"), ("-arm64", "arm64"), ("-arm", "arm")];
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "MSU",
+    mime_type: "application/vnd.ms-cab-compressed",
+    extensions: &["msu"],
+};
+
+/// Windows Update standalone packages: a cabinet holding the update's own
+/// cabinet, a `WSUSSCAN.cab` and a `*-pkgProperties.txt` description.
+pub struct MSUAnalyzer;
+
+impl FileAnalyzer for MSUAnalyzer {
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        is_msu_file(data)
+    }
+
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
+        parse_msu_metadata(data)
+    }
+
+    fn expected_size(&self, data: &[u8]) -> Option<usize> {
+        read_u32(data, 8).map(|size| size as usize)
+    }
+
+    fn file_list(&self, data: &[u8], limit: usize) -> Vec<String> {
+        Cabinet::parse(data)
+            .map(|cab| cab.files.into_iter().take(limit).map(|file| file.name).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// A cabinet whose file list looks like a Windows Update package.
+pub fn is_msu_file(data: &[u8]) -> bool {
+    data.starts_with(CAB_SIGNATURE)
+        && Cabinet::parse(data).is_some_and(|cab| {
+            cab.files.iter().any(|file| {
+                file.name.ends_with(PROPERTIES_SUFFIX) || file.name.eq_ignore_ascii_case("WSUSSCAN.cab")
+            })
+        })
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+struct Folder {
+    first_data_block: usize,
+    data_blocks: u16,
+    compression: u16,
+}
+
+struct CabFile {
+    name: String,
+    size: usize,
+    folder_offset: usize,
+    folder: u16,
+}
+
+struct Cabinet {
+    folders: Vec<Folder>,
+    files: Vec<CabFile>,
+    /// Per-block reserved bytes between each CFDATA header and its data.
+    data_reserve: usize,
+}
+
+impl Cabinet {
+    fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < CAB_HEADER_SIZE || !data.starts_with(CAB_SIGNATURE) {
+            return None;
+        }
+        let files_offset = read_u32(data, 16)? as usize;
+        let folder_count = read_u16(data, 26)?;
+        let file_count = read_u16(data, 28)?;
+        let flags = read_u16(data, 30)?;
+
+        let mut offset = CAB_HEADER_SIZE;
+        let (mut folder_reserve, mut data_reserve) = (0, 0);
+        if flags & CAB_FLAG_RESERVE_PRESENT != 0 {
+            let header_reserve = read_u16(data, offset)? as usize;
+            folder_reserve = *data.get(offset + 2)? as usize;
+            data_reserve = *data.get(offset + 3)? as usize;
+            offset += 4 + header_reserve;
+        }
+        // Names of the previous and next cabinet (and disk) in a spanned set
+        for flag in [CAB_FLAG_PREV_CABINET, CAB_FLAG_NEXT_CABINET] {
+            if flags & flag != 0 {
+                for _ in 0..2 {
+                    offset += data.get(offset..)?.iter().position(|&b| b == 0)? + 1;
+                }
+            }
+        }
+
+        let folders = (0..folder_count as usize)
+            .map(|i| {
+                let at = offset + i * (CFFOLDER_SIZE + folder_reserve);
+                Some(Folder {
+                    first_data_block: read_u32(data, at)? as usize,
+                    data_blocks: read_u16(data, at + 4)?,
+                    compression: read_u16(data, at + 6)?,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut files = Vec::with_capacity(file_count as usize);
+        let mut at = files_offset;
+        for _ in 0..file_count {
+            if !budget::charge(CFFILE_SIZE) {
+                break;
+            }
+            let name = data.get(at + CFFILE_SIZE..)?;
+            let name_len = name.iter().position(|&b| b == 0)?;
+            files.push(CabFile {
+                name: String::from_utf8_lossy(&name[..name_len]).into_owned(),
+                size: read_u32(data, at)? as usize,
+                folder_offset: read_u32(data, at + 4)? as usize,
+                folder: read_u16(data, at + 8)?,
+            });
+            at += CFFILE_SIZE + name_len + 1;
+        }
+        Some(Cabinet { folders, files, data_reserve })
+    }
+
+    /// The contents of `file`, when its folder is stored or MSZIP-compressed
+    /// and it fits in `MAX_PROPERTIES_SIZE`.
+    fn extract(&self, data: &[u8], file: &CabFile) -> Option<Vec<u8>> {
+        let folder = self.folders.get(file.folder as usize)?;
+        let compression = folder.compression & COMPRESSION_MASK;
+        if file.size > MAX_PROPERTIES_SIZE || !matches!(compression, COMPRESSION_NONE | COMPRESSION_MSZIP) {
+            return None;
+        }
+        let end = file.folder_offset.checked_add(file.size)?;
+
+        let mut output = Vec::new();
+        let mut at = folder.first_data_block;
+        for _ in 0..folder.data_blocks {
+            if output.len() >= end {
+                break;
+            }
+            let compressed_size = read_u16(data, at + 4)? as usize;
+            let uncompressed_size = read_u16(data, at + 6)? as usize;
+            let start = at + CFDATA_SIZE + self.data_reserve;
+            let block = data.get(start..start.checked_add(compressed_size)?)?;
+            if !budget::charge(compressed_size + uncompressed_size) {
+                return None;
+            }
+            if compression == COMPRESSION_NONE {
+                output.extend_from_slice(block);
+            } else {
+                let inflated = inflate_mszip_block(&output, block.strip_prefix(MSZIP_SIGNATURE)?, uncompressed_size)?;
+                output.extend_from_slice(&inflated);
+            }
+            at = start + compressed_size;
+        }
+        output.get(file.folder_offset..end).map(<[u8]>::to_vec)
+    }
+}
+
+/// Inflates one MSZIP block. Each block is a complete deflate stream that may
+/// refer back into the previous blocks' output, so that history is fed in
+/// first as a non-final stored block.
+fn inflate_mszip_block(history: &[u8], block: &[u8], uncompressed_size: usize) -> Option<Vec<u8>> {
+    let history = &history[history.len().saturating_sub(MSZIP_WINDOW)..];
+    let mut input = Vec::with_capacity(5 + history.len() + block.len());
+    if !history.is_empty() {
+        let len = history.len() as u16;
+        input.push(0); // BFINAL = 0, BTYPE = stored
+        input.extend_from_slice(&len.to_le_bytes());
+        input.extend_from_slice(&(!len).to_le_bytes());
+        input.extend_from_slice(history);
+    }
+    input.extend_from_slice(block);
+
+    let mut output = Vec::with_capacity(history.len() + uncompressed_size);
+    Decompress::new(false).decompress_vec(&input, &mut output, FlushDecompress::Finish).ok()?;
+    output.get(history.len()..).map(<[u8]>::to_vec)
+}
+
+/// `pkgProperties.txt` lines (`Key="Value"`), UTF-16LE with a BOM or UTF-8.
+fn parse_properties(data: &[u8]) -> HashMap<String, String> {
+    let text = match data.strip_prefix(&[0xFF, 0xFE]) {
+        Some(utf16) => {
+            let units: Vec<u16> = utf16.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        None => String::from_utf8_lossy(data).into_owned(),
+    };
+    text.lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            Some((key.trim().to_string(), value.trim().trim_matches('"').to_string()))
+        })
+        .collect()
+}
+
+/// KB number, target OS and architecture from an update file name such as
+/// `Windows10.0-KB5034441-x64.cab`.
+fn insert_name_fields(name: &str, meta: &mut HashMap<String, String>) {
+    let Some(kb_start) = name.find("-KB").map(|i| i + 1) else {
+        return;
+    };
+    let digits = name[kb_start + 2..].bytes().take_while(u8::is_ascii_digit).count();
+    if digits == 0 {
+        return;
+    }
+    meta.entry("KbNumber".into()).or_insert_with(|| name[kb_start + 2..kb_start + 2 + digits].to_string());
+    if let Some(version) = name[..kb_start - 1].strip_prefix("Windows") {
+        meta.entry("TargetOS".into()).or_insert_with(|| format!("Windows {}", version));
+    }
+    let rest = name[kb_start + 2 + digits..].to_ascii_lowercase();
+    if let Some((_, architecture)) = NAME_ARCHITECTURES.iter().find(|(suffix, _)| rest.starts_with(suffix)) {
+        meta.entry("Architecture".into()).or_insert_with(|| architecture.to_string());
+    }
+}
+
+fn parse_msu_metadata(data: &[u8]) -> MetadataResult {
+    let cab = Cabinet::parse(data).ok_or("Invalid cabinet header")?;
+
+    let mut meta = HashMap::new();
+    meta.insert("Format".into(), "MSU".into());
+    let names: Vec<&str> = cab.files.iter().map(|file| file.name.as_str()).collect();
+    meta.insert("CabinetFiles".into(), names.join(", "));
+    let uncompressed: u64 = cab.files.iter().map(|file| file.size as u64).sum();
+    meta.insert("CompressedSize".into(), data.len().to_string());
+    meta.insert("UncompressedSize".into(), uncompressed.to_string());
+
+    if let Some(properties) = cab
+        .files
+        .iter()
+        .find(|file| file.name.ends_with(PROPERTIES_SUFFIX))
+        .and_then(|file| cab.extract(data, file))
+    {
+        let properties = parse_properties(&properties);
+        for (property, key) in PROPERTY_KEYS {
+            if let Some(value) = properties.get(property).filter(|value| !value.is_empty()) {
+                meta.insert(key.into(), value.clone());
+            }
+        }
+    }
+    for name in &names {
+        insert_name_fields(name, &mut meta);
+    }
+    if let Some(kb) = meta.get("KbNumber").cloned() {
+        let kb = if kb.starts_with("KB") { kb } else { format!("KB{}", kb) };
+        meta.insert("ProductName".into(), kb.clone());
+        meta.insert("KbNumber".into(), kb);
+    }
+    Ok(meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{Compress, Compression, FlushCompress};
+
+    fn raw_deflate(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(data.len() + 64);
+        let _ = Compress::new(Compression::default(), false).compress_vec(data, &mut out, FlushCompress::Finish);
+        out
+    }
+
+    /// A single-folder cabinet of `names` holding `contents`; with `mszip`,
+    /// the folder data is compressed in MSZIP blocks of that many bytes.
+    fn cabinet(names: &[&str], contents: &[&[u8]], mszip: Option<usize>) -> Vec<u8> {
+        let folder_data = contents.concat();
+        let chunks: Vec<&[u8]> = folder_data.chunks(mszip.unwrap_or(folder_data.len())).collect();
+
+        let mut entries = Vec::new();
+        let mut folder_offset = 0u32;
+        for (name, data) in names.iter().zip(contents) {
+            entries.extend((data.len() as u32).to_le_bytes());
+            entries.extend(folder_offset.to_le_bytes());
+            entries.extend([0u8; 8]);
+            entries.extend(name.as_bytes());
+            entries.push(0);
+            folder_offset += data.len() as u32;
+        }
+        let files_offset = CAB_HEADER_SIZE + CFFOLDER_SIZE;
+        let data_offset = files_offset + entries.len();
+
+        let mut cab = vec![0u8; CAB_HEADER_SIZE];
+        cab[0..4].copy_from_slice(CAB_SIGNATURE);
+        cab[16..20].copy_from_slice(&(files_offset as u32).to_le_bytes());
+        cab[26..28].copy_from_slice(&1u16.to_le_bytes());
+        cab[28..30].copy_from_slice(&(names.len() as u16).to_le_bytes());
+        cab.extend((data_offset as u32).to_le_bytes());
+        cab.extend((chunks.len() as u16).to_le_bytes());
+        cab.extend(if mszip.is_some() { COMPRESSION_MSZIP } else { COMPRESSION_NONE }.to_le_bytes());
+        cab.extend(entries);
+        for chunk in chunks {
+            let block = match mszip {
+                Some(_) => [MSZIP_SIGNATURE, &raw_deflate(chunk)].concat(),
+                None => chunk.to_vec(),
+            };
+            cab.extend([0u8; 4]);
+            cab.extend((block.len() as u16).to_le_bytes());
+            cab.extend((chunk.len() as u16).to_le_bytes());
+            cab.extend(block);
+        }
+        let size = cab.len() as u32;
+        cab[8..12].copy_from_slice(&size.to_le_bytes());
+        cab
+    }
+
+    #[test]
+    fn test_msu_metadata() {
+        let properties = "ApplicabilityInfo=\"Windows 10.0;\"\r\nApplies to=\"Security Updates\"\r\n\
+            Company=\"Microsoft Corporation\"\r\nKB Article Number=\"5034441\"\r\nPackage Type=\"Update\"\r\n\
+            Processor Architecture=\"amd64\"\r\nProduct Name=\"Windows 10\"\r\n";
+        let utf16: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain(properties.encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        let names = ["WSUSSCAN.cab", "Windows10.0-KB5034441-x64-pkgProperties.txt", "Windows10.0-KB5034441-x64.cab"];
+        let contents: [&[u8]; 3] = [&[7u8; 3000], &utf16, b"MSCF"];
+
+        for mszip in [None, Some(2048)] {
+            let data = cabinet(&names, &contents, mszip);
+            assert!(MSUAnalyzer.sniff(&data));
+            let meta = MSUAnalyzer.parse_metadata(&data).unwrap_or_default();
+            let get = |key: &str| meta.get(key).map(String::as_str);
+            assert_eq!(get("KbNumber"), Some("KB5034441"));
+            assert_eq!(get("TargetOS"), Some("Windows 10"));
+            assert_eq!(get("Architecture"), Some("amd64"));
+            assert_eq!(get("PackageType"), Some("Update"));
+            assert_eq!(get("Publisher"), Some("Microsoft Corporation"));
+        }
+
+        // Without a readable properties file the names still identify the update
+        let data = cabinet(&["WSUSSCAN.cab", "Windows6.1-KB976932-X86.cab"], &[b"x", b"y"], None);
+        let meta = MSUAnalyzer.parse_metadata(&data).unwrap_or_default();
+        assert_eq!(meta.get("KbNumber").map(String::as_str), Some("KB976932"));
+        assert_eq!(meta.get("TargetOS").map(String::as_str), Some("Windows 6.1"));
+        assert_eq!(meta.get("Architecture").map(String::as_str), Some("x86"));
+        assert_eq!(MSUAnalyzer.file_list(&data, 1), ["WSUSSCAN.cab"]);
+    }
+}
@@ -0,0 +1,148 @@
+use serde::Deserialize;
+
+use crate::budget;
+
+/// Hard cap on `max_embedded_depth`, whatever the caller asks for, so crafted
+/// files nesting containers cannot drive unbounded recursion.
+pub const MAX_EMBEDDED_DEPTH: usize = 4;
+
+/// Default `max_compression_ratio`: legitimate packages stay well below
+/// 100:1, decompression bombs reach thousands to one.
+pub const DEFAULT_MAX_COMPRESSION_RATIO: u64 = 1000;
+
+/// Opt-in analysis behaviour, passed as a JSON object to `analyze_file_with_options`.
+/// Unknown keys are ignored and missing keys take their defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AnalysisOptions {
+    /// Scan the whole file for embedded indicators (`ExtractedUrls`,
+    /// `ExtractedEmails`, `ExtractedDomains`) and return the raw embedded
+    /// manifest as `RawManifest`. Off by default because it is a full extra
+    /// pass over the data.
+    pub deep_scan: bool,
+    /// How many levels of embedded objects (e.g. the MSI inside a PE
+    /// bootstrapper) to analyze and nest under `Embedded`. 0 disables it.
+    pub max_embedded_depth: usize,
+    /// Operations the analysis may spend before failing with
+    /// `ResourceLimitExceeded`. `None` scales the budget with the input size.
+    pub work_budget: Option<u64>,
+    /// Prefix every key but `Format` with its source, e.g. `pe.CompanyName`,
+    /// and flatten `Embedded` into `embedded.`-prefixed keys, so fields of the
+    /// outer and embedded objects cannot overwrite each other.
+    pub namespace_keys: bool,
+    /// Keys to keep, e.g. `["ProductName", "ProductVersion"]`; `None` keeps
+    /// everything and `Format` is always kept. Deep-scan and embedded analysis
+    /// are skipped unless one of their keys is listed.
+    pub fields: Option<Vec<String>>,
+    /// Locale for localized fields such as the RPM `Summary` (e.g. `de_DE`,
+    /// `de-DE` or `de`). `None` uses each format's default, `C` for RPM.
+    pub locale: Option<String>,
+    /// SHA-256 of the file as 64 hex digits, e.g. from a `Hasher` fed while
+    /// the upload arrived. Reported as `Sha256` instead of hashing the file
+    /// again during the deep scan.
+    pub sha256: Option<String>,
+    /// Add `_sources`, a JSON object giving each field's provenance:
+    /// `structured`, `heuristic`, `signature` or `embedded`.
+    pub include_sources: bool,
+    /// `UncompressedSize` to `CompressedSize` ratio above which
+    /// `SuspiciousCompressionRatio` is set. `None` uses
+    /// `DEFAULT_MAX_COMPRESSION_RATIO`.
+    pub max_compression_ratio: Option<u64>,
+    /// Size of the whole file when the data analyzed is only its leading
+    /// part, as `analyze_reader` loads it. Truncation and `CompressedSize` are
+    /// measured against it; `None` takes the length of the data.
+    pub file_size: Option<usize>,
+}
+
+impl AnalysisOptions {
+    pub fn from_json(options_json: &str) -> Result<Self, String> {
+        if options_json.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let options: Self =
+            serde_json::from_str(options_json).map_err(|e| format!("Invalid analysis options: {}", e))?;
+        if let Some(sha256) = &options.sha256 {
+            if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err("Invalid analysis options: sha256 must be 64 hex digits".to_string());
+            }
+        }
+        Ok(options)
+    }
+
+    pub fn with_deep_scan(mut self, deep_scan: bool) -> Self {
+        self.deep_scan = deep_scan;
+        self
+    }
+
+    pub fn with_max_embedded_depth(mut self, depth: usize) -> Self {
+        self.max_embedded_depth = depth;
+        self
+    }
+
+    pub fn with_work_budget(mut self, budget: u64) -> Self {
+        self.work_budget = Some(budget);
+        self
+    }
+
+    pub fn with_namespace_keys(mut self, namespace_keys: bool) -> Self {
+        self.namespace_keys = namespace_keys;
+        self
+    }
+
+    pub fn with_fields(mut self, fields: &[&str]) -> Self {
+        self.fields = Some(fields.iter().map(|field| field.to_string()).collect());
+        self
+    }
+
+    pub fn with_locale(mut self, locale: &str) -> Self {
+        self.locale = Some(locale.to_string());
+        self
+    }
+
+    pub fn with_sha256(mut self, sha256: &str) -> Self {
+        self.sha256 = Some(sha256.to_string());
+        self
+    }
+
+    pub fn with_include_sources(mut self, include_sources: bool) -> Self {
+        self.include_sources = include_sources;
+        self
+    }
+
+    pub fn with_max_compression_ratio(mut self, ratio: u64) -> Self {
+        self.max_compression_ratio = Some(ratio);
+        self
+    }
+
+    pub fn with_file_size(mut self, size: usize) -> Self {
+        self.file_size = Some(size);
+        self
+    }
+
+    /// Whether `key` survives the `fields` allow-list.
+    pub fn wants_field(&self, key: &str) -> bool {
+        key == "Format" || self.fields.as_ref().is_none_or(|fields| fields.iter().any(|field| field == key))
+    }
+
+    /// The budget for analyzing `len` bytes.
+    pub fn work_budget(&self, len: usize) -> u64 {
+        self.work_budget.unwrap_or_else(|| budget::default_budget(len))
+    }
+
+    /// The size of the file whose first `len` bytes are analyzed, never less
+    /// than `len`.
+    pub fn file_size(&self, len: usize) -> usize {
+        self.file_size.map_or(len, |size| size.max(len))
+    }
+
+    pub fn compression_ratio_limit(&self) -> u64 {
+        self.max_compression_ratio.unwrap_or(DEFAULT_MAX_COMPRESSION_RATIO)
+    }
+
+    pub fn embedded_depth(&self) -> usize {
+        if !self.wants_field("Embedded") {
+            return 0;
+        }
+        self.max_embedded_depth.min(MAX_EMBEDDED_DEPTH)
+    }
+}
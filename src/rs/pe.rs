@@ -1,8 +1,19 @@
 use pelite::pe64::{Pe as Pe64, PeFile as PeFile64};
 use pelite::pe32::{Pe as Pe32, PeFile as PeFile32};
-use goblin::pe::PE;
+use pelite::pe64::debug::{CodeView, Entry as DebugEntry};
+use pelite::image::IMAGE_DEBUG_TYPE_REPRO;
+use pelite::resources::version_info::{VersionInfo, Visit};
+use pelite::resources::{Name, Resources};
+use goblin::{
+    pe::{certificate_table::AttributeCertificateType, header::Header, PE},
+    Object,
+};
 use std::collections::HashMap;
-use crate::{msi, FileAnalyzer, MetadataResult};
+#[cfg(feature = "msi")]
+use crate::{msi, Confidence};
+use crate::xml::{xml_attribute, xml_element, xml_element_text};
+use crate::provenance::{self, Source};
+use crate::{authenticode, budget, digest, locale, progress, FileAnalyzer, FormatInfo, MetadataResult};
 
 // Constants for magic numbers and patterns
 const MSI_SIGNATURE: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
@@ -20,19 +31,160 @@ const PATTERN_WISE: &[u8] = b"Wise Installation System";
 const PATTERN_SETUP_FACTORY: &[u8] = b"Setup Factory";
 const PATTERN_SMART_INSTALL: &[u8] = b"Smart Install Maker";
 
+// Archives appended to a PE stub (self-extractors)
+const ZIP_EOCD_SIGNATURE: &[u8] = b"PK\x05\x06";
+const ZIP_EOCD_SIZE: usize = 22;
+const ZIP_MAX_COMMENT: usize = 0xFFFF;
+const SEVEN_ZIP_SIGNATURE: &[u8] = &[0x37, 0x7A, 0xBC, 0xAF, 0x27, 0x1C];
+
+// `ImgDelayDescr`; `grAttrs` bit 0 (dlattrRva) clear means the VC6 layout of
+// virtual addresses instead of RVAs
+const DELAY_DESCRIPTOR_SIZE: usize = 32;
+const DELAY_ATTRIBUTE_RVA: u32 = 1;
+// `IMAGE_IMPORT_DESCRIPTOR`; its thunks are 4 bytes in PE32 and 8 in PE32+,
+// with the top bit marking an import by ordinal
+const IMPORT_DESCRIPTOR_SIZE: usize = 20;
+// `IMAGE_BOUND_IMPORT_DESCRIPTOR` and `IMAGE_BOUND_FORWARDER_REF` are both 8 bytes
+const BOUND_DESCRIPTOR_SIZE: usize = 8;
+
+// Security-relevant bits of the COFF Characteristics and optional-header
+// DllCharacteristics, each reported as a "true"/"false" field
+const FILE_CHARACTERISTIC_FLAGS: [(u16, &str); 2] = [
+    (0x1000, "IsSystem"),
+    (0x2000, "IsDll"),
+];
+const DLL_CHARACTERISTIC_FLAGS: [(u16, &str); 5] = [
+    (0x0020, "HighEntropyVA"),
+    (0x0040, "ASLR"),
+    (0x0080, "ForceIntegrity"),
+    (0x0100, "DEP"),
+    (0x4000, "ControlFlowGuard"),
+];
+
+// `<supportedOS Id=...>` GUIDs from the application manifest compatibility section
+const SUPPORTED_OS_IDS: [(&str, &str); 5] = [
+    ("{e2011457-1546-43c5-a5fe-008deee3d3f0}", "Windows Vista"),
+    ("{35138b9a-5d96-4fbd-8e2d-a2440225f93a}", "Windows 7"),
+    ("{4a2f28e3-53b9-4441-ba9c-d69d4a4a6e38}", "Windows 8"),
+    ("{1f676c76-80e1-4239-95bb-83d0f6d0da78}", "Windows 8.1"),
+    ("{8e0f7a12-bfb3-4fe8-b9a5-48fd50a15a9a}", "Windows 10"),
+];
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "PE",
+    mime_type: "application/x-msdownload",
+    extensions: &["exe", "dll", "sys"],
+};
+
 pub struct PEAnalyzer;
 
 impl FileAnalyzer for PEAnalyzer {
-    fn get_file_info(_data: &[u8]) -> HashMap<String, String> {
-        let mut info = HashMap::new();
-        info.insert("Format".to_string(), "PE".to_string());
-        info
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
     }
 
-    fn parse_metadata(data: &[u8]) -> MetadataResult {
+    fn sniff(&self, data: &[u8]) -> bool {
+        matches!(Object::parse(data), Ok(Object::PE(_)))
+    }
+
+    /// A truncated PE usually fails the full goblin parse, so accept the headers alone.
+    fn sniff_header(&self, data: &[u8]) -> bool {
+        Header::parse(data).is_ok()
+    }
+
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
         let pe = PE::parse(data).map_err(|e| format!("Failed to parse PE file: {}", e))?;
         parse_pe_metadata(data, &pe)
     }
+
+    /// The RT_MANIFEST resource XML.
+    fn raw_manifest(&self, data: &[u8]) -> Option<Vec<u8>> {
+        let manifest = match PeFile64::from_bytes(data) {
+            Ok(file) => file.resources().ok()?.manifest().ok()?,
+            Err(_) => PeFile32::from_bytes(data).ok()?.resources().ok()?.manifest().ok()?,
+        };
+        Some(manifest.as_bytes().to_vec())
+    }
+
+    /// Headers, section table, every section's raw data and the certificate table.
+    fn expected_size(&self, data: &[u8]) -> Option<usize> {
+        let header = Header::parse(data).ok()?;
+        let table_start = (header.dos_header.pe_pointer as usize)
+            .saturating_add(24 + header.coff_header.size_of_optional_header as usize);
+        let table_end = table_start.saturating_add(header.coff_header.number_of_sections as usize * 40);
+
+        let sections_end = data
+            .get(table_start..table_end.min(data.len()))
+            .unwrap_or_default()
+            .chunks_exact(40)
+            .map(|section| {
+                let raw_size = u32::from_le_bytes([section[16], section[17], section[18], section[19]]);
+                let raw_pointer = u32::from_le_bytes([section[20], section[21], section[22], section[23]]);
+                (raw_pointer as usize).saturating_add(raw_size as usize)
+            })
+            .max()
+            .unwrap_or(0);
+        let (headers_end, certificate_end) = header.optional_header.map_or((0, 0), |optional| {
+            let certificate_end = optional
+                .data_directories
+                .get_certificate_table()
+                .map_or(0, |table| (table.virtual_address as usize).saturating_add(table.size as usize));
+            (optional.windows_fields.size_of_headers as usize, certificate_end)
+        });
+
+        Some(table_end.max(sections_end).max(headers_end).max(certificate_end))
+    }
+
+    fn parse_header(&self, data: &[u8]) -> MetadataResult {
+        self.parse_metadata(data).or_else(|_| parse_pe_header_metadata(data))
+    }
+
+    fn embedded_offsets(&self, data: &[u8]) -> Vec<usize> {
+        compound_file_offsets(data).take(1).collect()
+    }
+}
+
+fn parse_pe_header_metadata(buf: &[u8]) -> MetadataResult {
+    let header = Header::parse(buf).map_err(|e| format!("Failed to parse PE header: {}", e))?;
+    let mut meta = HashMap::new();
+
+    meta.insert("Format".into(), "PE".into());
+
+    let coff = &header.coff_header;
+    meta.insert("Machine".into(), format!("0x{:04X}", coff.machine));
+    meta.insert("NumberOfSections".into(), coff.number_of_sections.to_string());
+    meta.insert("SizeOfOptionalHeader".into(), coff.size_of_optional_header.to_string());
+    meta.insert("Characteristics".into(), format!("0x{:04X}", coff.characteristics));
+    insert_flags(coff.characteristics, &FILE_CHARACTERISTIC_FLAGS, &mut meta);
+    meta.insert("PointerToSymbolTable".into(), coff.pointer_to_symbol_table.to_string());
+    meta.insert("NumberOfSymbols".into(), coff.number_of_symbol_table.to_string());
+
+    if coff.time_date_stamp > 0 {
+        meta.insert("Timestamp".into(), coff.time_date_stamp.to_string());
+    }
+
+    if let Some(optional) = header.optional_header {
+        let is_64 = optional.standard_fields.magic == goblin::pe::optional_header::MAGIC_64;
+        meta.insert("Architecture".into(), machine_architecture(coff.machine, is_64).into());
+        meta.insert(
+            "EntryPoint".into(),
+            format!("0x{:08X}", optional.standard_fields.address_of_entry_point),
+        );
+        let image_base = optional.windows_fields.image_base;
+        meta.insert(
+            "ImageBase".into(),
+            if is_64 { format!("0x{:016X}", image_base) } else { format!("0x{:08X}", image_base) },
+        );
+        meta.insert("SizeOfImage".into(), optional.windows_fields.size_of_image.to_string());
+        insert_subsystem(optional.windows_fields.subsystem, &mut meta);
+        meta.insert(
+            "DllCharacteristics".into(),
+            format!("0x{:04X}", optional.windows_fields.dll_characteristics),
+        );
+        insert_flags(optional.windows_fields.dll_characteristics, &DLL_CHARACTERISTIC_FLAGS, &mut meta);
+    }
+
+    Ok(meta)
 }
 
 fn parse_pe_metadata(buf: &[u8], pe: &PE) -> MetadataResult {
@@ -41,18 +193,378 @@ fn parse_pe_metadata(buf: &[u8], pe: &PE) -> MetadataResult {
     meta.insert("Format".into(), "PE".into());
 
     detect_installer_type(buf, &mut meta);
+    extract_overlay_info(buf, pe, &mut meta);
+    insert_imports(buf, pe, &mut meta);
+    // The image hash and the checksum are the two passes over the whole file
+    let stage = progress::stage(0.5);
+    if let Some(hash) = compute_authentihash(buf, pe) {
+        meta.insert("AuthentiHash".into(), digest::to_hex(&hash));
+    }
+    drop(stage);
+    if let Some(signature) = pe
+        .certificates
+        .iter()
+        .find(|certificate| certificate.certificate_type == AttributeCertificateType::PkcsSignedData)
+    {
+        let tagging = provenance::Tagging::begin(Source::Signature, &meta);
+        meta.insert("CertificateTrust".into(), authenticode::certificate_trust(signature.certificate).into());
+        if let Some(signing_time) = authenticode::signing_time(signature.certificate) {
+            meta.insert("SigningTime".into(), signing_time);
+        }
+        tagging.finish(&meta);
+    }
+    let stage = progress::stage(0.5);
+    insert_checksum_info(buf, pe, &mut meta);
+    drop(stage);
 
+    meta.insert("Architecture".into(), machine_architecture(pe.header.coff_header.machine, pe.is_64).into());
     if pe.is_64 {
-        meta.insert("Architecture".into(), "x64".into());
         extract_pe64_metadata(buf, &mut meta);
     } else {
-        meta.insert("Architecture".into(), "x86".into());
         extract_pe32_metadata(buf, &mut meta);
     }
+    // Without a version-info company the signer is the best name available
+    if !meta.contains_key("CompanyName") {
+        if let Some(signer) = meta.get("SignedBy").cloned() {
+            meta.insert("CompanyName".into(), signer);
+            provenance::derive("CompanyName", "SignedBy");
+            meta.insert("CompanyNameSource".into(), "digital-signature".into());
+        }
+    }
 
     Ok(meta)
 }
 
+/// `ImportedDlls`: every DLL from the import table, then any further ones
+/// from the delay-load and bound import directories. `DelayLoadedDlls` marks
+/// those loaded on first call, which an installer may use for nearly all of
+/// its dependencies. `ImpHash` comes from the same import table walk.
+fn insert_imports(buf: &[u8], pe: &PE, meta: &mut HashMap<String, String>) {
+    let thunk_size = if pe.is_64 { 8 } else { 4 };
+    if let Some(hash) = imphash(&import_descriptors(buf, pe, thunk_size)) {
+        meta.insert("ImpHash".into(), hash);
+    }
+    let mut dlls: Vec<String> = pe.libraries.iter().map(|dll| dll.to_string()).collect();
+    let delay_loaded = delay_import_dlls(buf, pe);
+    for dll in delay_loaded.iter().chain(&bound_import_dlls(buf, pe)) {
+        if !dlls.iter().any(|known| known.eq_ignore_ascii_case(dll)) {
+            dlls.push(dll.clone());
+        }
+    }
+    if !dlls.is_empty() {
+        meta.insert("ImportedDlls".into(), dlls.join(", "));
+    }
+    if !delay_loaded.is_empty() {
+        meta.insert("DelayLoadedDlls".into(), delay_loaded.join(", "));
+    }
+}
+
+fn rva_to_offset(pe: &PE, rva: usize) -> Option<usize> {
+    let file_alignment = pe.header.optional_header?.windows_fields.file_alignment;
+    goblin::pe::utils::find_offset(rva, &pe.sections, file_alignment, &goblin::pe::options::ParseOptions::default())
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> Option<u32> {
+    let bytes = buf.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_thunk(buf: &[u8], offset: usize, thunk_size: usize) -> Option<u64> {
+    let bytes = buf.get(offset..offset.checked_add(thunk_size)?)?;
+    let mut value = [0u8; 8];
+    value[..thunk_size].copy_from_slice(bytes);
+    Some(u64::from_le_bytes(value))
+}
+
+/// Reads a non-empty NUL-terminated ASCII name starting at `offset`.
+fn c_string_at(buf: &[u8], offset: usize) -> Option<String> {
+    let bytes = buf.get(offset..)?;
+    let end = bytes.iter().position(|&b| b == 0)?;
+    let name = std::str::from_utf8(&bytes[..end]).ok()?;
+    (!name.is_empty() && name.is_ascii()).then(|| name.to_string())
+}
+
+/// The `IMAGE_DIRECTORY_ENTRY_IMPORT` descriptors with the functions named by
+/// their lookup tables (the address table when there is none), reading
+/// `thunk_size`-byte thunks: 4 for PE32, 8 for PE32+.
+fn import_descriptors(buf: &[u8], pe: &PE, thunk_size: usize) -> Vec<ImportDescriptor> {
+    let Some(start) = pe
+        .header
+        .optional_header
+        .and_then(|optional| optional.data_directories.get_import_table().copied())
+        .filter(|directory| directory.virtual_address != 0)
+        .and_then(|directory| rva_to_offset(pe, directory.virtual_address as usize))
+    else {
+        return Vec::new();
+    };
+    let ordinal_flag = 1u64 << (thunk_size * 8 - 1);
+    let mut descriptors = Vec::new();
+    for offset in (start..buf.len()).step_by(IMPORT_DESCRIPTOR_SIZE).take_while(|_| budget::charge(IMPORT_DESCRIPTOR_SIZE)) {
+        let (Some(lookup), Some(name), Some(address)) =
+            (read_u32(buf, offset), read_u32(buf, offset + 12), read_u32(buf, offset + 16))
+        else {
+            break;
+        };
+        if name == 0 {
+            break;
+        }
+        let Some(dll) = rva_to_offset(pe, name as usize).and_then(|at| c_string_at(buf, at)) else {
+            continue;
+        };
+        let table = if lookup != 0 { lookup } else { address };
+        let mut functions = Vec::new();
+        if let Some(table_start) = rva_to_offset(pe, table as usize) {
+            for thunk_offset in (table_start..buf.len()).step_by(thunk_size).take_while(|_| budget::charge(thunk_size)) {
+                let Some(thunk) = read_thunk(buf, thunk_offset, thunk_size).filter(|&thunk| thunk != 0) else {
+                    break;
+                };
+                if thunk & ordinal_flag != 0 {
+                    functions.push(ImportedFunction::ByOrdinal(thunk as u16));
+                } else if let Some(function) = rva_to_offset(pe, (thunk & 0x7FFF_FFFF) as usize)
+                    .and_then(|hint| c_string_at(buf, hint + 2))
+                {
+                    functions.push(ImportedFunction::ByName(function));
+                }
+            }
+        }
+        descriptors.push((dll, functions));
+    }
+    descriptors
+}
+
+/// DLL names from the `IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT` descriptors.
+fn delay_import_dlls(buf: &[u8], pe: &PE) -> Vec<String> {
+    let Some(directory) = pe
+        .header
+        .optional_header
+        .and_then(|optional| optional.data_directories.get_delay_import_descriptor().copied())
+        .filter(|directory| directory.virtual_address != 0)
+    else {
+        return Vec::new();
+    };
+    let Some(start) = rva_to_offset(pe, directory.virtual_address as usize) else {
+        return Vec::new();
+    };
+    let mut dlls = Vec::new();
+    for offset in (start..buf.len()).step_by(DELAY_DESCRIPTOR_SIZE).take_while(|_| budget::charge(DELAY_DESCRIPTOR_SIZE)) {
+        let (Some(attributes), Some(name)) = (read_u32(buf, offset), read_u32(buf, offset + 4)) else {
+            break;
+        };
+        if name == 0 {
+            break;
+        }
+        let name_rva = if attributes & DELAY_ATTRIBUTE_RVA != 0 {
+            Some(name as usize)
+        } else {
+            (name as usize).checked_sub(pe.image_base)
+        };
+        if let Some(dll) = name_rva.and_then(|rva| rva_to_offset(pe, rva)).and_then(|at| c_string_at(buf, at)) {
+            dlls.push(dll);
+        }
+    }
+    dlls
+}
+
+/// DLL names, forwarders included, from the `IMAGE_DIRECTORY_ENTRY_BOUND_IMPORT`
+/// descriptors. The directory lives in the headers, so its address and the
+/// name offsets are file offsets rather than RVAs.
+fn bound_import_dlls(buf: &[u8], pe: &PE) -> Vec<String> {
+    let Some(start) = pe
+        .header
+        .optional_header
+        .and_then(|optional| optional.data_directories.get_bound_import_table().copied())
+        .map(|directory| directory.virtual_address as usize)
+        .filter(|&start| start != 0)
+    else {
+        return Vec::new();
+    };
+    let mut dlls = Vec::new();
+    let mut offset = start;
+    while budget::charge(BOUND_DESCRIPTOR_SIZE) {
+        let Some(descriptor) = buf.get(offset..offset.saturating_add(BOUND_DESCRIPTOR_SIZE)) else {
+            break;
+        };
+        let name_offset = u16::from_le_bytes([descriptor[4], descriptor[5]]) as usize;
+        if name_offset == 0 {
+            break;
+        }
+        // A module descriptor is followed by its forwarder refs, which share its layout
+        if let Some(dll) = c_string_at(buf, start + name_offset) {
+            dlls.push(dll);
+        }
+        offset += BOUND_DESCRIPTOR_SIZE;
+    }
+    dlls
+}
+
+/// The overlay is everything past the last section's raw data. An Authenticode
+/// certificate table at the end of the file is excluded from the archive scan.
+fn extract_overlay_info(buf: &[u8], pe: &PE, meta: &mut HashMap<String, String>) {
+    let overlay_start = pe
+        .sections
+        .iter()
+        .map(|section| (section.pointer_to_raw_data as usize).saturating_add(section.size_of_raw_data as usize))
+        .max()
+        .unwrap_or(0);
+    if overlay_start == 0 || overlay_start >= buf.len() {
+        return;
+    }
+
+    meta.insert("OverlayOffset".into(), overlay_start.to_string());
+    meta.insert("OverlaySize".into(), (buf.len() - overlay_start).to_string());
+
+    let certificate_start = pe
+        .header
+        .optional_header
+        .and_then(|optional| optional.data_directories.get_certificate_table().copied())
+        .map(|table| table.virtual_address as usize)
+        .filter(|&offset| offset > overlay_start && offset < buf.len());
+    let overlay = &buf[overlay_start..certificate_start.unwrap_or(buf.len())];
+
+    if let Some(archive) = find_contained_archive(overlay) {
+        meta.insert("ContainedArchive".into(), archive.kind.into());
+        meta.insert("ContainedArchiveOffset".into(), (overlay_start + archive.offset).to_string());
+        if let Some(entries) = archive.entries {
+            meta.insert("ContainedArchiveEntries".into(), entries.to_string());
+        }
+    }
+}
+
+/// `LikelyReproducibleBuild`: a zeroed `TimeDateStamp`, or the
+/// `IMAGE_DEBUG_TYPE_REPRO` debug entry MSVC's `/Brepro` emits when it
+/// replaces the timestamp with a hash of the image.
+fn insert_reproducible_build(timestamp: u32, has_repro_entry: bool, meta: &mut HashMap<String, String>) {
+    meta.insert("LikelyReproducibleBuild".into(), (timestamp == 0 || has_repro_entry).to_string());
+}
+
+/// TLS callbacks run before the entry point, a common anti-analysis and
+/// injection technique.
+fn insert_tls_info(callback_count: usize, meta: &mut HashMap<String, String>) {
+    meta.insert("HasTlsCallbacks".into(), (callback_count > 0).to_string());
+    if callback_count > 0 {
+        meta.insert("TlsCallbackCount".into(), callback_count.to_string());
+    }
+}
+
+/// File offset of the optional header: the PE signature and COFF file header
+/// precede it.
+fn optional_header_offset(pe: &PE) -> Option<usize> {
+    (pe.header.dos_header.pe_pointer as usize).checked_add(4 + 20)
+}
+
+/// The PE image checksum (as computed by `CheckSumMappedFile`): a 16-bit
+/// one's-complement-style sum of every word of the file except the `CheckSum`
+/// field at `checksum_offset`, plus the file length.
+fn compute_pe_checksum(buf: &[u8], checksum_offset: usize) -> u32 {
+    let mut sum: u32 = 0;
+    for (idx, word) in buf.chunks(2).enumerate() {
+        if (idx * 2) % progress::REPORT_INTERVAL == 0 {
+            progress::report(idx * 2, buf.len());
+        }
+        if (checksum_offset..checksum_offset + 4).contains(&(idx * 2)) {
+            continue;
+        }
+        sum += u32::from(u16::from_le_bytes([word[0], word.get(1).copied().unwrap_or(0)]));
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    sum = (sum & 0xFFFF) + (sum >> 16);
+    sum.wrapping_add(buf.len() as u32)
+}
+
+/// `CheckSumStored`, `CheckSumComputed` and `CheckSumValid`. A stored value of
+/// 0 means the linker did not set one, which is normal outside drivers and
+/// system DLLs.
+fn insert_checksum_info(buf: &[u8], pe: &PE, meta: &mut HashMap<String, String>) {
+    let Some(optional) = pe.header.optional_header else {
+        return;
+    };
+    let Some(checksum_offset) = optional_header_offset(pe).map(|offset| offset + 64) else {
+        return;
+    };
+    if checksum_offset + 4 > buf.len() || !budget::charge(buf.len()) {
+        return;
+    }
+
+    let stored = optional.windows_fields.check_sum;
+    let computed = compute_pe_checksum(buf, checksum_offset);
+    meta.insert("CheckSumStored".into(), format!("0x{:08X}", stored));
+    meta.insert("CheckSumComputed".into(), format!("0x{:08X}", computed));
+    meta.insert("CheckSumValid".into(), (stored == computed).to_string());
+}
+
+/// Authenticode image hash (SHA-256): the whole file except the optional-header
+/// `CheckSum`, the security data-directory entry and the certificate table it
+/// points to. This is the digest an Authenticode signature signs.
+fn compute_authentihash(buf: &[u8], pe: &PE) -> Option<[u8; 32]> {
+    let optional = pe.header.optional_header?;
+    let optional_start = optional_header_offset(pe)?;
+    let checksum = optional_start + 64;
+    let data_directories = optional_start + if pe.is_64 { 112 } else { 96 };
+    let security_entry = data_directories + 4 * 8;
+    if optional.windows_fields.number_of_rva_and_sizes <= 4 || security_entry + 8 > buf.len() {
+        return None;
+    }
+
+    let certificate = optional
+        .data_directories
+        .get_certificate_table()
+        .map(|table| (table.virtual_address as usize, table.size as usize))
+        .filter(|&(offset, size)| size > 0 && offset >= security_entry + 8)
+        .and_then(|(offset, size)| Some((offset, offset.checked_add(size)?)))
+        .filter(|&(_, end)| end <= buf.len());
+    let (hashed_end, trailer_start) = certificate.unwrap_or((buf.len(), buf.len()));
+
+    let mut hasher = digest::Sha256::new();
+    let mut hashed = 0;
+    let ranges = [0..checksum, checksum + 4..security_entry, security_entry + 8..hashed_end, trailer_start..buf.len()];
+    for range in ranges {
+        for chunk in buf[range].chunks(progress::REPORT_INTERVAL) {
+            hasher.update(chunk);
+            hashed += chunk.len();
+            progress::report(hashed, buf.len());
+        }
+    }
+    Some(hasher.finalize())
+}
+
+struct ContainedArchive {
+    kind: &'static str,
+    /// Start of the archive relative to the scanned region.
+    offset: usize,
+    entries: Option<u16>,
+}
+
+/// Looks for a ZIP end-of-central-directory record near the end of `region`,
+/// then for a 7z signature anywhere in it. 7z headers are usually compressed,
+/// so no entry count is reported for them.
+fn find_contained_archive(region: &[u8]) -> Option<ContainedArchive> {
+    let tail_start = region.len().saturating_sub(ZIP_EOCD_SIZE + ZIP_MAX_COMMENT);
+    let eocd = region[tail_start..]
+        .windows(ZIP_EOCD_SIGNATURE.len())
+        .rposition(|window| window == ZIP_EOCD_SIGNATURE)
+        .map(|pos| tail_start + pos)
+        .and_then(|pos| region.get(pos..pos + ZIP_EOCD_SIZE).map(|record| (pos, record)));
+
+    if let Some((pos, record)) = eocd {
+        let entries = u16::from_le_bytes([record[10], record[11]]);
+        let cd_size = u32::from_le_bytes([record[12], record[13], record[14], record[15]]) as usize;
+        let cd_offset = u32::from_le_bytes([record[16], record[17], record[18], record[19]]) as usize;
+        return Some(ContainedArchive {
+            kind: "ZIP",
+            // SFX stubs shift the archive: the central directory ends where the EOCD starts.
+            offset: pos.saturating_sub(cd_size.saturating_add(cd_offset)),
+            // 0xFFFF means the real count lives in a ZIP64 record
+            entries: (entries != u16::MAX).then_some(entries),
+        });
+    }
+
+    find_bytes(region, SEVEN_ZIP_SIGNATURE).map(|offset| ContainedArchive {
+        kind: "7z",
+        offset,
+        entries: None,
+    })
+}
+
 fn detect_installer_type(buf: &[u8], meta: &mut HashMap<String, String>) {
     // Helper to check if a pattern exists in buffer
     let contains_pattern = |pattern: &[u8]| -> bool {
@@ -60,6 +572,7 @@ fn detect_installer_type(buf: &[u8], meta: &mut HashMap<String, String>) {
     };
 
     // Check installer patterns efficiently without converting to String
+    let tagging = provenance::Tagging::begin(Source::Heuristic, meta);
     if contains_pattern(PATTERN_INNO_SETUP) || contains_pattern(PATTERN_INNO_VERSION) {
         meta.insert("InstallerType".to_string(), "Inno Setup".to_string());
     } else if contains_pattern(PATTERN_NSIS) || contains_pattern(PATTERN_NSIS_HEADER) {
@@ -75,37 +588,97 @@ fn detect_installer_type(buf: &[u8], meta: &mut HashMap<String, String>) {
     } else if contains_pattern(PATTERN_SMART_INSTALL) {
         meta.insert("InstallerType".to_string(), "Smart Install Maker".to_string());
     }
+    tagging.finish(meta);
 
     // Check for embedded MSI
-    if let Some(pos) = find_bytes(buf, MSI_SIGNATURE) {
+    if let Some(pos) = compound_file_offsets(buf).next() {
         meta.insert("EmbeddedMSI".to_string(), "true".to_string());
         meta.insert("MSIOffset".to_string(), pos.to_string());
-        extract_embedded_msi_metadata(buf, pos, meta);
+        #[cfg(feature = "msi")]
+        {
+            let tagging = provenance::Tagging::begin(Source::Embedded, meta);
+            extract_embedded_msi_metadata(buf, meta);
+            tagging.finish(meta);
+        }
     }
 
+    let is_installer = meta.contains_key("InstallerType") || meta.contains_key("EmbeddedMSI");
+    meta.insert("IsInstaller".to_string(), is_installer.to_string());
+
+    let tagging = provenance::Tagging::begin(Source::Signature, meta);
     extract_signature_info(buf, meta);
+    tagging.finish(meta);
 }
 
-fn extract_embedded_msi_metadata(buf: &[u8], msi_offset: usize, meta: &mut HashMap<String, String>) {
-    if msi_offset >= buf.len() {
-        return;
+/// Offsets of the OLE signature in `buf` that start a plausible compound-file
+/// header (little-endian byte order mark and a 512 or 4096 byte sector size),
+/// so stray signature bytes in code or data are skipped.
+fn compound_file_offsets(buf: &[u8]) -> impl Iterator<Item = usize> + '_ {
+    buf.windows(MSI_SIGNATURE.len())
+        .enumerate()
+        .filter(|(_, window)| *window == MSI_SIGNATURE)
+        .map(|(pos, _)| pos)
+        .filter(|&pos| {
+            let header = buf.get(pos..pos + 0x20).unwrap_or_default();
+            header.len() == 0x20
+                && header[0x1C..0x1E] == [0xFE, 0xFF]
+                && matches!(u16::from_le_bytes([header[0x1E], header[0x1F]]), 9 | 12)
+        })
+}
+
+/// The compound file starting at `offset`, zero-padded to a whole number of
+/// sectors: the CFB reader rejects a final partial sector, which is common
+/// when an installer stores the MSI without its trailing slack.
+#[cfg(feature = "msi")]
+fn compound_file_at(buf: &[u8], offset: usize) -> std::borrow::Cow<'_, [u8]> {
+    let data = &buf[offset..];
+    let sector_size = 1usize << u16::from_le_bytes([data[0x1E], data[0x1F]]);
+    match data.len() % sector_size {
+        0 => std::borrow::Cow::Borrowed(data),
+        partial => {
+            let mut padded = data.to_vec();
+            padded.resize(data.len() + sector_size - partial, 0);
+            std::borrow::Cow::Owned(padded)
+        }
     }
+}
 
-    let msi_data = &buf[msi_offset..];
+/// Copies the product details the PE lacks from the first embedded compound
+/// file that is an actual MSI, or leaves `EmbeddedMSIWarning` when none of
+/// them parses. The caller marks the copies `embedded` in `_sources`.
+#[cfg(feature = "msi")]
+fn extract_embedded_msi_metadata(buf: &[u8], meta: &mut HashMap<String, String>) {
+    let mut failure = None;
+    let mut parsed = None;
+    for offset in compound_file_offsets(buf) {
+        let msi_data = compound_file_at(buf, offset);
+        if msi::MSIAnalyzer.detection_confidence(&msi_data) != Confidence::High {
+            failure.get_or_insert_with(|| format!("No MSI string pool in compound file at offset {}", offset));
+            continue;
+        }
+        match msi::MSIAnalyzer.parse_metadata(&msi_data) {
+            Ok(msi_meta) => {
+                parsed = Some((offset, msi_meta));
+                break;
+            }
+            Err(e) => {
+                failure.get_or_insert_with(|| format!("Embedded MSI at offset {} failed to parse: {}", offset, e));
+            }
+        }
+    }
 
-    if let Ok(msi_meta) = msi::MSIAnalyzer::parse_metadata(msi_data) {
-        let msi_fields = [
-            ("ProductName", "ProductName"),
-            ("Manufacturer", "Manufacturer"),
-            ("ProductVersion", "ProductVersion"),
-        ];
+    let Some((offset, msi_meta)) = parsed else {
+        if let Some(warning) = failure {
+            meta.insert("EmbeddedMSIWarning".to_string(), warning);
+        }
+        return;
+    };
+    meta.insert("MSIOffset".to_string(), offset.to_string());
 
-        for (msi_key, pe_key) in msi_fields.iter() {
-            if let Some(value) = msi_meta.get(*msi_key) {
-                if !meta.contains_key(*pe_key) {
-                    meta.insert(format!("{}FromEmbeddedMSI", pe_key), value.clone());
-                    meta.insert((*pe_key).to_string(), format!("{} (from embedded MSI)", value));
-                }
+    for key in ["ProductName", "Manufacturer", "ProductVersion"] {
+        if let Some(value) = msi_meta.get(key) {
+            if !meta.contains_key(key) {
+                meta.insert(key.to_string(), value.clone());
             }
         }
     }
@@ -152,11 +725,311 @@ fn extract_signature_info(buf: &[u8], meta: &mut HashMap<String, String>) {
     }
 }
 
+/// A function imported by name (`IMAGE_IMPORT_BY_NAME`) or by ordinal.
+enum ImportedFunction {
+    ByName(String),
+    ByOrdinal(u16),
+}
+
+/// One import descriptor: the DLL name and the functions imported from it.
+type ImportDescriptor = (String, Vec<ImportedFunction>);
+
+/// The imphash: MD5 of the comma-joined `dll.function` terms (lowercased, the
+/// DLL without its `.dll`/`.ocx`/`.sys` extension) in import table order.
+/// Ordinal imports hash as `ord<N>`; pefile additionally resolves a few
+/// well-known ordinals (ws2_32, wsock32, oleaut32) by name, so hashes can
+/// differ for those. `None` without any imported function.
+fn imphash(descriptors: &[ImportDescriptor]) -> Option<String> {
+    let mut entries = Vec::new();
+    for (dll_name, imports) in descriptors {
+        let dll = dll_name.to_ascii_lowercase();
+        let library = match dll.rsplit_once('.') {
+            Some((stem, "dll" | "ocx" | "sys")) => stem,
+            _ => dll.as_str(),
+        };
+        for import in imports {
+            let function = match import {
+                ImportedFunction::ByName(name) => name.to_ascii_lowercase(),
+                ImportedFunction::ByOrdinal(ordinal) => format!("ord{}", ordinal),
+            };
+            entries.push(format!("{}.{}", library, function));
+        }
+    }
+    (!entries.is_empty()).then(|| digest::to_hex(&digest::md5(entries.join(",").as_bytes())))
+}
+
+fn insert_flags(value: u16, flags: &[(u16, &str)], meta: &mut HashMap<String, String>) {
+    for (flag, name) in flags {
+        meta.insert((*name).to_string(), (value & flag != 0).to_string());
+    }
+}
+
+/// `Architecture` from the COFF `Machine` field. Machines not listed fall back
+/// to the optional header's bitness.
+fn machine_architecture(machine: u16, is_64: bool) -> &'static str {
+    match machine {
+        0x014C => "x86",
+        0x8664 => "x64",
+        0xAA64 => "ARM64",
+        0x01C0 | 0x01C2 | 0x01C4 => "ARM",
+        0x0200 => "IA64",
+        0x5032 => "RISCV32",
+        0x5064 => "RISCV64",
+        0x6264 => "LoongArch64",
+        _ if is_64 => "x64",
+        _ => "x86",
+    }
+}
+
+fn subsystem_name(subsystem: u16) -> Option<&'static str> {
+    match subsystem {
+        1 => Some("Native"),
+        2 => Some("Windows GUI"),
+        3 => Some("Windows Console"),
+        5 => Some("OS/2 Console"),
+        7 => Some("POSIX Console"),
+        9 => Some("Windows CE GUI"),
+        10 => Some("EFI Application"),
+        11 => Some("EFI Boot Service Driver"),
+        12 => Some("EFI Runtime Driver"),
+        13 => Some("EFI ROM"),
+        14 => Some("Xbox"),
+        16 => Some("Windows Boot Application"),
+        _ => None,
+    }
+}
+
+/// `Subsystem` by name (the raw value when unknown) and `IsGuiApp`.
+fn insert_subsystem(subsystem: u16, meta: &mut HashMap<String, String>) {
+    let name = subsystem_name(subsystem).map_or_else(|| subsystem.to_string(), str::to_string);
+    meta.insert("Subsystem".into(), name);
+    meta.insert("IsGuiApp".into(), matches!(subsystem, 2 | 9).to_string());
+}
+
+/// PDB reference from the CodeView debug entry. `PdbGuid` and `PdbAge` are
+/// what a symbol store indexes by; RSDS (CodeView 7.0) is the only modern form.
+fn extract_code_view_info(code_view: &CodeView, meta: &mut HashMap<String, String>) {
+    if let Ok(path) = code_view.pdb_file_name().to_str() {
+        if !path.is_empty() {
+            meta.insert("PdbPath".into(), path.to_string());
+        }
+    }
+    if let CodeView::Cv70 { image, .. } = code_view {
+        let guid = &image.Signature;
+        meta.insert(
+            "PdbGuid".into(),
+            format!(
+                "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{}",
+                guid.Data1,
+                guid.Data2,
+                guid.Data3,
+                guid.Data4[0],
+                guid.Data4[1],
+                guid.Data4[2..].iter().map(|b| format!("{:02X}", b)).collect::<String>()
+            ),
+        );
+    }
+    meta.insert("PdbAge".into(), code_view.age().to_string());
+}
+
+/// Reads the RT_MANIFEST application manifest: `RequestedExecutionLevel`
+/// (asInvoker, highestAvailable, requireAdministrator), `DpiAware` and the
+/// `SupportedOS` compatibility list.
+fn extract_manifest_info(manifest: &str, meta: &mut HashMap<String, String>) {
+    if let Some(level) = xml_element(manifest, "requestedExecutionLevel")
+        .and_then(|tag| xml_attribute(tag, "level"))
+    {
+        meta.insert("RequestedExecutionLevel".into(), level.to_string());
+    }
+
+    // dpiAwareness (Windows 10) supersedes dpiAware when both are declared
+    let dpi_aware = xml_element_text(manifest, "dpiAwareness")
+        .or_else(|| xml_element_text(manifest, "dpiAware"));
+    if let Some(dpi_aware) = dpi_aware {
+        meta.insert("DpiAware".into(), dpi_aware.to_string());
+    }
+
+    let lowercase = manifest.to_ascii_lowercase();
+    let supported_os: Vec<&str> = SUPPORTED_OS_IDS
+        .iter()
+        .filter(|(id, _)| lowercase.contains(id))
+        .map(|(_, name)| *name)
+        .collect();
+    if !supported_os.is_empty() {
+        meta.insert("SupportedOS".into(), supported_os.join(", "));
+    }
+}
+
 #[inline]
 fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if !budget::charge(haystack.len()) {
+        return None;
+    }
     haystack.windows(needle.len()).position(|window| window == needle)
 }
 
+/// Language block name (e.g. `040904B0`) and the strings of one
+/// `StringFileInfo` table.
+type StringTable = (String, Vec<(String, String)>);
+
+/// Collects every `StringFileInfo` table, including those whose block name is
+/// not a valid language or that `\VarFileInfo\Translation` does not list.
+#[derive(Default)]
+struct StringTables(Vec<StringTable>);
+
+impl<'a> Visit<'a> for StringTables {
+    fn string_table(&mut self, lang: &'a [u16]) -> bool {
+        self.0.push((String::from_utf16_lossy(lang), Vec::new()));
+        true
+    }
+
+    fn string(&mut self, key: &'a [u16], value: &'a [u16]) {
+        if let Some((_, strings)) = self.0.last_mut() {
+            strings.push((String::from_utf16_lossy(key), String::from_utf16_lossy(value)));
+        }
+    }
+}
+
+/// Raw bytes of the first `RT_VERSION` resource, whatever its name and language.
+fn version_resource<'a>(rsrc: &Resources<'a>) -> Option<&'a [u8]> {
+    rsrc.root().ok()?.get_dir(Name::VERSION).ok()?.first_dir().ok()?.first_data().ok()?.bytes().ok()
+}
+
+/// One node of a version resource: `wLength`, `wValueLength`, `wType` and a
+/// NUL-terminated UTF-16 key, then the value and the children, each aligned
+/// to 32 bits.
+struct VersionNode<'a> {
+    key: String,
+    value: &'a [u8],
+    children: &'a [u8],
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+fn utf16_text(bytes: &[u8]) -> Vec<u16> {
+    bytes
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect()
+}
+
+fn parse_version_node(node: &[u8]) -> VersionNode<'_> {
+    let value_len = u16::from_le_bytes([node[2], node[3]]) as usize;
+    let is_text = node[4] == 1;
+    let key = utf16_text(&node[6..]);
+    let value_start = align4(6 + (key.len() + 1) * 2).min(node.len());
+    let value_end = (value_start + if is_text { value_len * 2 } else { value_len }).min(node.len());
+    VersionNode {
+        key: String::from_utf16_lossy(&key),
+        value: &node[value_start..value_end],
+        children: node.get(align4(value_end)..).unwrap_or_default(),
+    }
+}
+
+/// Sibling nodes packed in `data`, stopping at the first malformed length.
+fn version_nodes(mut data: &[u8]) -> impl Iterator<Item = VersionNode<'_>> {
+    std::iter::from_fn(move || {
+        let len = u16::from_le_bytes([*data.first()?, *data.get(1)?]) as usize;
+        if len < 6 || len > data.len() {
+            return None;
+        }
+        let node = parse_version_node(&data[..len]);
+        data = data.get(align4(len)..).unwrap_or_default();
+        Some(node)
+    })
+}
+
+/// `StringFileInfo` tables read straight from the resource bytes, for version
+/// resources pelite cannot parse (misaligned data) or yields no strings for.
+fn raw_string_tables(resource: &[u8]) -> Vec<StringTable> {
+    let Some(root) = version_nodes(resource).next() else {
+        return Vec::new();
+    };
+    version_nodes(root.children)
+        .filter(|node| node.key == "StringFileInfo")
+        .flat_map(|file_info| version_nodes(file_info.children))
+        .map(|table| {
+            let strings = version_nodes(table.children)
+                .map(|string| (string.key, String::from_utf16_lossy(&utf16_text(string.value))))
+                .collect();
+            (table.key, strings)
+        })
+        .collect()
+}
+
+fn has_strings(tables: &[StringTable]) -> bool {
+    tables.iter().any(|(_, strings)| strings.iter().any(|(_, value)| !value.is_empty()))
+}
+
+/// Version-info strings from every `StringFileInfo` table: those of the
+/// languages `\VarFileInfo\Translation` lists first, then the rest in file
+/// order, with the first non-empty value of a key winning. Falls back to
+/// parsing `resource` by hand when the typed API yields no strings.
+fn insert_version_strings(ver: Option<VersionInfo<'_>>, resource: Option<&[u8]>, meta: &mut HashMap<String, String>) {
+    let translations = ver.map(|ver| ver.translation()).unwrap_or_default();
+    meta.insert("TranslationCount".into(), translations.len().to_string());
+
+    let mut tables = StringTables::default();
+    if let Some(ver) = ver {
+        ver.visit(&mut tables);
+    }
+    let mut tables = tables.0;
+    if !has_strings(&tables) {
+        if let Some(raw) = resource.map(raw_string_tables).filter(|raw| has_strings(raw)) {
+            tables = raw;
+        }
+    }
+
+    let is_listed = |block: &str| translations.iter().any(|lang| block.eq_ignore_ascii_case(&lang.to_string()));
+    let language = translations
+        .iter()
+        .map(|lang| lang.lang_id)
+        .chain(tables.iter().filter_map(|(block, _)| u16::from_str_radix(block.get(..4)?, 16).ok()))
+        .find_map(locale::language_from_lcid);
+    if let Some(language) = language {
+        meta.insert("Language".into(), language);
+    }
+
+    let mut total = 0;
+    for (idx, lang) in translations.iter().enumerate() {
+        meta.insert(format!("Translation_{}", idx), format!("{:?}", lang));
+
+        let block = lang.to_string();
+        let mut count = 0;
+        for (key, value) in tables.iter().filter(|(name, _)| name.eq_ignore_ascii_case(&block)).flat_map(|(_, strings)| strings) {
+            count += 1;
+            meta.insert(format!("Debug_{}_{}", idx, key), value.clone());
+        }
+        total += count;
+        meta.insert(format!("StringsInTranslation_{}", idx), count.to_string());
+    }
+    meta.insert("TotalCallbackCalls".into(), total.to_string());
+
+    let mut ordered: Vec<&StringTable> = tables.iter().collect();
+    ordered.sort_by_key(|(block, _)| !is_listed(block));
+    let mut all_strings: HashMap<&str, &str> = HashMap::new();
+    for (key, value) in ordered.into_iter().flat_map(|(_, strings)| strings) {
+        if !value.is_empty() {
+            all_strings.entry(key.as_str()).or_insert(value.as_str());
+        }
+    }
+    meta.insert("StringsCount".into(), all_strings.len().to_string());
+
+    if !all_strings.is_empty() {
+        if all_strings.contains_key("CompanyName") {
+            meta.insert("CompanyNameSource".into(), "version-info".into());
+        }
+        for (key, value) in all_strings {
+            meta.insert(key.to_string(), value.to_string());
+        }
+    } else {
+        meta.insert("NoStringsFound".into(), "true".into());
+    }
+}
+
 fn extract_pe32_metadata(buf: &[u8], meta: &mut HashMap<String, String>) {
     if let Ok(image) = PeFile32::from_bytes(&buf) {
         let header = image.file_header();
@@ -165,6 +1038,7 @@ fn extract_pe32_metadata(buf: &[u8], meta: &mut HashMap<String, String>) {
         meta.insert("NumberOfSections".into(), header.NumberOfSections.to_string());
         meta.insert("SizeOfOptionalHeader".into(), header.SizeOfOptionalHeader.to_string());
         meta.insert("Characteristics".into(), format!("0x{:04X}", header.Characteristics));
+        insert_flags(header.Characteristics, &FILE_CHARACTERISTIC_FLAGS, meta);
         meta.insert("PointerToSymbolTable".into(), header.PointerToSymbolTable.to_string());
         meta.insert("NumberOfSymbols".into(), header.NumberOfSymbols.to_string());
 
@@ -178,12 +1052,33 @@ fn extract_pe32_metadata(buf: &[u8], meta: &mut HashMap<String, String>) {
         meta.insert("EntryPoint".into(), format!("0x{:08X}", optional.AddressOfEntryPoint));
         meta.insert("ImageBase".into(), format!("0x{:08X}", optional.ImageBase));
         meta.insert("SizeOfImage".into(), optional.SizeOfImage.to_string());
-        meta.insert("Subsystem".into(), format!("{}", optional.Subsystem));
+        insert_subsystem(optional.Subsystem, meta);
         meta.insert("DllCharacteristics".into(), format!("0x{:04X}", optional.DllCharacteristics));
+        insert_flags(optional.DllCharacteristics, &DLL_CHARACTERISTIC_FLAGS, meta);
+
+        if let Some(code_view) = image.debug().ok().and_then(|debug| {
+            debug.iter().find_map(|dir| match dir.entry() {
+                Ok(DebugEntry::CodeView(code_view)) => Some(code_view),
+                _ => None,
+            })
+        }) {
+            extract_code_view_info(&code_view, meta);
+        }
+
+        let has_repro_entry = image
+            .debug()
+            .is_ok_and(|debug| debug.iter().any(|dir| dir.image().Type == IMAGE_DEBUG_TYPE_REPRO));
+        insert_reproducible_build(header.TimeDateStamp, has_repro_entry, meta);
+
+        let tls_callbacks = image.tls().ok().and_then(|tls| tls.callbacks().ok()).map_or(0, |callbacks| callbacks.len());
+        insert_tls_info(tls_callbacks, meta);
 
         match image.resources() {
             Ok(rsrc) => {
                 meta.insert("HasResources".into(), "true".into());
+                if let Ok(manifest) = rsrc.manifest() {
+                    extract_manifest_info(manifest, meta);
+                }
                 match rsrc.version_info() {
                     Ok(ver) => {
                         meta.insert("HasVersionInfo".into(), "true".into());
@@ -210,47 +1105,13 @@ fn extract_pe32_metadata(buf: &[u8], meta: &mut HashMap<String, String>) {
                             meta.insert("FileType".into(), format!("0x{:08X}", fixed.dwFileType));
                         }
 
-                        let translations = ver.translation();
-                        meta.insert("TranslationCount".into(), translations.len().to_string());
-
-                        let mut all_strings = HashMap::new();
-                        let mut strings_per_lang: Vec<usize> = Vec::new();
-
-                        for (idx, lang) in translations.iter().enumerate() {
-                            meta.insert(format!("Translation_{}", idx), format!("{:?}", lang));
-
-                            let mut count = 0;
-                            ver.strings(*lang, |key, value| {
-                                count += 1;
-                                meta.insert(format!("Debug_{}_{}", idx, key), value.to_string());
-                                if !value.is_empty() {
-                                    all_strings.insert(key.to_string(), value.to_string());
-                                }
-                            });
-
-                            strings_per_lang.push(count);
-                            meta.insert(format!("StringsInTranslation_{}", idx), count.to_string());
-                        }
-
-                        meta.insert("TotalCallbackCalls".into(), strings_per_lang.iter().sum::<usize>().to_string());
-
-                        meta.insert("StringsCount".into(), all_strings.len().to_string());
-
-                        if !all_strings.is_empty() {
-                            for (key, value) in all_strings.iter() {
-                                meta.insert(key.clone(), value.clone());
-                            }
-                        } else {
-                            meta.insert("NoStringsFound".into(), "true".into());
-                            if let Some(company) = meta.get("CompanyName").cloned() {
-                                if meta.contains_key("SignedBy") && !company.contains("from digital signature") {
-                                    meta.insert("CompanyName".into(), format!("{} (from digital signature)", company));
-                                }
-                            }
-                        }
+                        insert_version_strings(Some(ver), version_resource(&rsrc), meta);
                     }
                     Err(e) => {
                         meta.insert("VersionInfoError".into(), format!("{:?}", e));
+                        if let Some(resource) = version_resource(&rsrc) {
+                            insert_version_strings(None, Some(resource), meta);
+                        }
                     }
                 }
             }
@@ -269,6 +1130,7 @@ fn extract_pe64_metadata(buf: &[u8], meta: &mut HashMap<String, String>) {
         meta.insert("NumberOfSections".into(), header.NumberOfSections.to_string());
         meta.insert("SizeOfOptionalHeader".into(), header.SizeOfOptionalHeader.to_string());
         meta.insert("Characteristics".into(), format!("0x{:04X}", header.Characteristics));
+        insert_flags(header.Characteristics, &FILE_CHARACTERISTIC_FLAGS, meta);
         meta.insert("PointerToSymbolTable".into(), header.PointerToSymbolTable.to_string());
         meta.insert("NumberOfSymbols".into(), header.NumberOfSymbols.to_string());
 
@@ -282,12 +1144,33 @@ fn extract_pe64_metadata(buf: &[u8], meta: &mut HashMap<String, String>) {
         meta.insert("EntryPoint".into(), format!("0x{:08X}", optional.AddressOfEntryPoint));
         meta.insert("ImageBase".into(), format!("0x{:016X}", optional.ImageBase));
         meta.insert("SizeOfImage".into(), optional.SizeOfImage.to_string());
-        meta.insert("Subsystem".into(), format!("{}", optional.Subsystem));
+        insert_subsystem(optional.Subsystem, meta);
         meta.insert("DllCharacteristics".into(), format!("0x{:04X}", optional.DllCharacteristics));
+        insert_flags(optional.DllCharacteristics, &DLL_CHARACTERISTIC_FLAGS, meta);
+
+        if let Some(code_view) = image.debug().ok().and_then(|debug| {
+            debug.iter().find_map(|dir| match dir.entry() {
+                Ok(DebugEntry::CodeView(code_view)) => Some(code_view),
+                _ => None,
+            })
+        }) {
+            extract_code_view_info(&code_view, meta);
+        }
+
+        let has_repro_entry = image
+            .debug()
+            .is_ok_and(|debug| debug.iter().any(|dir| dir.image().Type == IMAGE_DEBUG_TYPE_REPRO));
+        insert_reproducible_build(header.TimeDateStamp, has_repro_entry, meta);
+
+        let tls_callbacks = image.tls().ok().and_then(|tls| tls.callbacks().ok()).map_or(0, |callbacks| callbacks.len());
+        insert_tls_info(tls_callbacks, meta);
 
         match image.resources() {
             Ok(rsrc) => {
                 meta.insert("HasResources".into(), "true".into());
+                if let Ok(manifest) = rsrc.manifest() {
+                    extract_manifest_info(manifest, meta);
+                }
                 match rsrc.version_info() {
                     Ok(ver) => {
                         meta.insert("HasVersionInfo".into(), "true".into());
@@ -314,47 +1197,13 @@ fn extract_pe64_metadata(buf: &[u8], meta: &mut HashMap<String, String>) {
                             meta.insert("FileType".into(), format!("0x{:08X}", fixed.dwFileType));
                         }
 
-                        let translations = ver.translation();
-                        meta.insert("TranslationCount".into(), translations.len().to_string());
-
-                        let mut all_strings = HashMap::new();
-                        let mut strings_per_lang: Vec<usize> = Vec::new();
-
-                        for (idx, lang) in translations.iter().enumerate() {
-                            meta.insert(format!("Translation_{}", idx), format!("{:?}", lang));
-
-                            let mut count = 0;
-                            ver.strings(*lang, |key, value| {
-                                count += 1;
-                                meta.insert(format!("Debug_{}_{}", idx, key), value.to_string());
-                                if !value.is_empty() {
-                                    all_strings.insert(key.to_string(), value.to_string());
-                                }
-                            });
-
-                            strings_per_lang.push(count);
-                            meta.insert(format!("StringsInTranslation_{}", idx), count.to_string());
-                        }
-
-                        meta.insert("TotalCallbackCalls".into(), strings_per_lang.iter().sum::<usize>().to_string());
-
-                        meta.insert("StringsCount".into(), all_strings.len().to_string());
-
-                        if !all_strings.is_empty() {
-                            for (key, value) in all_strings.iter() {
-                                meta.insert(key.clone(), value.clone());
-                            }
-                        } else {
-                            meta.insert("NoStringsFound".into(), "true".into());
-                            if let Some(company) = meta.get("CompanyName").cloned() {
-                                if meta.contains_key("SignedBy") && !company.contains("from digital signature") {
-                                    meta.insert("CompanyName".into(), format!("{} (from digital signature)", company));
-                                }
-                            }
-                        }
+                        insert_version_strings(Some(ver), version_resource(&rsrc), meta);
                     }
                     Err(e) => {
                         meta.insert("VersionInfoError".into(), format!("{:?}", e));
+                        if let Some(resource) = version_resource(&rsrc) {
+                            insert_version_strings(None, Some(resource), meta);
+                        }
                     }
                 }
             }
@@ -364,3 +1213,312 @@ fn extract_pe64_metadata(buf: &[u8], meta: &mut HashMap<String, String>) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_manifest_info() {
+        let manifest = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<assembly xmlns="urn:schemas-microsoft-com:asm.v1" manifestVersion="1.0">
+  <trustInfo xmlns="urn:schemas-microsoft-com:asm.v3">
+    <security><requestedPrivileges>
+      <requestedExecutionLevel level="requireAdministrator" uiAccess="false"/>
+    </requestedPrivileges></security>
+  </trustInfo>
+  <compatibility xmlns="urn:schemas-microsoft-com:compatibility.v1"><application>
+    <supportedOS Id="{35138b9a-5d96-4fbd-8e2d-a2440225f93a}"/>
+    <supportedOS Id="{8e0f7a12-bfb3-4fe8-b9a5-48fd50a15a9a}"/>
+  </application></compatibility>
+  <application xmlns="urn:schemas-microsoft-com:asm.v3"><windowsSettings>
+    <dpiAware xmlns="http://schemas.microsoft.com/SMI/2005/WindowsSettings"> true/pm </dpiAware>
+  </windowsSettings></application>
+</assembly>"#;
+
+        let mut meta = HashMap::new();
+        extract_manifest_info(manifest, &mut meta);
+        assert_eq!(meta.get("RequestedExecutionLevel").map(String::as_str), Some("requireAdministrator"));
+        assert_eq!(meta.get("DpiAware").map(String::as_str), Some("true/pm"));
+        assert_eq!(meta.get("SupportedOS").map(String::as_str), Some("Windows 7, Windows 10"));
+    }
+
+    /// A version-resource node with the given key, value and children.
+    fn version_node(key: &str, value: &[u8], is_text: bool, children: &[Vec<u8>]) -> Vec<u8> {
+        let value_len = if is_text { value.len() / 2 } else { value.len() };
+        let mut node = vec![0, 0];
+        node.extend((value_len as u16).to_le_bytes());
+        node.extend([is_text as u8, 0]);
+        node.extend(key.encode_utf16().chain([0]).flat_map(u16::to_le_bytes));
+        node.resize(align4(node.len()), 0);
+        node.extend_from_slice(value);
+        for child in children {
+            node.resize(align4(node.len()), 0);
+            node.extend_from_slice(child);
+        }
+        let len = (node.len() as u16).to_le_bytes();
+        node[..2].copy_from_slice(&len);
+        node
+    }
+
+    fn version_string(key: &str, value: &str) -> Vec<u8> {
+        let value: Vec<u8> = value.encode_utf16().chain([0]).flat_map(u16::to_le_bytes).collect();
+        version_node(key, &value, true, &[])
+    }
+
+    #[test]
+    fn test_version_strings_from_raw_resource() {
+        let english = version_node(
+            "040904B0",
+            &[],
+            true,
+            &[version_string("CompanyName", "Acme"), version_string("ProductName", "")],
+        );
+        // A table no Translation entry lists still contributes its strings
+        let japanese = version_node("041104B0", &[], true, &[version_string("ProductName", "Widget")]);
+        let file_info = version_node("StringFileInfo", &[], true, &[english, japanese]);
+        let resource = version_node("VS_VERSION_INFO", &[0; 52], false, &[file_info]);
+
+        let tables = raw_string_tables(&resource);
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].0, "040904B0");
+
+        let mut meta = HashMap::new();
+        insert_version_strings(None, Some(&resource), &mut meta);
+        assert_eq!(meta.get("CompanyName").map(String::as_str), Some("Acme"));
+        assert_eq!(meta.get("CompanyNameSource").map(String::as_str), Some("version-info"));
+        assert_eq!(meta.get("ProductName").map(String::as_str), Some("Widget"));
+        assert_eq!(meta.get("Language").map(String::as_str), Some("en-US"));
+        assert!(!meta.contains_key("NoStringsFound"));
+
+        let mut meta = HashMap::new();
+        insert_version_strings(None, Some(&resource[..4]), &mut meta);
+        assert_eq!(meta.get("NoStringsFound").map(String::as_str), Some("true"));
+    }
+
+    #[test]
+    fn test_compute_pe_checksum() {
+        // Words 0x0001 and 0xFFFF fold to 1, plus the odd trailing byte 3 and
+        // the length 9; the two words at the CheckSum offset are skipped.
+        let buf = [0x01, 0x00, 0xAA, 0xAA, 0xAA, 0xAA, 0xFF, 0xFF, 0x03];
+        assert_eq!(compute_pe_checksum(&buf, 2), 13);
+    }
+
+    #[test]
+    fn test_machine_architecture() {
+        assert_eq!(machine_architecture(0xAA64, true), "ARM64");
+        assert_eq!(machine_architecture(0x01C4, false), "ARM");
+        assert_eq!(machine_architecture(0x8664, true), "x64");
+        assert_eq!(machine_architecture(0x014C, false), "x86");
+        assert_eq!(machine_architecture(0, true), "x64");
+    }
+
+    #[test]
+    fn test_imphash() {
+        let descriptors: Vec<ImportDescriptor> = vec![
+            ("KERNEL32.dll".into(), vec![ImportedFunction::ByOrdinal(17)]),
+            ("msvcrt.DLL".into(), Vec::new()),
+            ("api-ms-win-crt.x".into(), vec![ImportedFunction::ByOrdinal(2)]),
+        ];
+        let expected = digest::to_hex(&digest::md5(b"kernel32.ord17,api-ms-win-crt.x.ord2"));
+        assert_eq!(imphash(&descriptors), Some(expected));
+        assert_eq!(imphash(&descriptors[1..2]), None);
+    }
+
+    fn put(data: &mut [u8], at: usize, bytes: &[u8]) {
+        data[at..at + bytes.len()].copy_from_slice(bytes);
+    }
+
+    /// A data directory entry: its index, RVA and size.
+    type Directory = (usize, u32, u32);
+
+    /// A 0x400-byte PE32 with `directories` and one section at RVA 0x1000
+    /// (file offset 0x200) starting with `section`.
+    fn pe32_image(directories: &[Directory], section: &[u8]) -> Vec<u8> {
+        let mut data = vec![0u8; 0x400];
+        put(&mut data, 0, b"MZ");
+        put(&mut data, 0x3C, &0x40u32.to_le_bytes());
+        put(&mut data, 0x40, b"PE\0\0");
+        put(&mut data, 0x44, &0x014Cu16.to_le_bytes());
+        put(&mut data, 0x46, &1u16.to_le_bytes());
+        put(&mut data, 0x54, &0xE0u16.to_le_bytes());
+        let optional = 0x58;
+        put(&mut data, optional, &0x010Bu16.to_le_bytes());
+        put(&mut data, optional + 28, &0x0040_0000u32.to_le_bytes());
+        put(&mut data, optional + 32, &0x1000u32.to_le_bytes());
+        put(&mut data, optional + 36, &0x200u32.to_le_bytes());
+        put(&mut data, optional + 56, &0x2000u32.to_le_bytes());
+        put(&mut data, optional + 60, &0x200u32.to_le_bytes());
+        put(&mut data, optional + 92, &16u32.to_le_bytes());
+        for &(index, rva, size) in directories {
+            put(&mut data, optional + 96 + 8 * index, &rva.to_le_bytes());
+            put(&mut data, optional + 100 + 8 * index, &size.to_le_bytes());
+        }
+
+        let header = 0x138;
+        put(&mut data, header, b".text\0\0\0");
+        put(&mut data, header + 8, &0x1000u32.to_le_bytes());
+        put(&mut data, header + 12, &0x1000u32.to_le_bytes());
+        put(&mut data, header + 16, &0x200u32.to_le_bytes());
+        put(&mut data, header + 20, &0x200u32.to_le_bytes());
+        put(&mut data, 0x200, section);
+        data
+    }
+
+    /// A PE32 whose section holds a delay-load descriptor for WINHTTP.dll,
+    /// with a bound import directory in the headers naming KERNEL32.dll with
+    /// a forwarder to NTDLL.dll.
+    fn pe_with_delay_and_bound_imports() -> Vec<u8> {
+        let mut section = vec![0u8; 0x4C];
+        put(&mut section, 0, &DELAY_ATTRIBUTE_RVA.to_le_bytes());
+        put(&mut section, 4, &0x1040u32.to_le_bytes());
+        put(&mut section, 0x40, b"WINHTTP.dll\0");
+        let mut data = pe32_image(&[(11, 0x160, 0), (13, 0x1000, 0)], &section);
+
+        // Bound imports: KERNEL32.dll with one forwarder ref, then the terminator
+        put(&mut data, 0x160 + 4, &[0x18, 0, 1, 0]);
+        put(&mut data, 0x160 + 12, &[0x25, 0]);
+        put(&mut data, 0x160 + 0x18, b"KERNEL32.dll\0NTDLL.dll\0");
+        data
+    }
+
+    #[test]
+    fn test_code_view_pdb_info() {
+        // One IMAGE_DEBUG_DIRECTORY pointing at an RSDS record 0x20 into the section
+        let mut section = vec![0u8; 0x20];
+        put(&mut section, 12, &2u32.to_le_bytes()); // IMAGE_DEBUG_TYPE_CODEVIEW
+        let path = b"C:\\build\\app.pdb\0";
+        put(&mut section, 16, &(24 + path.len() as u32).to_le_bytes());
+        put(&mut section, 20, &0x1020u32.to_le_bytes());
+        put(&mut section, 24, &0x220u32.to_le_bytes());
+        section.extend(b"RSDS");
+        // GUID {12345678-9ABC-DEF0-0102-030405060708}: three little-endian
+        // fields, then eight bytes in order
+        section.extend(0x1234_5678u32.to_le_bytes());
+        section.extend(0x9ABCu16.to_le_bytes());
+        section.extend(0xDEF0u16.to_le_bytes());
+        section.extend([1, 2, 3, 4, 5, 6, 7, 8]);
+        section.extend(3u32.to_le_bytes());
+        section.extend(path);
+
+        let meta = PEAnalyzer.parse_metadata(&pe32_image(&[(6, 0x1000, 28)], &section)).unwrap_or_default();
+        assert_eq!(meta.get("PdbPath").map(String::as_str), Some("C:\\build\\app.pdb"));
+        assert_eq!(meta.get("PdbGuid").map(String::as_str), Some("12345678-9ABC-DEF0-0102-030405060708"));
+        assert_eq!(meta.get("PdbAge").map(String::as_str), Some("3"));
+
+        let meta = PEAnalyzer.parse_metadata(&pe32_image(&[], &[])).unwrap_or_default();
+        assert!(!meta.contains_key("PdbGuid"));
+    }
+
+    #[test]
+    fn test_authentihash_skips_checksum_and_certificate_table() {
+        // Overlay, an 0x40-byte certificate table at 0x440 and more overlay after it
+        let mut data = pe32_image(&[(4, 0x440, 0x40)], &[0xC3]);
+        put(&mut data, 0x58 + 64, &0xDEAD_BEEFu32.to_le_bytes());
+        data.extend(b"SETUPDAT".repeat(8));
+        data.extend(0x40u32.to_le_bytes());
+        data.extend(0x0200u16.to_le_bytes());
+        data.extend(2u16.to_le_bytes()); // WIN_CERT_TYPE_PKCS_SIGNED_DATA
+        data.extend([0xAA; 0x38]);
+        data.extend(b"TRAILER!".repeat(4));
+
+        let hash = |data: &[u8]| PE::parse(data).ok().and_then(|pe| compute_authentihash(data, &pe)).map(|h| digest::to_hex(&h));
+        // SHA-256 of [0, 0x98), [0x9C, 0xD8), [0xE0, 0x440) and [0x480, end)
+        let expected = "91b4af1231b4a73f135d16866a115b32e8a309640155e8ad231b6e06dd57c5da";
+        assert_eq!(hash(&data).as_deref(), Some(expected));
+
+        // Neither the checksum nor the certificate contents are covered...
+        let mut resigned = data.clone();
+        put(&mut resigned, 0x58 + 64, &0u32.to_le_bytes());
+        put(&mut resigned, 0x448, &[0x55; 0x38]);
+        assert_eq!(hash(&resigned).as_deref(), Some(expected));
+        // ...but the overlay on either side of it is
+        let mut patched = data.clone();
+        put(&mut patched, 0x480, b"PATCHED!");
+        assert_ne!(hash(&patched).as_deref(), Some(expected));
+    }
+
+    #[test]
+    fn test_tls_callbacks() {
+        // IMAGE_TLS_DIRECTORY32 whose AddressOfCallBacks points at a
+        // null-terminated array of two callback VAs 0x20 into the section
+        let mut section = vec![0u8; 0x2C];
+        put(&mut section, 12, &0x0040_1020u32.to_le_bytes());
+        put(&mut section, 0x20, &0x0040_1100u32.to_le_bytes());
+        put(&mut section, 0x24, &0x0040_1180u32.to_le_bytes());
+        let meta = PEAnalyzer.parse_metadata(&pe32_image(&[(9, 0x1000, 24)], &section)).unwrap_or_default();
+        assert_eq!(meta.get("HasTlsCallbacks").map(String::as_str), Some("true"));
+        assert_eq!(meta.get("TlsCallbackCount").map(String::as_str), Some("2"));
+
+        let meta = PEAnalyzer.parse_metadata(&pe32_image(&[], &[])).unwrap_or_default();
+        assert_eq!(meta.get("HasTlsCallbacks").map(String::as_str), Some("false"));
+        assert!(!meta.contains_key("TlsCallbackCount"));
+    }
+
+    #[test]
+    fn test_delay_and_bound_imports() {
+        let meta = PEAnalyzer.parse_metadata(&pe_with_delay_and_bound_imports()).unwrap_or_default();
+        assert_eq!(meta.get("ImportedDlls").map(String::as_str), Some("WINHTTP.dll, KERNEL32.dll, NTDLL.dll"));
+        assert_eq!(meta.get("DelayLoadedDlls").map(String::as_str), Some("WINHTTP.dll"));
+    }
+
+    #[test]
+    fn test_import_descriptors() {
+        // KERNEL32.dll importing ExitProcess by name and ordinal 17
+        let mut section = vec![0u8; 0x70];
+        put(&mut section, 0, &0x1030u32.to_le_bytes());
+        put(&mut section, 12, &0x1050u32.to_le_bytes());
+        put(&mut section, 16, &0x1030u32.to_le_bytes());
+        put(&mut section, 0x30, &0x1060u32.to_le_bytes());
+        put(&mut section, 0x34, &0x8000_0011u32.to_le_bytes());
+        put(&mut section, 0x50, b"KERNEL32.dll\0");
+        put(&mut section, 0x62, b"ExitProcess\0");
+        let data = pe32_image(&[(1, 0x1000, 40)], &section);
+
+        let meta = PEAnalyzer.parse_metadata(&data).unwrap_or_default();
+        let expected = digest::to_hex(&digest::md5(b"kernel32.exitprocess,kernel32.ord17"));
+        assert_eq!(meta.get("ImpHash"), Some(&expected));
+        assert_eq!(meta.get("ImportedDlls").map(String::as_str), Some("KERNEL32.dll"));
+    }
+
+    #[test]
+    fn test_find_contained_archive() {
+        let mut overlay = vec![0u8; 64];
+        // 46-byte central directory at archive offset 2, so the archive starts at 16
+        overlay[16..20].copy_from_slice(b"PK\x03\x04");
+        let mut eocd = ZIP_EOCD_SIGNATURE.to_vec();
+        eocd.extend_from_slice(&[0, 0, 0, 0, 3, 0, 3, 0]);
+        eocd.extend_from_slice(&46u32.to_le_bytes());
+        eocd.extend_from_slice(&2u32.to_le_bytes());
+        eocd.extend_from_slice(&[0, 0]);
+        overlay.extend_from_slice(&eocd);
+
+        let archive = find_contained_archive(&overlay);
+        assert_eq!(archive.as_ref().map(|a| a.kind), Some("ZIP"));
+        assert_eq!(archive.as_ref().and_then(|a| a.entries), Some(3));
+        assert_eq!(archive.map(|a| a.offset), Some(16));
+
+        let mut overlay = vec![0u8; 32];
+        overlay.extend_from_slice(SEVEN_ZIP_SIGNATURE);
+        let archive = find_contained_archive(&overlay);
+        assert_eq!(archive.as_ref().map(|a| a.kind), Some("7z"));
+        assert_eq!(archive.map(|a| a.offset), Some(32));
+        assert!(find_contained_archive(&[0u8; 8]).is_none());
+    }
+
+    #[test]
+    fn test_compound_file_offsets_and_padding() {
+        let mut buf = MSI_SIGNATURE.to_vec();
+        buf.extend_from_slice(&[0u8; 24]);
+        let header_at = buf.len();
+        buf.extend_from_slice(MSI_SIGNATURE);
+        buf.resize(header_at + 0x1C, 0);
+        buf.extend_from_slice(&[0xFE, 0xFF, 9, 0]);
+        buf.resize(header_at + 700, 0);
+
+        // The first signature has no byte order mark behind it
+        assert_eq!(compound_file_offsets(&buf).collect::<Vec<_>>(), vec![header_at]);
+        #[cfg(feature = "msi")]
+        assert_eq!(compound_file_at(&buf, header_at).len(), 1024);
+    }
+}
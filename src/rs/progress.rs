@@ -0,0 +1,181 @@
+//! Progress reporting for one analysis. The full-file passes that dominate
+//! large inputs (Authenticode hashing, deep-scan string extraction) report how
+//! far they got, and a `Session` turns that into a fraction of the whole
+//! analysis for the caller's callback, e.g. a progress bar in the browser.
+
+use std::cell::RefCell;
+
+/// Bytes a scan processes between two `report` calls.
+pub const REPORT_INTERVAL: usize = 1 << 20;
+/// Smallest change in the fraction worth a callback (and, in WASM, a call
+/// across the JS boundary).
+const REPORT_STEP: f64 = 0.01;
+
+/// A slice of the whole analysis: it starts at `start`, spans `width` and has
+/// used up `cursor` (0..=1) of itself on completed sub-stages.
+struct Stage {
+    start: f64,
+    width: f64,
+    cursor: f64,
+}
+
+impl Stage {
+    fn at(&self, fraction: f64) -> f64 {
+        self.start + self.width * fraction.clamp(0.0, 1.0)
+    }
+}
+
+pub type Callback = Box<dyn FnMut(f64)>;
+
+struct Progress {
+    callback: Option<Callback>,
+    stages: Vec<Stage>,
+    reported: f64,
+}
+
+thread_local! {
+    static PROGRESS: RefCell<Option<Progress>> = const { RefCell::new(None) };
+}
+
+/// Passes `fraction` to the callback when it moved at least `REPORT_STEP`
+/// past the last one (or reached 1), so the reported value never goes back.
+fn emit(fraction: f64) {
+    let callback = PROGRESS.with_borrow_mut(|progress| {
+        let progress = progress.as_mut()?;
+        if fraction < progress.reported + REPORT_STEP && !(fraction >= 1.0 && progress.reported < 1.0) {
+            return None;
+        }
+        progress.reported = fraction;
+        progress.callback.take()
+    });
+    // Called without the borrow held, so the callback may itself analyze
+    if let Some(mut callback) = callback {
+        callback(fraction);
+        PROGRESS.with_borrow_mut(|progress| {
+            if let Some(progress) = progress.as_mut() {
+                progress.callback = Some(callback);
+            }
+        });
+    }
+}
+
+/// Progress reporting for one analysis: `callback` gets the fraction of the
+/// work done (0..=1) as it progresses and 1 on `finish`. A session started
+/// inside one that already reports progress is inactive.
+pub struct Session {
+    active: bool,
+}
+
+impl Session {
+    pub fn start(callback: Callback) -> Self {
+        let active = PROGRESS.with_borrow(Option::is_none);
+        if active {
+            PROGRESS.set(Some(Progress {
+                callback: Some(callback),
+                stages: vec![Stage { start: 0.0, width: 1.0, cursor: 0.0 }],
+                reported: 0.0,
+            }));
+        }
+        Session { active }
+    }
+
+    /// Reports 1 and ends the session.
+    pub fn finish(self) {
+        if self.active {
+            emit(1.0);
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if self.active {
+            PROGRESS.set(None);
+        }
+    }
+}
+
+/// Opens the next `share` (0..=1) of the current stage, so the scans run
+/// while the returned scope lives report relative to that slice. Inert
+/// outside a `Session`.
+pub fn stage(share: f64) -> StageScope {
+    let pushed = PROGRESS.with_borrow_mut(|progress| {
+        let stages = &mut progress.as_mut()?.stages;
+        let parent = stages.last()?;
+        let stage = Stage { start: parent.at(parent.cursor), width: parent.width * share, cursor: 0.0 };
+        stages.push(stage);
+        Some(())
+    });
+    StageScope { share, pushed: pushed.is_some() }
+}
+
+/// A stage opened by `stage`; dropping it closes the stage and reports its
+/// end.
+pub struct StageScope {
+    share: f64,
+    pushed: bool,
+}
+
+impl Drop for StageScope {
+    fn drop(&mut self) {
+        if !self.pushed {
+            return;
+        }
+        let share = self.share;
+        let end = PROGRESS.with_borrow_mut(|progress| {
+            let stages = &mut progress.as_mut()?.stages;
+            stages.pop();
+            let parent = stages.last_mut()?;
+            parent.cursor = (parent.cursor + share).min(1.0);
+            Some(parent.at(parent.cursor))
+        });
+        // An unwinding analysis reports nothing more
+        if let Some(end) = end.filter(|_| !std::thread::panicking()) {
+            emit(end);
+        }
+    }
+}
+
+/// Reports that the running scan has processed `done` of `total` bytes of the
+/// current stage. Cheap outside a `Session`.
+pub fn report(done: usize, total: usize) {
+    if total == 0 {
+        return;
+    }
+    let fraction = PROGRESS.with_borrow(|progress| {
+        let stage = progress.as_ref()?.stages.last()?;
+        Some(stage.at(done as f64 / total as f64))
+    });
+    if let Some(fraction) = fraction {
+        emit(fraction);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_progress_stages() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&seen);
+        let session = Session::start(Box::new(move |fraction| sink.borrow_mut().push((fraction * 100.0).round() as u32)));
+        {
+            let _stage = stage(0.5);
+            report(1, 4);
+            report(1, 1000); // behind the last report: dropped
+            report(2, 4);
+        }
+        {
+            let _stage = stage(0.5);
+            report(1, 2);
+        }
+        session.finish();
+        assert_eq!(*seen.borrow(), [13, 25, 50, 75, 100]);
+
+        // Without a callback installed nothing is recorded
+        report(1, 2);
+        assert_eq!(seen.borrow().len(), 5);
+    }
+}
@@ -0,0 +1,147 @@
+//! Where each field's value came from, for the opt-in `_sources` map: parsed
+//! from the format's own structures, scraped by a heuristic scan, taken from
+//! a digital signature or copied from an embedded object. Analyzers only mark
+//! the exceptions with a `Tagging`; every other field is structured.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Structured,
+    Heuristic,
+    #[cfg(feature = "pe")]
+    Signature,
+    Embedded,
+}
+
+impl Source {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Source::Structured => "structured",
+            Source::Heuristic => "heuristic",
+            #[cfg(feature = "pe")]
+            Source::Signature => "signature",
+            Source::Embedded => "embedded",
+        }
+    }
+}
+
+/// Fields marked with a source other than `Structured`.
+pub type Sources = HashMap<String, Source>;
+
+thread_local! {
+    static SOURCES: RefCell<Option<Sources>> = const { RefCell::new(None) };
+}
+
+/// Collects the sources the analysis marks while it runs. Dropping it, on
+/// `finish` or while unwinding, restores the recording it was nested in.
+pub struct Recording {
+    previous: Option<Sources>,
+}
+
+impl Recording {
+    pub fn start() -> Self {
+        Recording { previous: SOURCES.replace(Some(HashMap::new())) }
+    }
+
+    /// The sources marked since `start`.
+    pub fn finish(self) -> Sources {
+        SOURCES.take().unwrap_or_default()
+    }
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        SOURCES.set(self.previous.take());
+    }
+}
+
+/// Attributes every field added or changed between `begin` and `finish` to
+/// one source, whatever the code run in between marked them as. Inert
+/// outside a `Recording`.
+pub struct Tagging {
+    source: Source,
+    before: Option<HashMap<String, String>>,
+    /// Collects, and on drop discards, the marks made in between.
+    nested: Option<Recording>,
+}
+
+impl Tagging {
+    pub fn begin(source: Source, meta: &HashMap<String, String>) -> Self {
+        if SOURCES.with_borrow(Option::is_none) {
+            return Tagging { source, before: None, nested: None };
+        }
+        Tagging { source, before: Some(meta.clone()), nested: Some(Recording::start()) }
+    }
+
+    /// Marks the fields of `meta` that differ from those at `begin`.
+    pub fn finish(self, meta: &HashMap<String, String>) {
+        let Tagging { source, before, nested } = self;
+        drop(nested);
+        let Some(before) = before else {
+            return;
+        };
+        SOURCES.with_borrow_mut(|sources| {
+            let Some(sources) = sources.as_mut() else {
+                return;
+            };
+            for (key, value) in meta {
+                if before.get(key) != Some(value) {
+                    sources.insert(key.clone(), source);
+                }
+            }
+        });
+    }
+}
+
+/// Gives `to`, a copy of `from`, the source of `from`.
+pub fn derive(to: &str, from: &str) {
+    SOURCES.with_borrow_mut(|sources| {
+        let Some(sources) = sources.as_mut() else {
+            return;
+        };
+        match sources.get(from).copied() {
+            Some(source) => sources.insert(to.to_string(), source),
+            None => sources.remove(to),
+        };
+    });
+}
+
+/// The `_sources` object for `meta`: every field but `Format` with its source.
+pub fn sources_json(meta: &HashMap<String, String>, sources: &Sources) -> String {
+    let map: BTreeMap<&str, &str> = meta
+        .keys()
+        .filter(|key| *key != "Format")
+        .map(|key| (key.as_str(), sources.get(key).copied().unwrap_or(Source::Structured).as_str()))
+        .collect();
+    serde_json::to_string(&map).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tagging_marks_changed_fields() {
+        let mut meta = HashMap::from([("ProductName".to_string(), "Widget".to_string())]);
+        let recording = Recording::start();
+        let heuristic = Tagging::begin(Source::Heuristic, &meta);
+        meta.insert("ProductName".into(), "Widget".into());
+        meta.insert("ProductVersion".into(), "1.0".into());
+        // Nested marks give way to the enclosing one
+        let embedded = Tagging::begin(Source::Embedded, &meta);
+        meta.insert("Publisher".into(), "Acme".into());
+        embedded.finish(&meta);
+        heuristic.finish(&meta);
+        derive("VersionNormalized", "ProductVersion");
+        let sources = recording.finish();
+        meta.insert("VersionNormalized".into(), "1.0".into());
+
+        let json = sources_json(&meta, &sources);
+        assert_eq!(
+            json,
+            r#"{"ProductName":"structured","ProductVersion":"heuristic","Publisher":"heuristic","VersionNormalized":"heuristic"}"#
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Native entry points over a seekable reader or a path, for callers that
+//! hold large files on disk rather than in memory. Formats whose fields all
+//! come from a known leading region (`FileAnalyzer::metadata_len`: RPM, ISO
+//! 9660) are analyzed from that prefix. Everything else loads the whole file,
+//! as does every deep scan: the PE image hash, checksum and overlay, and the
+//! DMG trailer and product scan, cover the whole file.
+
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::{analyze_with_options, detect, AnalysisOptions, AnalysisResult, AnalyzeError};
+
+/// Bytes read from the start of the file to detect its format: enough for
+/// every signature, including the ISO 9660 descriptor set.
+const HEAD_SIZE: usize = 64 * 1024;
+/// Bytes read from the end of the file for trailer signatures (the DMG koly
+/// block).
+const TAIL_SIZE: usize = 512;
+/// Times an analyzer may ask for a longer prefix, e.g. once per RPM header.
+const MAX_PREFIX_ROUNDS: usize = 4;
+
+/// A seekable source `analyze_reader` can read a file from.
+pub trait ReadSeek: Read + Seek {}
+
+impl ReadSeek for File {}
+impl ReadSeek for BufReader<File> {}
+impl ReadSeek for Cursor<&[u8]> {}
+impl ReadSeek for Cursor<Vec<u8>> {}
+
+fn io_error(e: std::io::Error) -> AnalyzeError {
+    AnalyzeError::Io(format!("Failed to read file: {}", e))
+}
+
+/// Up to `len` bytes at `offset`.
+fn read_at(reader: &mut dyn ReadSeek, offset: usize, len: usize) -> Result<Vec<u8>, AnalyzeError> {
+    reader.seek(SeekFrom::Start(offset as u64)).map_err(io_error)?;
+    let mut buf = Vec::new();
+    reader.take(len as u64).read_to_end(&mut buf).map_err(io_error)?;
+    Ok(buf)
+}
+
+/// The leading bytes of a `len`-byte file that hold everything its analyzer
+/// reads, or the whole file when that is not known without loading it.
+fn metadata_prefix(reader: &mut dyn ReadSeek, len: usize, options: &AnalysisOptions) -> Result<Vec<u8>, AnalyzeError> {
+    if options.deep_scan || len <= HEAD_SIZE + TAIL_SIZE {
+        return read_at(reader, 0, len);
+    }
+    let mut prefix = read_at(reader, 0, HEAD_SIZE)?;
+    let mut probe = prefix.clone();
+    probe.extend(read_at(reader, len - TAIL_SIZE, TAIL_SIZE)?);
+    // Detected on both ends, so a trailer-signed format is never taken for
+    // the one its head looks like
+    let Some(analyzer) = detect(&probe) else {
+        return read_at(reader, 0, len);
+    };
+    for _ in 0..MAX_PREFIX_ROUNDS {
+        match analyzer.metadata_len(&prefix) {
+            Some(needed) if needed <= prefix.len() => return Ok(prefix),
+            Some(needed) if needed < len => prefix = read_at(reader, 0, needed)?,
+            _ => break,
+        }
+    }
+    read_at(reader, 0, len)
+}
+
+/// `analyze_with_options` on the contents of `reader`, reading only the
+/// leading bytes its format's fields come from when the analyzer knows them
+/// (RPM, ISO 9660) and the whole stream otherwise.
+pub fn analyze_reader(reader: &mut dyn ReadSeek, options: &AnalysisOptions) -> Result<AnalysisResult, AnalyzeError> {
+    let len = reader.seek(SeekFrom::End(0)).map_err(io_error)?;
+    let len = usize::try_from(len).map_err(|_| AnalyzeError::Io("File too large to analyze".to_string()))?;
+    let data = metadata_prefix(reader, len, options)?;
+    analyze_with_options(&data, &options.clone().with_file_size(len))
+}
+
+/// `analyze_reader` on the file at `path`.
+pub fn analyze_path(path: &Path, options: &AnalysisOptions) -> Result<AnalysisResult, AnalyzeError> {
+    let mut file = File::open(path).map_err(io_error)?;
+    analyze_reader(&mut file, options)
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(any(feature = "iso", feature = "rpm"))]
+    use super::*;
+
+    #[cfg(feature = "iso")]
+    #[test]
+    fn test_analyze_reader_matches_buffer() {
+        // An ISO whose primary volume descriptor says it is 1 MiB, padded to
+        // that size: only the descriptor area is read
+        let mut iso = vec![0u8; 1024 * 1024];
+        let pvd = 0x8000;
+        iso[pvd] = 1;
+        iso[pvd + 1..pvd + 6].copy_from_slice(b"CD001");
+        iso[pvd + 40..pvd + 46].copy_from_slice(b"VOLUME");
+        iso[pvd + 80..pvd + 84].copy_from_slice(&512u32.to_le_bytes());
+        iso[pvd + 84..pvd + 88].copy_from_slice(&512u32.to_be_bytes());
+        iso[pvd + 128..pvd + 130].copy_from_slice(&2048u16.to_le_bytes());
+        iso[pvd + 130..pvd + 132].copy_from_slice(&2048u16.to_be_bytes());
+        let terminator = pvd + 2048;
+        iso[terminator] = 255;
+        iso[terminator + 1..terminator + 6].copy_from_slice(b"CD001");
+
+        let options = AnalysisOptions::default();
+        let expected = analyze_with_options(&iso, &options).map(|result| result.fields);
+        let mut reader = Cursor::new(iso.as_slice());
+        assert_eq!(metadata_prefix(&mut reader, iso.len(), &options).unwrap_or_default().len(), HEAD_SIZE);
+        assert_eq!(analyze_reader(&mut reader, &options).map(|result| result.fields), expected);
+
+        // Cut short, it is still reported as truncated against the real length
+        iso.truncate(512 * 1024);
+        let truncated = analyze_reader(&mut Cursor::new(iso.as_slice()), &options).map(|result| result.fields);
+        assert_eq!(truncated, analyze_with_options(&iso, &options).map(|result| result.fields));
+        assert_eq!(truncated.unwrap_or_default().get("Truncated").map(String::as_str), Some("true"));
+    }
+
+    #[cfg(feature = "rpm")]
+    #[test]
+    fn test_rpm_payload_measured_against_whole_file() {
+        let mut signature = vec![0x8E, 0xAD, 0xE8, 0x01, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 4];
+        for field in [269u32, 6, 0, 1] {
+            signature.extend_from_slice(&field.to_be_bytes());
+        }
+        signature.extend_from_slice(b"abc\0");
+        let mut header = vec![0x8E, 0xAD, 0xE8, 0x01, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 8];
+        for field in [1000u32, 6, 0, 1, 1009, 4, 4, 1] {
+            header.extend_from_slice(&field.to_be_bytes());
+        }
+        header.extend_from_slice(b"foo\0");
+        header.extend_from_slice(&(4u32 << 20).to_be_bytes());
+        let mut rpm = vec![0u8; 96];
+        rpm[0..4].copy_from_slice(&[0xED, 0xAB, 0xEE, 0xDB]);
+        rpm.extend([signature, vec![0; 4], header].concat());
+        let payload_start = rpm.len();
+        rpm.resize(1024 * 1024, 0x1F);
+
+        let options = AnalysisOptions::default();
+        let mut reader = Cursor::new(rpm.as_slice());
+        assert!(metadata_prefix(&mut reader, rpm.len(), &options).unwrap_or_default().len() < rpm.len());
+        let fields = analyze_reader(&mut reader, &options).map(|result| result.fields).unwrap_or_default();
+        assert_eq!(Ok(&fields), analyze_with_options(&rpm, &options).map(|result| result.fields).as_ref());
+        assert_eq!(fields.get("CompressedSize"), Some(&(rpm.len() - payload_start).to_string()));
+    }
+}
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{Confidence, FormatInfo};
+
+/// Typed result of `analyze`: the detected format plus the same metadata map
+/// `parse_metadata` produces.
+#[derive(Debug, Clone)]
+pub struct AnalysisResult {
+    pub format: &'static FormatInfo,
+    pub confidence: Confidence,
+    pub fields: HashMap<String, String>,
+}
+
+impl AnalysisResult {
+    /// A metadata field by its key, e.g. `result.get("ProductVersion")`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields.get(key).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnalyzeError {
+    /// No analyzer in this build recognized the data; carries the list of
+    /// supported formats, as in the WASM `error` message.
+    UnsupportedFormat(String),
+    /// The input is empty or shorter than any supported format's signature.
+    TooSmall(String),
+    /// The format was recognized but its structure could not be parsed.
+    Malformed(String),
+    /// The analysis spent its work budget (`AnalysisOptions::work_budget`)
+    /// before finishing, typically on a crafted input.
+    ResourceLimitExceeded(String),
+    /// `analyze_reader` or `analyze_path` could not read the file.
+    Io(String),
+}
+
+impl fmt::Display for AnalyzeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalyzeError::UnsupportedFormat(message)
+            | AnalyzeError::TooSmall(message)
+            | AnalyzeError::Malformed(message)
+            | AnalyzeError::ResourceLimitExceeded(message)
+            | AnalyzeError::Io(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for AnalyzeError {}
@@ -1,19 +1,76 @@
 use std::collections::HashMap;
-use crate::{FileAnalyzer, MetadataResult};
+use crate::{digest, locale, Confidence, FileAnalyzer, FormatInfo, MetadataResult};
 
 pub struct RPMAnalyzer;
 
 const RPM_LEAD_MAGIC: &[u8] = &[0xED, 0xAB, 0xEE, 0xDB];
 const RPM_HEADER_MAGIC: &[u8] = &[0x8E, 0xAD, 0xE8, 0x01];
+const RPM_LEAD_SIZE: usize = 96;
+/// How far a header is searched for when it is not where the structure
+/// before it says, e.g. after an oddly sized lead or signature padding.
+const HEADER_SCAN_WINDOW: usize = 4096;
+/// The signature header is padded to this; writers get it wrong.
+const HEADER_ALIGNMENT: usize = 8;
+const RPM_STRING_ARRAY_TYPE: u32 = 8;
+/// `HEADERI18NTABLE`: the locales, `C` first, that I18NSTRING entries index.
+const RPM_I18N_TABLE_TAG: u32 = 100;
+
+/// I18NSTRING tags, one translation per `HEADERI18NTABLE` locale, and their
+/// metadata names.
+const LOCALIZED_TAGS: [(u32, &str); 3] = [
+    (1004, "Summary"),
+    (1005, "Description"),
+    (1016, "GroupName"),
+];
+
+/// `OLDFILENAMES`: full paths, in packages predating compressed file lists.
+const RPM_OLD_FILENAMES_TAG: u32 = 1027;
+/// Compressed file list: each `BASENAMES` entry belongs in the `DIRNAMES`
+/// entry its `DIRINDEXES` entry (INT32) points at.
+const RPM_DIRINDEXES_TAG: u32 = 1116;
+const RPM_BASENAMES_TAG: u32 = 1117;
+const RPM_DIRNAMES_TAG: u32 = 1118;
+/// Installed size of the files: `LONGSIZE` (INT64) in packages with files
+/// of 4 GiB or more, `SIZE` (INT32) otherwise.
+const RPM_LONGSIZE_TAG: u32 = 5009;
+const RPM_SIZE_TAG: u32 = 1009;
+
+/// (script tag, interpreter tag, metadata name)
+type Scriptlet = (u32, u32, &'static str);
+
+/// Install-time scriptlets. `%post -p /sbin/ldconfig` style scriptlets have
+/// an interpreter but no body.
+const SCRIPTLETS: [Scriptlet; 4] = [
+    (1023, 1085, "PreInstall"),
+    (1024, 1086, "PostInstall"),
+    (1025, 1087, "PreUninstall"),
+    (1026, 1088, "PostUninstall"),
+];
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "RPM",
+    mime_type: "application/x-rpm",
+    extensions: &["rpm"],
+};
 
 impl FileAnalyzer for RPMAnalyzer {
-    fn get_file_info(_data: &[u8]) -> HashMap<String, String> {
-        let mut info = HashMap::new();
-        info.insert("Format".to_string(), "RPM".to_string());
-        info
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
     }
 
-    fn parse_metadata(data: &[u8]) -> MetadataResult {
+    fn sniff(&self, data: &[u8]) -> bool {
+        is_rpm_file(data)
+    }
+
+    fn detection_confidence(&self, data: &[u8]) -> Confidence {
+        if has_header_magic(data) {
+            Confidence::High
+        } else {
+            Confidence::Medium
+        }
+    }
+
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
         let mut meta = HashMap::new();
         meta.insert("Format".into(), "RPM".into());
 
@@ -24,25 +81,101 @@ impl FileAnalyzer for RPMAnalyzer {
         // RPM Lead is 96 bytes
         // We can extract basic info from lead if needed, but the real metadata is in the header
 
-        let mut offset = 96;
-
-        // Skip Signature Header
-        offset = skip_header_structure(data, offset)?;
-
-        // The next structure is the Immutable Header
+        let offset = immutable_header_offset(data)?;
         parse_header_structure(data, offset, &mut meta)?;
 
+        // The payload is whatever follows the header
+        if let (Some(installed), Some(payload_start)) = (installed_size(data, offset), header_end(data, offset)) {
+            meta.insert("CompressedSize".into(), data.len().saturating_sub(payload_start).to_string());
+            meta.insert("UncompressedSize".into(), installed.to_string());
+        }
 
         Ok(meta)
     }
+
+    fn file_list(&self, data: &[u8], limit: usize) -> Vec<String> {
+        match immutable_header_offset(data) {
+            Ok(offset) => header_file_list(data, offset, limit),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn absolute_file_paths(&self) -> bool {
+        true
+    }
+
+    fn payload_offset(&self, data: &[u8]) -> Option<usize> {
+        header_end(data, immutable_header_offset(data).ok()?)
+    }
+
+    /// The lead and both headers, plus room for headers found by scanning.
+    fn metadata_len(&self, head: &[u8]) -> Option<usize> {
+        self.expected_size(head).map(|size| size.saturating_add(HEADER_SCAN_WINDOW))
+    }
+
+    /// Lead, signature header and immutable header, wherever the headers were
+    /// found; the payload that follows has no recorded size.
+    fn expected_size(&self, data: &[u8]) -> Option<usize> {
+        let signature = locate_header(data, RPM_LEAD_SIZE, RPM_LEAD_SIZE).unwrap_or(RPM_LEAD_SIZE);
+        if data.len() < signature.saturating_add(16) {
+            return Some(signature.saturating_add(16));
+        }
+        let offset = immutable_header_offset(data).ok()?;
+        if data.len() < offset.saturating_add(16) {
+            return Some(offset.saturating_add(16));
+        }
+        header_end(data, offset)
+    }
 }
 
 pub fn is_rpm_file(data: &[u8]) -> bool {
     data.len() >= 4 && &data[0..4] == RPM_LEAD_MAGIC
 }
 
+/// Returns true when the signature header magic follows the 96-byte lead.
+fn has_header_magic(data: &[u8]) -> bool {
+    locate_header(data, RPM_LEAD_SIZE, RPM_LEAD_SIZE).is_some()
+}
+
+/// `expected` when a header starts there, otherwise the first header within
+/// `HEADER_SCAN_WINDOW` bytes of `from`.
+fn locate_header(data: &[u8], expected: usize, from: usize) -> Option<usize> {
+    // The magic is followed by four reserved zero bytes
+    let is_header = |at: usize| {
+        data.get(at..at.saturating_add(8))
+            .is_some_and(|intro| &intro[..4] == RPM_HEADER_MAGIC && intro[4..] == [0; 4])
+    };
+    if is_header(expected) {
+        return Some(expected);
+    }
+    let end = from.saturating_add(HEADER_SCAN_WINDOW).min(data.len());
+    (from..end).find(|&at| is_header(at))
+}
+
+/// Offset of the immutable header, past the lead and the signature header.
+/// Either header is searched for when it is not at its computed offset; if
+/// that fails too, parsing at the computed offset reports what is wrong.
+fn immutable_header_offset(data: &[u8]) -> Result<usize, String> {
+    let signature = locate_header(data, RPM_LEAD_SIZE, RPM_LEAD_SIZE).unwrap_or(RPM_LEAD_SIZE);
+    let expected = skip_header_structure(data, signature)?;
+    // The immutable header may start before the padded end
+    Ok(locate_header(data, expected, expected.saturating_sub(HEADER_ALIGNMENT - 1)).unwrap_or(expected))
+}
+
+/// Unpadded end of the header at `offset`; after the immutable header, where
+/// the payload starts.
+fn header_end(data: &[u8], offset: usize) -> Option<usize> {
+    let intro = data.get(offset..offset.checked_add(16)?)?;
+    if &intro[..4] != RPM_HEADER_MAGIC {
+        return None;
+    }
+    let index_count = u32::from_be_bytes([intro[8], intro[9], intro[10], intro[11]]) as usize;
+    let store_size = u32::from_be_bytes([intro[12], intro[13], intro[14], intro[15]]) as usize;
+    index_count.checked_mul(16)?.checked_add(store_size)?.checked_add(16)?.checked_add(offset)
+}
+
 fn skip_header_structure(data: &[u8], offset: usize) -> Result<usize, String> {
-    if data.len() < offset + 16 {
+    if data.len() < offset.saturating_add(16) {
         return Err("File too small for Header structure".into());
     }
 
@@ -53,16 +186,19 @@ fn skip_header_structure(data: &[u8], offset: usize) -> Result<usize, String> {
     let index_count = u32::from_be_bytes([data[offset + 8], data[offset + 9], data[offset + 10], data[offset + 11]]) as usize;
     let store_size = u32::from_be_bytes([data[offset + 12], data[offset + 13], data[offset + 14], data[offset + 15]]) as usize;
 
-    let total_size = 16 + (index_count * 16) + store_size;
-
-    // Header structure is padded to 8 bytes
-    let padded_size = (total_size + 7) & !7;
+    // Checked so a crafted count cannot wrap on 32-bit (WASM) targets
+    let padded_size = index_count
+        .checked_mul(16)
+        .and_then(|size| size.checked_add(store_size))
+        .and_then(|size| size.checked_add(16 + HEADER_ALIGNMENT - 1))
+        .map(|size| size & !(HEADER_ALIGNMENT - 1)) // Header structure is padded to 8 bytes
+        .ok_or("RPM Header size overflows")?;
 
-    Ok(offset + padded_size)
+    offset.checked_add(padded_size).ok_or_else(|| "RPM Header size overflows".to_string())
 }
 
 fn parse_header_structure(data: &[u8], offset: usize, meta: &mut HashMap<String, String>) -> Result<(), String> {
-    if data.len() < offset + 16 {
+    if data.len() < offset.saturating_add(16) {
         return Err("File too small for Immutable Header".into());
     }
 
@@ -74,20 +210,51 @@ fn parse_header_structure(data: &[u8], offset: usize, meta: &mut HashMap<String,
     let store_size = u32::from_be_bytes([data[offset + 12], data[offset + 13], data[offset + 14], data[offset + 15]]) as usize;
 
     let index_start = offset + 16;
-    let store_start = index_start + (index_count * 16);
+    let store_start = index_count.checked_mul(16).and_then(|size| size.checked_add(index_start));
+    let store_end = store_start.and_then(|start| start.checked_add(store_size));
 
-    if data.len() < store_start + store_size {
+    let (Some(store_start), Some(store_end)) = (store_start, store_end) else {
+        return Err("RPM Immutable Header size overflows".into());
+    };
+    if data.len() < store_end {
         return Err("RPM file truncated in Header structure".into());
     }
 
+    // rpm's SHA1HEADER digest covers the header exactly as stored: magic,
+    // counts, index and data store.
+    meta.insert("HeaderSha1".into(), digest::to_hex(&digest::sha1(&data[offset..store_end])));
+
+    let mut i18n_table = Vec::new();
+    let mut localized = [None; LOCALIZED_TAGS.len()];
+
     for i in 0..index_count {
         let entry_offset = index_start + (i * 16);
         let tag = u32::from_be_bytes([data[entry_offset], data[entry_offset + 1], data[entry_offset + 2], data[entry_offset + 3]]);
-        let _dtype = u32::from_be_bytes([data[entry_offset + 4], data[entry_offset + 5], data[entry_offset + 6], data[entry_offset + 7]]);
+        let dtype = u32::from_be_bytes([data[entry_offset + 4], data[entry_offset + 5], data[entry_offset + 6], data[entry_offset + 7]]);
         let offset = u32::from_be_bytes([data[entry_offset + 8], data[entry_offset + 9], data[entry_offset + 10], data[entry_offset + 11]]) as usize;
-        // let count = u32::from_be_bytes([data[entry_offset + 12], data[entry_offset + 13], data[entry_offset + 14], data[entry_offset + 15]]) as usize;
+        let count = u32::from_be_bytes([data[entry_offset + 12], data[entry_offset + 13], data[entry_offset + 14], data[entry_offset + 15]]) as usize;
+
+        let abs_offset = store_start.saturating_add(offset);
 
-        let abs_offset = store_start + offset;
+        if let Some(slot) = LOCALIZED_TAGS.iter().position(|(localized_tag, _)| *localized_tag == tag) {
+            localized[slot] = Some((abs_offset, count));
+        }
+
+        for (script_tag, program_tag, name) in SCRIPTLETS {
+            if tag == script_tag || tag == program_tag {
+                meta.insert(format!("Has{}Script", name), "true".into());
+            }
+            if tag == program_tag {
+                let interpreter = if dtype == RPM_STRING_ARRAY_TYPE {
+                    read_string_array(data, abs_offset, count).join(" ")
+                } else {
+                    read_string(data, abs_offset).unwrap_or_default()
+                };
+                if !interpreter.is_empty() {
+                    meta.insert(format!("{}Interpreter", name), interpreter);
+                }
+            }
+        }
 
         match tag {
             1000 => { // NAME
@@ -105,11 +272,15 @@ fn parse_header_structure(data: &[u8], offset: usize, meta: &mut HashMap<String,
                     meta.insert("Release".into(), s);
                 }
             }
-            1004 => { // SUMMARY
-                if let Some(s) = read_string(data, abs_offset) {
-                    meta.insert("Description".into(), s);
+            1003 => { // EPOCH (INT32)
+                if let Some(epoch) = data.get(abs_offset..abs_offset.saturating_add(4)) {
+                    let epoch = u32::from_be_bytes([epoch[0], epoch[1], epoch[2], epoch[3]]);
+                    meta.insert("Epoch".into(), epoch.to_string());
                 }
             }
+            RPM_I18N_TABLE_TAG => {
+                i18n_table = read_string_array(data, abs_offset, count);
+            }
             1011 => { // VENDOR
                 if let Some(s) = read_string(data, abs_offset) {
                     meta.insert("Vendor".into(), s);
@@ -120,11 +291,6 @@ fn parse_header_structure(data: &[u8], offset: usize, meta: &mut HashMap<String,
                     meta.insert("License".into(), s);
                 }
             }
-            1016 => { // GROUP
-                if let Some(s) = read_string(data, abs_offset) {
-                    meta.insert("GroupName".into(), s);
-                }
-            }
             1020 => { // URL
                 if let Some(s) = read_string(data, abs_offset) {
                     meta.insert("Url".into(), s);
@@ -144,9 +310,108 @@ fn parse_header_structure(data: &[u8], offset: usize, meta: &mut HashMap<String,
         }
     }
 
+    for (_, _, name) in SCRIPTLETS {
+        meta.entry(format!("Has{}Script", name)).or_insert_with(|| "false".into());
+    }
+
+    let index = i18n_index(&i18n_table, locale::requested_locale().as_deref());
+    for ((_, name), entry) in LOCALIZED_TAGS.iter().zip(localized) {
+        let Some((abs_offset, count)) = entry else {
+            continue;
+        };
+        let translations = read_string_array(data, abs_offset, count.max(1));
+        if let Some(s) = translations.get(index).or(translations.first()) {
+            meta.insert(name.to_string(), s.clone());
+        }
+    }
+    if !meta.contains_key("Description") {
+        if let Some(summary) = meta.get("Summary").cloned() {
+            meta.insert("Description".into(), summary);
+        }
+    }
+    if !i18n_table.is_empty() {
+        meta.insert("Locales".into(), i18n_table.join(", "));
+    }
+
     Ok(())
 }
 
+/// Absolute data offset and count of `tag` in the header at `offset`.
+fn find_tag(data: &[u8], offset: usize, tag: u32) -> Option<(usize, usize)> {
+    let intro = data.get(offset..offset.checked_add(16)?)?;
+    if &intro[0..4] != RPM_HEADER_MAGIC {
+        return None;
+    }
+    let index_count = u32::from_be_bytes([intro[8], intro[9], intro[10], intro[11]]) as usize;
+    let index_start = offset + 16;
+    let store_start = index_count.checked_mul(16)?.checked_add(index_start)?;
+    data.get(index_start..store_start)?.chunks_exact(16).find_map(|entry| {
+        let field = |at: usize| -> usize { u32::from_be_bytes([entry[at], entry[at + 1], entry[at + 2], entry[at + 3]]) as usize };
+        (field(0) == tag as usize).then(|| (store_start.saturating_add(field(8)), field(12)))
+    })
+}
+
+fn installed_size(data: &[u8], offset: usize) -> Option<u64> {
+    if let Some((at, _)) = find_tag(data, offset, RPM_LONGSIZE_TAG) {
+        let bytes = data.get(at..at.checked_add(8)?)?;
+        return Some(u64::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]]));
+    }
+    let (at, _) = find_tag(data, offset, RPM_SIZE_TAG)?;
+    let bytes = data.get(at..at.checked_add(4)?)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64)
+}
+
+/// The first `limit` paths the package installs, from the compressed file
+/// list or, in old packages, `OLDFILENAMES`.
+fn header_file_list(data: &[u8], offset: usize, limit: usize) -> Vec<String> {
+    let tag = |tag| find_tag(data, offset, tag);
+    let (Some(basenames), Some(dirnames), Some(dir_indexes)) =
+        (tag(RPM_BASENAMES_TAG), tag(RPM_DIRNAMES_TAG), tag(RPM_DIRINDEXES_TAG))
+    else {
+        return tag(RPM_OLD_FILENAMES_TAG)
+            .map(|(at, count)| read_string_array(data, at, count.min(limit)))
+            .unwrap_or_default();
+    };
+    let dirnames = read_string_array(data, dirnames.0, dirnames.1);
+    read_string_array(data, basenames.0, basenames.1.min(limit))
+        .into_iter()
+        .enumerate()
+        .map_while(|(i, basename)| {
+            let at = dir_indexes.0.checked_add(i * 4)?;
+            let index = data.get(at..at + 4)?;
+            let dirname = dirnames.get(u32::from_be_bytes([index[0], index[1], index[2], index[3]]) as usize)?;
+            Some(format!("{}{}", dirname, basename))
+        })
+        .collect()
+}
+
+/// Position of the best match for `requested` in the header's locale table:
+/// the full locale (`de_DE.UTF-8`), then without codeset (`de_DE`), then the
+/// bare language (`de`), then `C`, as rpm itself resolves I18NSTRINGs.
+fn i18n_index(table: &[String], requested: Option<&str>) -> usize {
+    let requested = requested.unwrap_or("C").replace('-', "_");
+    let without_codeset = requested.split_once('.').map_or(requested.as_str(), |(base, _)| base);
+    let language = without_codeset.split_once('_').map_or(without_codeset, |(language, _)| language);
+    [requested.as_str(), without_codeset, language, "C"]
+        .iter()
+        .find_map(|candidate| table.iter().position(|locale| locale == candidate))
+        .unwrap_or(0)
+}
+
+/// `count` consecutive NUL-terminated strings, stopping early at the end of
+/// the data.
+fn read_string_array(data: &[u8], mut offset: usize, count: usize) -> Vec<String> {
+    let mut strings = Vec::new();
+    for _ in 0..count {
+        let Some(s) = read_string(data, offset) else {
+            break;
+        };
+        offset = offset.saturating_add(s.len() + 1);
+        strings.push(s);
+    }
+    strings
+}
+
 fn read_string(data: &[u8], offset: usize) -> Option<String> {
     if offset >= data.len() {
         return None;
@@ -173,4 +438,156 @@ mod tests {
         let invalid_data = vec![0; 100];
         assert!(!is_rpm_file(&invalid_data));
     }
+
+    #[test]
+    fn test_header_sha1_covers_stored_header() {
+        let mut data = vec![0xAA; 8];
+        data.extend_from_slice(&[0x8E, 0xAD, 0xE8, 0x01, 0, 0, 0, 0]);
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(&4u32.to_be_bytes());
+        for field in [1000u32, 6, 0, 1] {
+            data.extend_from_slice(&field.to_be_bytes());
+        }
+        data.extend_from_slice(b"foo\0");
+        data.extend_from_slice(b"payload");
+
+        let mut meta = HashMap::new();
+        assert!(parse_header_structure(&data, 8, &mut meta).is_ok());
+        assert_eq!(meta.get("ProductName").map(String::as_str), Some("foo"));
+        assert_eq!(
+            meta.get("HeaderSha1").map(String::as_str),
+            Some("de23e8b7f794b753274ebfab91da6a4e04cfc4e3")
+        );
+    }
+
+    #[test]
+    fn test_headers_found_off_their_computed_offsets() {
+        // A 4-byte signature header store, padded to 8 bytes or not
+        let mut signature = vec![0x8E, 0xAD, 0xE8, 0x01, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 4];
+        for field in [269u32, 6, 0, 1] {
+            signature.extend_from_slice(&field.to_be_bytes());
+        }
+        signature.extend_from_slice(b"abc\0");
+        let mut header = vec![0x8E, 0xAD, 0xE8, 0x01, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 4];
+        for field in [1000u32, 6, 0, 1] {
+            header.extend_from_slice(&field.to_be_bytes());
+        }
+        header.extend_from_slice(b"foo\0");
+
+        let mut lead = [0; RPM_LEAD_SIZE];
+        lead[0..4].copy_from_slice(RPM_LEAD_MAGIC);
+        let padded = [&lead[..], &signature, &[0; 4], &header].concat();
+        let unpadded = [&lead[..], &signature, &header].concat();
+        let long_lead = [&lead[..], &[0; 12], &signature, &[0; 4], &header].concat();
+
+        for data in [padded, unpadded, long_lead] {
+            let meta = RPMAnalyzer.parse_metadata(&data).unwrap_or_default();
+            assert_eq!(meta.get("ProductName").map(String::as_str), Some("foo"));
+        }
+    }
+
+    #[test]
+    fn test_payload_after_displaced_immutable_header() {
+        let mut signature = vec![0x8E, 0xAD, 0xE8, 0x01, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 4];
+        for field in [269u32, 6, 0, 1] {
+            signature.extend_from_slice(&field.to_be_bytes());
+        }
+        signature.extend_from_slice(b"abc\0");
+        // NAME and SIZE (INT32): a 48-byte header
+        let mut header = vec![0x8E, 0xAD, 0xE8, 0x01, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 8];
+        for field in [1000u32, 6, 0, 1, 1009, 4, 4, 1] {
+            header.extend_from_slice(&field.to_be_bytes());
+        }
+        header.extend_from_slice(b"foo\0");
+        header.extend_from_slice(&4096u32.to_be_bytes());
+
+        let mut lead = [0; RPM_LEAD_SIZE];
+        lead[0..4].copy_from_slice(RPM_LEAD_MAGIC);
+        // 24 bytes of padding push the immutable header past its computed offset
+        let data = [&lead[..], &signature, &[0; 4], &[0; 24], &header, &[0x1F; 1000]].concat();
+
+        let payload_start = data.len() - 1000;
+        assert_eq!(RPMAnalyzer.expected_size(&data), Some(payload_start));
+        let meta = RPMAnalyzer.parse_metadata(&data).unwrap_or_default();
+        assert_eq!(meta.get("ProductName").map(String::as_str), Some("foo"));
+        assert_eq!(meta.get("CompressedSize").map(String::as_str), Some("1000"));
+        assert_eq!(meta.get("UncompressedSize").map(String::as_str), Some("4096"));
+        assert_eq!(RPMAnalyzer.metadata_len(&data), Some(payload_start + HEADER_SCAN_WINDOW));
+
+        // Cut inside the displaced header, the upload is still reported short
+        assert_eq!(RPMAnalyzer.expected_size(&data[..payload_start - 8]), Some(payload_start));
+    }
+
+    #[test]
+    fn test_localized_summary() {
+        let store: &[u8] = b"C\0de\0Hello\0Hallo\0";
+        let entries: [[u32; 4]; 2] = [[100, 8, 0, 2], [1004, 9, 5, 2]];
+
+        let mut data = vec![0x8E, 0xAD, 0xE8, 0x01, 0, 0, 0, 0];
+        data.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        data.extend_from_slice(&(store.len() as u32).to_be_bytes());
+        for field in entries.iter().flatten() {
+            data.extend_from_slice(&field.to_be_bytes());
+        }
+        data.extend_from_slice(store);
+
+        let summary = |requested: Option<&str>| {
+            let mut meta = HashMap::new();
+            let _locale = locale::request_locale(requested);
+            let _ = parse_header_structure(&data, 0, &mut meta);
+            meta.get("Summary").cloned()
+        };
+        assert_eq!(summary(None).as_deref(), Some("Hello"));
+        assert_eq!(summary(Some("de_AT.UTF-8")).as_deref(), Some("Hallo"));
+        assert_eq!(summary(Some("fr")).as_deref(), Some("Hello"));
+
+        let mut meta = HashMap::new();
+        assert!(parse_header_structure(&data, 0, &mut meta).is_ok());
+        assert_eq!(meta.get("Description").map(String::as_str), Some("Hello"));
+        assert_eq!(meta.get("Locales").map(String::as_str), Some("C, de"));
+    }
+
+    #[test]
+    fn test_header_file_list() {
+        let mut store = b"/usr/bin/\0/etc/\0tool\0tool.conf\0helper\0\0\0".to_vec();
+        for dir_index in [0u32, 1, 0] {
+            store.extend_from_slice(&dir_index.to_be_bytes());
+        }
+        let entries: [[u32; 4]; 3] = [[1118, 8, 0, 2], [1117, 8, 16, 3], [1116, 4, 40, 3]];
+
+        let mut data = vec![0x8E, 0xAD, 0xE8, 0x01, 0, 0, 0, 0];
+        data.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        data.extend_from_slice(&(store.len() as u32).to_be_bytes());
+        for field in entries.iter().flatten() {
+            data.extend_from_slice(&field.to_be_bytes());
+        }
+        data.extend_from_slice(&store);
+
+        assert_eq!(header_file_list(&data, 0, 10), ["/usr/bin/tool", "/etc/tool.conf", "/usr/bin/helper"]);
+        assert_eq!(header_file_list(&data, 0, 1), ["/usr/bin/tool"]);
+    }
+
+    #[test]
+    fn test_scriptlets_and_interpreters() {
+        let store: &[u8] = b"echo hi\0/bin/sh\0/usr/bin/lua\0-e\0";
+        let entries: [[u32; 4]; 3] = [[1024, 6, 0, 1], [1086, 8, 8, 1], [1087, 8, 16, 2]];
+
+        let mut data = vec![0x8E, 0xAD, 0xE8, 0x01, 0, 0, 0, 0];
+        data.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        data.extend_from_slice(&(store.len() as u32).to_be_bytes());
+        for field in entries.iter().flatten() {
+            data.extend_from_slice(&field.to_be_bytes());
+        }
+        data.extend_from_slice(store);
+
+        let mut meta = HashMap::new();
+        assert!(parse_header_structure(&data, 0, &mut meta).is_ok());
+        let get = |key: &str| meta.get(key).map(String::as_str);
+        assert_eq!(get("HasPreInstallScript"), Some("false"));
+        assert_eq!(get("HasPostInstallScript"), Some("true"));
+        assert_eq!(get("PostInstallInterpreter"), Some("/bin/sh"));
+        assert_eq!(get("HasPreUninstallScript"), Some("true"));
+        assert_eq!(get("PreUninstallInterpreter"), Some("/usr/bin/lua -e"));
+        assert_eq!(get("HasPostUninstallScript"), Some("false"));
+    }
 }
@@ -0,0 +1,554 @@
+//! Registry of every metadata field the analyzers emit, served as a JSON
+//! schema so consumers (and the TypeScript types) can track the open-ended
+//! output map. A field added to an analyzer belongs here too.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// How a field's string value should be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldKind {
+    #[serde(rename = "string")]
+    Text,
+    /// `"true"` or `"false"`
+    Boolean,
+    /// Decimal integer
+    Integer,
+    /// `0x`-prefixed hexadecimal
+    Hex,
+    /// Comma-separated values
+    List,
+    /// JSON-encoded object
+    Json,
+}
+
+use FieldKind::{Boolean, Hex, Integer, Json, List, Text};
+
+type FieldSpec = (&'static str, FieldKind);
+
+/// Fields of one format. `open` formats also copy source keys through
+/// verbatim (control fields, version-info strings, Property table rows).
+struct FormatFields {
+    format: &'static str,
+    open: bool,
+    fields: &'static [FieldSpec],
+}
+
+/// Matches every format.
+const ANY_FORMAT: &str = "*";
+
+/// `{...}` in a name stands for an index or source key.
+const REGISTRY: [FormatFields; 18] = [
+    FormatFields {
+        format: ANY_FORMAT,
+        open: false,
+        fields: &[
+            ("Format", Text),
+            ("MimeType", Text),
+            ("Extension", Text),
+            ("DetectionConfidence", Text),
+            ("Category", Text),
+            ("Size", Integer),
+            ("ProductName", Text),
+            ("ProductVersion", Text),
+            ("VersionNormalized", Text),
+            ("Publisher", Text),
+            ("Language", Text),
+            ("Partial", Boolean),
+            ("Truncated", Boolean),
+            ("ExpectedSize", Integer),
+            ("ActualSize", Integer),
+            ("CompressedSize", Integer),
+            ("UncompressedSize", Integer),
+            ("CompressionRatio", Text),
+            ("SuspiciousCompressionRatio", Boolean),
+            ("ExtractedUrls", List),
+            ("ExtractedEmails", List),
+            ("ExtractedDomains", List),
+            ("HeaderHexDump", Text),
+            ("Sha256", Text),
+            ("RawManifest", Text),
+            ("FileList", List),
+            ("FileListTruncated", Boolean),
+            ("HasPathTraversal", Boolean),
+            ("Embedded", Json),
+            ("_sources", Json),
+            ("Offset", Integer),
+        ],
+    },
+    FormatFields {
+        format: "PE",
+        open: true,
+        fields: &[
+            ("Architecture", Text),
+            ("Machine", Hex),
+            ("NumberOfSections", Integer),
+            ("SizeOfOptionalHeader", Integer),
+            ("Characteristics", Hex),
+            ("PointerToSymbolTable", Integer),
+            ("NumberOfSymbols", Integer),
+            ("Timestamp", Integer),
+            ("EntryPoint", Hex),
+            ("ImageBase", Hex),
+            ("SizeOfImage", Integer),
+            ("Subsystem", Text),
+            ("IsGuiApp", Boolean),
+            ("IsDll", Boolean),
+            ("IsSystem", Boolean),
+            ("ASLR", Boolean),
+            ("DEP", Boolean),
+            ("HighEntropyVA", Boolean),
+            ("ControlFlowGuard", Boolean),
+            ("ForceIntegrity", Boolean),
+            ("DllCharacteristics", Hex),
+            ("HasVersionInfo", Boolean),
+            ("HasResources", Boolean),
+            ("FileVersionNumber", Text),
+            ("ProductVersionNumber", Text),
+            ("FileFlags", Hex),
+            ("FileOS", Hex),
+            ("FileType", Hex),
+            ("CompanyName", Text),
+            ("CompanyNameSource", Text),
+            ("FileDescription", Text),
+            ("FileVersion", Text),
+            ("InternalName", Text),
+            ("OriginalFilename", Text),
+            ("LegalCopyright", Text),
+            ("LegalTrademarks", Text),
+            ("Comments", Text),
+            ("PrivateBuild", Text),
+            ("SpecialBuild", Text),
+            ("TranslationCount", Integer),
+            ("Translation_{n}", Text),
+            ("StringsInTranslation_{n}", Integer),
+            ("Debug_{n}_{key}", Text),
+            ("StringsCount", Integer),
+            ("TotalCallbackCalls", Integer),
+            ("NoStringsFound", Boolean),
+            ("VersionInfoError", Text),
+            ("ResourcesError", Text),
+            ("SignedBy", Text),
+            ("CertificateTrust", Text),
+            ("SigningTime", Text),
+            ("LikelyReproducibleBuild", Boolean),
+            ("InstallerType", Text),
+            ("IsInstaller", Boolean),
+            ("EmbeddedMSI", Boolean),
+            ("MSIOffset", Integer),
+            ("EmbeddedMSIWarning", Text),
+            ("OverlayOffset", Integer),
+            ("OverlaySize", Integer),
+            ("ContainedArchive", Text),
+            ("ContainedArchiveOffset", Integer),
+            ("ContainedArchiveEntries", Integer),
+            ("AuthentiHash", Text),
+            ("ImpHash", Text),
+            ("ImportedDlls", List),
+            ("DelayLoadedDlls", List),
+            ("CheckSumStored", Hex),
+            ("CheckSumComputed", Hex),
+            ("CheckSumValid", Boolean),
+            ("HasTlsCallbacks", Boolean),
+            ("TlsCallbackCount", Integer),
+            ("PdbPath", Text),
+            ("PdbGuid", Text),
+            ("PdbAge", Integer),
+            ("RequestedExecutionLevel", Text),
+            ("DpiAware", Text),
+            ("SupportedOS", List),
+        ],
+    },
+    FormatFields {
+        format: "MSI",
+        open: true,
+        fields: &[
+            ("ProductVersionValid", Boolean),
+            ("Architecture", Text),
+            ("Manufacturer", Text),
+            ("ProductCode", Text),
+            ("UpgradeCode", Text),
+            ("PackageCode", Text),
+            ("PackageId", Text),
+            ("Title", Text),
+            ("Comments", Text),
+            ("Keywords", Text),
+            ("FileCount", Integer),
+            ("TotalFileSize", Integer),
+            ("ComponentCount", Integer),
+            ("FeatureCount", Integer),
+            ("LaunchConditions", Text),
+            ("Properties", Json),
+            ("InstallScope", Text),
+            ("RequiresElevation", Boolean),
+            ("HelpLink", Text),
+            ("HelpTelephone", Text),
+            ("AboutUrl", Text),
+            ("UpdateUrl", Text),
+            ("Contact", Text),
+            ("EstimatedSize", Integer),
+            ("NoRemove", Boolean),
+            ("InstallerFramework", Text),
+            ("RootClsid", Text),
+            ("CompoundFileType", Text),
+            ("CompoundFileError", Text),
+        ],
+    },
+    FormatFields {
+        format: "CompoundFile",
+        open: false,
+        fields: &[("RootClsid", Text), ("CompoundFileType", Text)],
+    },
+    FormatFields {
+        format: "DMG",
+        open: false,
+        fields: &[
+            ("Architecture", Text),
+            ("ImageType", Text),
+            ("Compression", Text),
+            ("HasKolySignature", Boolean),
+            ("KolyOffset", Integer),
+            ("DMGVersion", Integer),
+            ("DisplayName", Text),
+            ("ProgramName", Text),
+            ("ApplicationBundle", Text),
+            ("BundleIdentifier", Text),
+            ("ExecutableName", Text),
+            ("FileVersion", Text),
+            ("FileVersionNumber", Text),
+            ("ProductVersionNumber", Text),
+            ("FileDescription", Text),
+            ("LegalCopyright", Text),
+            ("ApplicationCategory", Text),
+            ("PackageType", Text),
+            ("PrincipalClass", Text),
+            ("IconFile", Text),
+            ("MinimumSystemVersion", Text),
+            ("CompanyName", Text),
+            ("Manufacturer", Text),
+            ("Vendor", Text),
+        ],
+    },
+    FormatFields {
+        format: "DEB",
+        open: true,
+        fields: &[
+            ("Package", Text),
+            ("Version", Text),
+            ("Architecture", Text),
+            ("Maintainer", Text),
+            ("Description", Text),
+            ("Depends", List),
+            ("Section", Text),
+            ("Priority", Text),
+            ("Homepage", Text),
+            ("PackageSection", Text),
+            ("PackagePriority", Text),
+            ("MultiArch", Text),
+            ("IsEssential", Boolean),
+            ("IsDebugPackage", Boolean),
+            ("SourcePackage", Text),
+            ("SourceVersion", Text),
+            ("BuiltUsing", Json),
+            ("IsSigned", Boolean),
+            ("SignatureMembers", List),
+            ("SignatureSize", Integer),
+        ],
+    },
+    FormatFields {
+        format: "RPM",
+        open: false,
+        fields: &[
+            ("Release", Text),
+            ("Epoch", Integer),
+            ("Summary", Text),
+            ("Description", Text),
+            ("Locales", List),
+            ("Vendor", Text),
+            ("License", Text),
+            ("GroupName", Text),
+            ("Url", Text),
+            ("Architecture", Text),
+            ("SourceRpm", Text),
+            ("HeaderSha1", Text),
+            ("HasPreInstallScript", Boolean),
+            ("HasPostInstallScript", Boolean),
+            ("HasPreUninstallScript", Boolean),
+            ("HasPostUninstallScript", Boolean),
+            ("PreInstallInterpreter", Text),
+            ("PostInstallInterpreter", Text),
+            ("PreUninstallInterpreter", Text),
+            ("PostUninstallInterpreter", Text),
+        ],
+    },
+    FormatFields {
+        format: "Snap",
+        open: false,
+        fields: &[
+            ("Name", Text),
+            ("Version", Text),
+            ("Summary", Text),
+            ("Grade", Text),
+            ("Confinement", Text),
+            ("Base", Text),
+            ("SnapType", Text),
+            ("Architectures", List),
+            ("HasSnapYaml", Boolean),
+            ("Compression", Text),
+            ("BlockSize", Integer),
+            ("InodeCount", Integer),
+            ("Timestamp", Integer),
+        ],
+    },
+    FormatFields {
+        format: "Flatpak",
+        open: false,
+        fields: &[
+            ("Ref", Text),
+            ("BundleKind", Text),
+            ("AppId", Text),
+            ("Architecture", Text),
+            ("Branch", Text),
+            ("Runtime", Text),
+            ("Sdk", Text),
+            ("Command", Text),
+            ("Origin", Text),
+            ("RuntimeRepo", Text),
+        ],
+    },
+    FormatFields {
+        format: "CPIO",
+        open: false,
+        fields: &[
+            ("CpioFormat", Text),
+            ("EntryCount", Integer),
+            ("TotalFileSize", Integer),
+            ("FirstEntries", List),
+            ("MissingTrailer", Boolean),
+        ],
+    },
+    FormatFields {
+        format: "ISO9660",
+        open: false,
+        fields: &[
+            ("SystemIdentifier", Text),
+            ("VolumeIdentifier", Text),
+            ("VolumeSetIdentifier", Text),
+            ("PublisherIdentifier", Text),
+            ("DataPreparerIdentifier", Text),
+            ("ApplicationIdentifier", Text),
+            ("VolumeSpaceSize", Integer),
+            ("LogicalBlockSize", Integer),
+            ("VolumeSize", Integer),
+            ("CreationDate", Text),
+            ("CompanyName", Text),
+        ],
+    },
+    FormatFields {
+        format: "LNK",
+        open: false,
+        fields: &[
+            ("LinkFlags", Hex),
+            ("LinkFlagNames", Text),
+            ("TargetAttributes", Text),
+            ("TargetFileSize", Integer),
+            ("LinkTarget", Text),
+            ("Description", Text),
+            ("RelativePath", Text),
+            ("WorkingDirectory", Text),
+            ("CommandLineArgs", Text),
+            ("IconLocation", Text),
+        ],
+    },
+    FormatFields {
+        format: "MSU",
+        open: false,
+        fields: &[
+            ("KbNumber", Text),
+            ("TargetOS", Text),
+            ("Architecture", Text),
+            ("PackageType", Text),
+            ("InstallationType", Text),
+            ("AppliesTo", Text),
+            ("SupportLink", Text),
+            ("CabinetFiles", List),
+        ],
+    },
+    FormatFields {
+        format: "AppInstaller",
+        open: false,
+        fields: &[
+            ("AppInstallerUri", Text),
+            ("AppInstallerVersion", Text),
+            ("PackageType", Text),
+            ("PackageUri", Text),
+            ("Architecture", Text),
+            ("Dependencies", List),
+            ("HoursBetweenUpdateChecks", Integer),
+            ("AutomaticBackgroundTask", Boolean),
+        ],
+    },
+    FormatFields {
+        format: "ELF",
+        open: false,
+        fields: &[
+            ("Architecture", Text),
+            ("Class", Text),
+            ("Endianness", Text),
+            ("FileType", Text),
+            ("EntryPoint", Hex),
+            ("Interpreter", Text),
+            ("SOName", Text),
+            ("NeededLibraries", List),
+            ("LibcFlavor", Text),
+            ("SectionCount", Integer),
+            ("SegmentCount", Integer),
+            ("HasWxSegment", Boolean),
+            ("Entropy", Text),
+            ("Packer", Text),
+            ("HasCET", Boolean),
+            ("CETFeatures", List),
+            ("HasRelro", Boolean),
+            ("HasStackProtector", Boolean),
+            ("IsPIE", Boolean),
+        ],
+    },
+    FormatFields {
+        format: "WebAssembly",
+        open: false,
+        fields: &[
+            ("Version", Integer),
+            ("NumberOfSections", Integer),
+            ("ImportCount", Integer),
+            ("ExportCount", Integer),
+            ("HasNameSection", Boolean),
+            ("CustomSections", List),
+        ],
+    },
+    FormatFields {
+        format: "JavaClass",
+        open: false,
+        fields: &[
+            ("MajorVersion", Integer),
+            ("MinorVersion", Integer),
+            ("JavaVersion", Text),
+            ("PreviewFeatures", Boolean),
+        ],
+    },
+    FormatFields {
+        format: "Mach-O",
+        open: false,
+        fields: &[
+            ("Architecture", Text),
+            ("CpuType", Hex),
+            ("CpuSubType", Text),
+            ("FileType", Text),
+            ("Flags", Hex),
+            ("FlagNames", Text),
+            ("NumberOfLoadCommands", Integer),
+            ("HasTextSegment", Boolean),
+            ("InstallName", Text),
+            ("CurrentVersion", Text),
+            ("CompatibilityVersion", Text),
+            ("LinkedLibraries", List),
+            ("IsSigned", Boolean),
+            ("SigningIdentifier", Text),
+            ("TeamIdentifier", Text),
+            ("MinimumSystemVersion", Text),
+            ("Platform", Text),
+            ("IsUniversal", Boolean),
+            ("SliceCount", Integer),
+            ("Architectures", List),
+            ("Slice_{n}_Architecture", Text),
+            ("Slice_{n}_Offset", Integer),
+            ("Slice_{n}_Size", Integer),
+        ],
+    },
+];
+
+/// Registry groups that apply to this build: the shared fields and every
+/// compiled-in format.
+fn active_groups() -> impl Iterator<Item = &'static FormatFields> {
+    let formats: Vec<&str> = crate::supported_formats().iter().map(|format| format.name).collect();
+    REGISTRY
+        .iter()
+        .filter(move |group| group.format == ANY_FORMAT || formats.contains(&group.format))
+}
+
+/// Whether `name` matches a registry `pattern`, where each `{...}` stands for
+/// one or more characters.
+#[cfg(test)]
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('{') else {
+        return pattern == name;
+    };
+    let Some(name_rest) = name.strip_prefix(prefix) else {
+        return false;
+    };
+    let suffix = rest.split_once('}').map_or("", |(_, suffix)| suffix);
+    (1..=name_rest.len())
+        .filter(|&len| name_rest.is_char_boundary(len))
+        .any(|len| matches_pattern(suffix, &name_rest[len..]))
+}
+
+/// Whether the registry accounts for `name` in a `format` analysis, either
+/// as a declared field or as a verbatim key of an open format.
+#[cfg(test)]
+pub fn is_registered(format: &str, name: &str) -> bool {
+    REGISTRY.iter().any(|group| {
+        (group.format == ANY_FORMAT || group.format == format)
+            && (group.open || group.fields.iter().any(|(pattern, _)| matches_pattern(pattern, name)))
+    })
+}
+
+/// Every field this build can emit as `{name, type, formats}` (sorted by
+/// name; `formats` is `["*"]` for fields every analysis may carry), plus the
+/// formats whose source keys are passed through verbatim.
+pub fn output_schema() -> serde_json::Value {
+    let mut fields: BTreeMap<FieldSpec, Vec<&str>> = BTreeMap::new();
+    for group in active_groups() {
+        for &(name, kind) in group.fields {
+            fields.entry((name, kind)).or_default().push(group.format);
+        }
+    }
+    let fields: Vec<serde_json::Value> = fields
+        .into_iter()
+        .map(|((name, kind), formats)| serde_json::json!({ "name": name, "type": kind, "formats": formats }))
+        .collect();
+    let open_formats: Vec<&str> = active_groups().filter(|group| group.open).map(|group| group.format).collect();
+
+    serde_json::json!({ "fields": fields, "openFormats": open_formats })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("Slice_{n}_Size", "Slice_12_Size"));
+        assert!(matches_pattern("Debug_{n}_{key}", "Debug_0_CompanyName"));
+        assert!(matches_pattern("StringsInTranslation_{n}", "StringsInTranslation_0"));
+        assert!(!matches_pattern("Slice_{n}_Size", "Slice__Size"));
+        assert!(!matches_pattern("Format", "Formats"));
+    }
+
+    #[test]
+    fn test_output_schema() {
+        let schema = output_schema();
+        let fields = schema["fields"].as_array().cloned().unwrap_or_default();
+        let field = |name: &str| fields.iter().find(|field| field["name"] == name).cloned();
+
+        #[cfg(feature = "rpm")]
+        assert_eq!(field("HeaderSha1").map(|f| f["formats"].clone()), Some(serde_json::json!(["RPM"])));
+        #[cfg(feature = "pe")]
+        assert_eq!(field("ASLR").map(|f| f["type"].clone()), Some(serde_json::json!("boolean")));
+        assert_eq!(field("Category").map(|f| f["formats"].clone()), Some(serde_json::json!(["*"])));
+        #[cfg(all(feature = "pe", feature = "msi", feature = "deb"))]
+        assert_eq!(schema["openFormats"], serde_json::json!(["PE", "MSI", "DEB"]));
+        assert!(is_registered("CPIO", "EntryCount"));
+        assert!(!is_registered("CPIO", "ImpHash"));
+    }
+}
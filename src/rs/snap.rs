@@ -0,0 +1,565 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use flate2::read::ZlibDecoder;
+use crate::{budget, FileAnalyzer, FormatInfo, MetadataResult};
+
+// SquashFS 4.0 superblock (little-endian)
+const SQUASHFS_MAGIC: &[u8] = b"hsqs";
+const SUPERBLOCK_SIZE: usize = 96;
+const SQUASHFS_MAJOR: u16 = 4;
+
+const COMPRESSION_GZIP: u16 = 1;
+const COMPRESSION_XZ: u16 = 4;
+const COMPRESSION_NAMES: [&str; 6] = ["gzip", "lzma", "lzo", "xz", "lz4", "zstd"];
+
+/// Metadata blocks hold at most 8 KiB; the header's top bit marks a block
+/// stored uncompressed.
+const METADATA_BLOCK_SIZE: usize = 8192;
+const METADATA_UNCOMPRESSED: u16 = 0x8000;
+/// Data and fragment block sizes with this bit set are stored uncompressed.
+const DATA_UNCOMPRESSED: u32 = 1 << 24;
+const NO_FRAGMENT: u32 = 0xFFFF_FFFF;
+const FRAGMENT_ENTRY_SIZE: usize = 16;
+const FRAGMENT_ENTRIES_PER_BLOCK: usize = METADATA_BLOCK_SIZE / FRAGMENT_ENTRY_SIZE;
+
+const INODE_HEADER_SIZE: usize = 16;
+const BASIC_DIRECTORY: u16 = 1;
+const BASIC_FILE: u16 = 2;
+const EXTENDED_DIRECTORY: u16 = 8;
+const EXTENDED_FILE: u16 = 9;
+/// Header plus the largest fixed part (extended file) of an inode.
+const MAX_INODE_SIZE: usize = INODE_HEADER_SIZE + 40;
+/// Deepest directory `file_list` descends into.
+const MAX_LIST_DEPTH: usize = 64;
+
+/// Largest `snap.yaml` worth decompressing.
+const MAX_SNAP_YAML_SIZE: u64 = 256 * 1024;
+
+/// Top-level `snap.yaml` keys and the metadata keys they map to.
+const SNAP_YAML_KEYS: [(&str, &str); 7] = [
+    ("name", "Name"),
+    ("version", "Version"),
+    ("summary", "Summary"),
+    ("grade", "Grade"),
+    ("confinement", "Confinement"),
+    ("base", "Base"),
+    ("type", "SnapType"),
+];
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "Snap",
+    mime_type: "application/vnd.snap",
+    extensions: &["snap"],
+};
+
+/// Snap packages: SquashFS 4.0 images carrying a `meta/snap.yaml`.
+pub struct SnapAnalyzer;
+
+impl FileAnalyzer for SnapAnalyzer {
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        is_snap_file(data)
+    }
+
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
+        parse_snap_metadata(data)
+    }
+
+    fn expected_size(&self, data: &[u8]) -> Option<usize> {
+        SquashFs::parse(data).map(|fs| fs.bytes_used as usize)
+    }
+
+    fn file_list(&self, data: &[u8], limit: usize) -> Vec<String> {
+        let mut paths = Vec::new();
+        if let Some(fs) = SquashFs::parse(data) {
+            if let Some(root) = fs.inode(fs.root_inode) {
+                fs.walk(&root, "", 0, limit, &mut paths);
+            }
+        }
+        paths
+    }
+}
+
+pub fn is_snap_file(data: &[u8]) -> bool {
+    data.starts_with(SQUASHFS_MAGIC) && read_u16(data, 28) == Some(SQUASHFS_MAJOR)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset.checked_add(8)?)?;
+    let mut word = [0u8; 8];
+    word.copy_from_slice(bytes);
+    Some(u64::from_le_bytes(word))
+}
+
+/// A `Write` that refuses to grow past `limit`, so a crafted block cannot
+/// decompress into more than a block's worth of memory.
+struct BoundedWriter {
+    buf: Vec<u8>,
+    limit: usize,
+}
+
+impl Write for BoundedWriter {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        if self.buf.len() + data.len() > self.limit {
+            return Err(std::io::ErrorKind::OutOfMemory.into());
+        }
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+enum Inode {
+    Directory { block: u32, offset: u16, size: u32 },
+    File { blocks_start: u64, fragment: u32, fragment_offset: u32, size: u64, block_sizes: Vec<u32> },
+}
+
+struct DirEntry {
+    name: String,
+    /// Inode reference (block offset << 16 | offset).
+    reference: u64,
+    /// Basic inode type, whatever the inode's own (extended) type.
+    kind: u16,
+}
+
+struct SquashFs<'a> {
+    data: &'a [u8],
+    compression: u16,
+    block_size: u32,
+    inode_count: u32,
+    mkfs_time: u32,
+    root_inode: u64,
+    bytes_used: u64,
+    inode_table: u64,
+    directory_table: u64,
+    fragment_table: u64,
+}
+
+impl<'a> SquashFs<'a> {
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        if data.len() < SUPERBLOCK_SIZE || !is_snap_file(data) {
+            return None;
+        }
+        Some(SquashFs {
+            data,
+            inode_count: read_u32(data, 4)?,
+            mkfs_time: read_u32(data, 8)?,
+            block_size: read_u32(data, 12)?,
+            compression: read_u16(data, 20)?,
+            root_inode: read_u64(data, 32)?,
+            bytes_used: read_u64(data, 40)?,
+            inode_table: read_u64(data, 64)?,
+            directory_table: read_u64(data, 72)?,
+            fragment_table: read_u64(data, 80)?,
+        })
+    }
+
+    fn compression_name(&self) -> Option<&'static str> {
+        COMPRESSION_NAMES.get((self.compression as usize).checked_sub(1)?).copied()
+    }
+
+    /// Decompresses one block into at most `limit` bytes. Only gzip and xz,
+    /// the compressors snaps are built with by default, are supported.
+    fn decompress(&self, block: &[u8], limit: usize) -> Option<Vec<u8>> {
+        if !budget::charge(block.len() + limit) {
+            return None;
+        }
+        let mut out = BoundedWriter { buf: Vec::new(), limit };
+        match self.compression {
+            COMPRESSION_GZIP => {
+                let mut inflated = Vec::new();
+                ZlibDecoder::new(block).take(limit as u64 + 1).read_to_end(&mut inflated).ok()?;
+                out.write_all(&inflated).ok()?;
+            }
+            COMPRESSION_XZ => lzma_rs::xz_decompress(&mut &block[..], &mut out).ok()?,
+            _ => return None,
+        }
+        Some(out.buf)
+    }
+
+    /// `len` bytes at `offset` into the metadata blocks starting at `pos`,
+    /// decoding only as many blocks as that takes. Shorter at the end of the
+    /// image.
+    fn metadata(&self, mut pos: u64, offset: usize, len: usize) -> Option<Vec<u8>> {
+        let mut out = Vec::new();
+        while out.len() < offset + len {
+            let start = usize::try_from(pos).ok()?;
+            let Some(header) = read_u16(self.data, start) else {
+                break;
+            };
+            let size = (header & !METADATA_UNCOMPRESSED) as usize;
+            let block = self.data.get(start + 2..start + 2 + size)?;
+            if header & METADATA_UNCOMPRESSED != 0 {
+                out.extend_from_slice(block);
+            } else {
+                out.extend(self.decompress(block, METADATA_BLOCK_SIZE)?);
+            }
+            pos += 2 + size as u64;
+        }
+        let end = out.len().min(offset + len);
+        out.get(offset..end).map(<[u8]>::to_vec)
+    }
+
+    /// The inode an inode reference (block offset << 16 | offset) points to.
+    fn inode(&self, reference: u64) -> Option<Inode> {
+        let block = self.inode_table.checked_add(reference >> 16)?;
+        let offset = (reference & 0xFFFF) as usize;
+        let raw = self.metadata(block, offset, MAX_INODE_SIZE)?;
+        let body = INODE_HEADER_SIZE;
+        match read_u16(&raw, 0)? {
+            BASIC_DIRECTORY => Some(Inode::Directory {
+                block: read_u32(&raw, body)?,
+                size: read_u16(&raw, body + 8)? as u32,
+                offset: read_u16(&raw, body + 10)?,
+            }),
+            EXTENDED_DIRECTORY => Some(Inode::Directory {
+                size: read_u32(&raw, body + 4)?,
+                block: read_u32(&raw, body + 8)?,
+                offset: read_u16(&raw, body + 18)?,
+            }),
+            BASIC_FILE => {
+                let (blocks_start, fragment) = (read_u32(&raw, body)? as u64, read_u32(&raw, body + 4)?);
+                let (fragment_offset, size) = (read_u32(&raw, body + 8)?, read_u32(&raw, body + 12)? as u64);
+                let block_sizes = self.block_sizes(block, offset + body + 16, size, fragment)?;
+                Some(Inode::File { blocks_start, fragment, fragment_offset, size, block_sizes })
+            }
+            EXTENDED_FILE => {
+                let (blocks_start, size) = (read_u64(&raw, body)?, read_u64(&raw, body + 8)?);
+                let (fragment, fragment_offset) = (read_u32(&raw, body + 28)?, read_u32(&raw, body + 32)?);
+                let block_sizes = self.block_sizes(block, offset + body + 40, size, fragment)?;
+                Some(Inode::File { blocks_start, fragment, fragment_offset, size, block_sizes })
+            }
+            _ => None,
+        }
+    }
+
+    /// The sizes of a file's full blocks; the tail lives in a fragment
+    /// unless `fragment` is `NO_FRAGMENT`.
+    fn block_sizes(&self, block: u64, offset: usize, size: u64, fragment: u32) -> Option<Vec<u32>> {
+        if size > MAX_SNAP_YAML_SIZE || self.block_size == 0 {
+            return Some(Vec::new());
+        }
+        let block_size = self.block_size as u64;
+        let count = if fragment == NO_FRAGMENT { size.div_ceil(block_size) } else { size / block_size } as usize;
+        let raw = self.metadata(block, offset, count * 4)?;
+        (0..count).map(|i| read_u32(&raw, i * 4)).collect()
+    }
+
+    /// The entries of `directory`, in listing (sorted) order.
+    fn entries(&self, directory: &Inode) -> Option<Vec<DirEntry>> {
+        let Inode::Directory { block, offset, size } = *directory else {
+            return None;
+        };
+        // The listing size counts the "." and ".." entries squashfs omits
+        let len = (size as usize).checked_sub(3)?;
+        let listing = self.metadata(self.directory_table.checked_add(block as u64)?, offset as usize, len)?;
+
+        let mut entries = Vec::new();
+        let mut pos = 0;
+        while pos + 12 <= listing.len() {
+            let count = read_u32(&listing, pos)? as usize + 1;
+            let start = read_u32(&listing, pos + 4)? as u64;
+            pos += 12;
+            for _ in 0..count {
+                let inode_offset = read_u16(&listing, pos)? as u64;
+                let kind = read_u16(&listing, pos + 4)?;
+                let name_len = read_u16(&listing, pos + 6)? as usize + 1;
+                let name = listing.get(pos + 8..pos + 8 + name_len)?;
+                let name = String::from_utf8_lossy(name).into_owned();
+                entries.push(DirEntry { name, reference: (start << 16) | inode_offset, kind });
+                pos += 8 + name_len;
+            }
+        }
+        Some(entries)
+    }
+
+    /// Reference of the inode named `name` in `directory`.
+    fn lookup(&self, directory: &Inode, name: &str) -> Option<u64> {
+        self.entries(directory)?
+            .into_iter()
+            .find_map(|entry| (entry.name == name).then_some(entry.reference))
+    }
+
+    /// Adds the paths under `directory` to `paths`, depth first, until it
+    /// holds `limit`.
+    fn walk(&self, directory: &Inode, prefix: &str, depth: usize, limit: usize, paths: &mut Vec<String>) {
+        for entry in self.entries(directory).unwrap_or_default() {
+            if paths.len() >= limit || !budget::charge(METADATA_BLOCK_SIZE) {
+                return;
+            }
+            let path = format!("{}{}", prefix, entry.name);
+            paths.push(path.clone());
+            if entry.kind != BASIC_DIRECTORY || depth >= MAX_LIST_DEPTH {
+                continue;
+            }
+            if let Some(subdirectory) = self.inode(entry.reference) {
+                self.walk(&subdirectory, &format!("{}/", path), depth + 1, limit, paths);
+            }
+        }
+    }
+
+    /// The contents of a file inode no larger than `MAX_SNAP_YAML_SIZE`.
+    fn read_file(&self, file: &Inode) -> Option<Vec<u8>> {
+        let Inode::File { blocks_start, fragment, fragment_offset, size, ref block_sizes } = *file else {
+            return None;
+        };
+        if size > MAX_SNAP_YAML_SIZE {
+            return None;
+        }
+        let block_size = self.block_size as usize;
+        let mut out = Vec::new();
+        let mut pos = usize::try_from(blocks_start).ok()?;
+        for &word in block_sizes {
+            let on_disk = (word & !DATA_UNCOMPRESSED) as usize;
+            if on_disk == 0 {
+                // Sparse block
+                out.resize(out.len() + block_size, 0);
+                continue;
+            }
+            out.extend(self.data_block(pos, word)?);
+            pos += on_disk;
+        }
+        if fragment != NO_FRAGMENT {
+            let tail = (size % self.block_size as u64) as usize;
+            let fragment = self.fragment(fragment as usize)?;
+            let start = fragment_offset as usize;
+            out.extend_from_slice(fragment.get(start..start + tail)?);
+        }
+        out.truncate(size as usize);
+        Some(out)
+    }
+
+    fn data_block(&self, pos: usize, word: u32) -> Option<Vec<u8>> {
+        let on_disk = (word & !DATA_UNCOMPRESSED) as usize;
+        let block = self.data.get(pos..pos.checked_add(on_disk)?)?;
+        if word & DATA_UNCOMPRESSED != 0 {
+            Some(block.to_vec())
+        } else {
+            self.decompress(block, self.block_size as usize)
+        }
+    }
+
+    /// Fragment block `index`, found through the fragment table's array of
+    /// metadata block locations.
+    fn fragment(&self, index: usize) -> Option<Vec<u8>> {
+        let location_at = usize::try_from(self.fragment_table).ok()? + index / FRAGMENT_ENTRIES_PER_BLOCK * 8;
+        let location = read_u64(self.data, location_at)?;
+        let offset = index % FRAGMENT_ENTRIES_PER_BLOCK * FRAGMENT_ENTRY_SIZE;
+        let entry = self.metadata(location, offset, FRAGMENT_ENTRY_SIZE)?;
+        self.data_block(usize::try_from(read_u64(&entry, 0)?).ok()?, read_u32(&entry, 8)?)
+    }
+
+    fn snap_yaml(&self) -> Option<Vec<u8>> {
+        let root = self.inode(self.root_inode)?;
+        let meta = self.inode(self.lookup(&root, "meta")?)?;
+        let snap_yaml = self.inode(self.lookup(&meta, "snap.yaml")?)?;
+        self.read_file(&snap_yaml)
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value)
+}
+
+/// The top-level scalars of `snap.yaml` plus `architectures`, written either
+/// inline (`[amd64, arm64]`) or as a block sequence.
+fn parse_snap_yaml(yaml: &str, meta: &mut HashMap<String, String>) {
+    let mut architectures = Vec::new();
+    let mut in_architectures = false;
+    for line in yaml.lines() {
+        if in_architectures {
+            if let Some(item) = line.trim_start().strip_prefix("- ").filter(|_| line.starts_with([' ', '-'])) {
+                architectures.push(unquote(item).to_string());
+                continue;
+            }
+            in_architectures = false;
+        }
+        if line.starts_with([' ', '\t', '#']) {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        if key == "architectures" {
+            let value = value.trim();
+            match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                Some(list) => architectures.extend(list.split(',').map(|a| unquote(a).to_string())),
+                None => in_architectures = value.is_empty(),
+            }
+        } else if let Some((_, field)) = SNAP_YAML_KEYS.iter().find(|(yaml_key, _)| *yaml_key == key) {
+            let value = unquote(value);
+            if !value.is_empty() && !value.starts_with(['|', '>']) {
+                meta.insert(field.to_string(), value.to_string());
+            }
+        }
+    }
+    architectures.retain(|a| !a.is_empty());
+    if !architectures.is_empty() {
+        meta.insert("Architectures".into(), architectures.join(", "));
+    }
+}
+
+fn parse_snap_metadata(data: &[u8]) -> MetadataResult {
+    let fs = SquashFs::parse(data).ok_or("Invalid SquashFS superblock")?;
+
+    let mut meta = HashMap::new();
+    meta.insert("Format".into(), "Snap".into());
+    if let Some(compression) = fs.compression_name() {
+        meta.insert("Compression".into(), compression.into());
+    }
+    meta.insert("BlockSize".into(), fs.block_size.to_string());
+    meta.insert("InodeCount".into(), fs.inode_count.to_string());
+    meta.insert("Timestamp".into(), fs.mkfs_time.to_string());
+
+    let snap_yaml = fs.snap_yaml();
+    meta.insert("HasSnapYaml".into(), snap_yaml.is_some().to_string());
+    if let Some(yaml) = snap_yaml {
+        parse_snap_yaml(&String::from_utf8_lossy(&yaml), &mut meta);
+    }
+    Ok(meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+
+    const SNAP_YAML: &str = "name: hello-world\nversion: '6.4'\nsummary: The 'hello-world' of snaps\n\
+        description: |\n  This is a simple snap.\narchitectures:\n  - amd64\nconfinement: strict\ngrade: stable\n";
+
+    fn compress(compression: u16, data: &[u8]) -> Vec<u8> {
+        if compression == COMPRESSION_XZ {
+            let mut out = Vec::new();
+            let _ = lzma_rs::xz_compress(&mut &data[..], &mut out);
+            return out;
+        }
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        let _ = encoder.write_all(data);
+        encoder.finish().unwrap_or_default()
+    }
+
+    /// An image holding `/meta/snap.yaml`, with the file's tail in a
+    /// fragment and the inode table compressed.
+    fn snap_image(compression: u16) -> Vec<u8> {
+        let block_size = 64u32;
+        let yaml = SNAP_YAML.as_bytes();
+        let full_blocks = yaml.len() / block_size as usize;
+        let tail = &yaml[full_blocks * block_size as usize..];
+
+        // Data: full blocks (uncompressed), then the fragment block (compressed)
+        let mut image = vec![0u8; SUPERBLOCK_SIZE];
+        let blocks_start = image.len() as u32;
+        image.extend(&yaml[..full_blocks * block_size as usize]);
+        let fragment_start = image.len() as u64;
+        let fragment = compress(compression, tail);
+        image.extend(&fragment);
+
+        // Inodes: the snap.yaml file, then the meta and root directories
+        let mut inodes = Vec::new();
+        let header = |kind: u16| [kind.to_le_bytes().as_slice(), &[0u8; 14]].concat();
+        inodes.extend(header(BASIC_FILE));
+        inodes.extend(blocks_start.to_le_bytes());
+        inodes.extend(0u32.to_le_bytes()); // fragment index
+        inodes.extend(0u32.to_le_bytes()); // offset in fragment
+        inodes.extend((yaml.len() as u32).to_le_bytes());
+        for _ in 0..full_blocks {
+            inodes.extend((block_size | DATA_UNCOMPRESSED).to_le_bytes());
+        }
+        let meta_inode = inodes.len() as u16;
+        let meta_listing = 12 + 8 + "snap.yaml".len();
+        inodes.extend(header(BASIC_DIRECTORY));
+        inodes.extend([0u32.to_le_bytes(), 0u32.to_le_bytes()].concat());
+        inodes.extend(((meta_listing + 3) as u16).to_le_bytes());
+        inodes.extend([0u8; 6]);
+        let root_inode = inodes.len() as u16;
+        inodes.extend(header(EXTENDED_DIRECTORY));
+        inodes.extend(0u32.to_le_bytes());
+        inodes.extend(((12 + 8 + "meta".len() + 3) as u32).to_le_bytes());
+        inodes.extend(0u32.to_le_bytes());
+        inodes.extend([0u8; 6]);
+        inodes.extend((meta_listing as u16).to_le_bytes());
+        inodes.extend([0u8; 4]);
+
+        // Directories: meta's listing at 0, root's right after
+        let listing = |name: &str, inode: u16, kind: u16| {
+            let mut out = [0u32.to_le_bytes(), 0u32.to_le_bytes(), 1u32.to_le_bytes()].concat();
+            out.extend(inode.to_le_bytes());
+            out.extend(0u16.to_le_bytes());
+            out.extend(kind.to_le_bytes());
+            out.extend(((name.len() - 1) as u16).to_le_bytes());
+            out.extend(name.as_bytes());
+            out
+        };
+        let directories = [listing("snap.yaml", 0, BASIC_FILE), listing("meta", meta_inode, BASIC_DIRECTORY)].concat();
+
+        let inode_table = image.len() as u64;
+        let compressed = compress(compression, &inodes);
+        image.extend((compressed.len() as u16).to_le_bytes());
+        image.extend(compressed);
+        let directory_table = image.len() as u64;
+        image.extend((directories.len() as u16 | METADATA_UNCOMPRESSED).to_le_bytes());
+        image.extend(&directories);
+        let fragment_entries = image.len() as u64;
+        image.extend((FRAGMENT_ENTRY_SIZE as u16 | METADATA_UNCOMPRESSED).to_le_bytes());
+        image.extend(fragment_start.to_le_bytes());
+        image.extend((fragment.len() as u32).to_le_bytes());
+        image.extend(0u32.to_le_bytes());
+        let fragment_table = image.len() as u64;
+        image.extend(fragment_entries.to_le_bytes());
+
+        image[0..4].copy_from_slice(SQUASHFS_MAGIC);
+        image[4..8].copy_from_slice(&3u32.to_le_bytes());
+        image[12..16].copy_from_slice(&block_size.to_le_bytes());
+        image[20..22].copy_from_slice(&compression.to_le_bytes());
+        image[28..30].copy_from_slice(&SQUASHFS_MAJOR.to_le_bytes());
+        image[32..40].copy_from_slice(&(root_inode as u64).to_le_bytes());
+        let bytes_used = image.len() as u64;
+        image[40..48].copy_from_slice(&bytes_used.to_le_bytes());
+        image[64..72].copy_from_slice(&inode_table.to_le_bytes());
+        image[72..80].copy_from_slice(&directory_table.to_le_bytes());
+        image[80..88].copy_from_slice(&fragment_table.to_le_bytes());
+        image
+    }
+
+    #[test]
+    fn test_snap_metadata() {
+        for (compression, name) in [(COMPRESSION_GZIP, "gzip"), (COMPRESSION_XZ, "xz")] {
+            let image = snap_image(compression);
+            assert!(SnapAnalyzer.sniff(&image));
+            let meta = SnapAnalyzer.parse_metadata(&image).unwrap_or_default();
+            let get = |key: &str| meta.get(key).map(String::as_str);
+
+            assert_eq!(get("Compression"), Some(name));
+            assert_eq!(get("HasSnapYaml"), Some("true"));
+            assert_eq!(get("Name"), Some("hello-world"));
+            assert_eq!(get("Version"), Some("6.4"));
+            assert_eq!(get("Summary"), Some("The 'hello-world' of snaps"));
+            assert_eq!(get("Grade"), Some("stable"));
+            assert_eq!(get("Confinement"), Some("strict"));
+            assert_eq!(get("Architectures"), Some("amd64"));
+            assert_eq!(SnapAnalyzer.file_list(&image, 10), ["meta", "meta/snap.yaml"]);
+        }
+    }
+}
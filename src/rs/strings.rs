@@ -0,0 +1,311 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::provenance::{self, Source};
+use crate::{budget, progress, AnalysisOptions};
+
+/// Shortest run of printable characters worth reporting as a string.
+const MIN_STRING_LEN: usize = 4;
+/// Upper bound on entries per extracted-indicator field.
+const MAX_EXTRACTED: usize = 100;
+/// Leading bytes shown in `HeaderHexDump`.
+const HEADER_DUMP_LEN: usize = 64;
+
+const URL_SCHEMES: [&str; 3] = ["https://", "http://", "ftp://"];
+const URL_TRAILING_PUNCTUATION: &[char] = &['.', ')', ']', '\'', ';', ':', '!', '?'];
+
+/// Bare domains are only reported for these TLDs; matching any `a.b` token
+/// would flag every `kernel32.dll` and `System.IO` in a binary.
+const DOMAIN_TLDS: [&str; 40] = [
+    "com", "net", "org", "info", "biz", "io", "co", "me", "dev", "app", "xyz", "top", "site",
+    "online", "club", "edu", "gov", "mil", "int", "us", "uk", "de", "fr", "ru", "cn", "jp",
+    "kr", "in", "br", "nl", "it", "es", "pl", "ua", "ca", "au", "eu", "tk", "cc", "tv",
+];
+
+fn is_printable(b: u8) -> bool {
+    (0x20..=0x7E).contains(&b)
+}
+
+/// Every run of at least `min_len` printable ASCII characters, followed by
+/// every such run encoded as UTF-16LE (as found in PE resources).
+pub fn printable_strings(data: &[u8], min_len: usize) -> Vec<String> {
+    let mut strings = Vec::new();
+    // One ASCII pass and two UTF-16 passes
+    if !budget::charge(data.len().saturating_mul(3)) {
+        return strings;
+    }
+    let total = data.len().saturating_mul(3);
+    let mut run_start = 0;
+    for (i, &b) in data.iter().enumerate() {
+        if i % progress::REPORT_INTERVAL == 0 {
+            progress::report(i, total);
+        }
+        if is_printable(b) {
+            continue;
+        }
+        if i - run_start >= min_len {
+            if let Ok(s) = std::str::from_utf8(&data[run_start..i]) {
+                strings.push(s.to_string());
+            }
+        }
+        run_start = i + 1;
+    }
+    if data.len() - run_start >= min_len {
+        if let Ok(s) = std::str::from_utf8(&data[run_start..]) {
+            strings.push(s.to_string());
+        }
+    }
+
+    for alignment in 0..2 {
+        let mut current = String::new();
+        let pass_start = data.len() * (alignment + 1);
+        for (i, unit) in data.get(alignment..).unwrap_or_default().chunks_exact(2).enumerate() {
+            if (i * 2) % progress::REPORT_INTERVAL == 0 {
+                progress::report(pass_start + i * 2, total);
+            }
+            if unit[1] == 0 && is_printable(unit[0]) {
+                current.push(unit[0] as char);
+                continue;
+            }
+            if current.len() >= min_len {
+                strings.push(std::mem::take(&mut current));
+            }
+            current.clear();
+        }
+        if current.len() >= min_len {
+            strings.push(current);
+        }
+    }
+    strings
+}
+
+fn is_url_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-._~:/?#[]@!$&'()*+;=%".contains(c)
+}
+
+fn is_valid_host(host: &str) -> bool {
+    host.contains('.')
+        && !host.starts_with('.')
+        && !host.ends_with('.')
+        && host.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+}
+
+fn is_email_local_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "._%+-".contains(c)
+}
+
+fn is_domain_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '.' || c == '-'
+}
+
+/// Dot-separated DNS labels ending in an alphabetic TLD of at least two letters.
+fn is_valid_domain(domain: &str) -> bool {
+    let labels: Vec<&str> = domain.split('.').collect();
+    let tld = labels.last().copied().unwrap_or_default();
+    labels.len() >= 2
+        && tld.len() >= 2
+        && tld.chars().all(|c| c.is_ascii_alphabetic())
+        && labels.iter().all(|label| {
+            !label.is_empty() && label.len() <= 63 && !label.starts_with('-') && !label.ends_with('-')
+        })
+}
+
+/// Finds `user@domain.tld` addresses inside one extracted string.
+fn emails_in(s: &str) -> Vec<&str> {
+    let mut emails = Vec::new();
+    for (at, _) in s.match_indices('@') {
+        let start = s[..at]
+            .rfind(|c: char| !is_email_local_char(c))
+            .map_or(0, |idx| idx + 1);
+        let end = s[at + 1..]
+            .find(|c: char| !is_domain_char(c))
+            .map_or(s.len(), |idx| at + 1 + idx);
+        let local = s[start..at].trim_start_matches('.');
+        let domain = s[at + 1..end].trim_end_matches(['.', '-']);
+        if !local.is_empty() && is_valid_domain(domain) {
+            emails.push(&s[at - local.len()..at + 1 + domain.len()]);
+        }
+    }
+    emails
+}
+
+/// Finds bare domain names with a well-known TLD inside one extracted string.
+fn domains_in(s: &str) -> Vec<String> {
+    s.split(|c: char| !is_domain_char(c))
+        .map(|token| token.trim_matches(['.', '-']))
+        .filter(|token| is_valid_domain(token))
+        .map(str::to_ascii_lowercase)
+        .filter(|domain| {
+            let tld = domain.rsplit('.').next().unwrap_or_default();
+            DOMAIN_TLDS.contains(&tld)
+        })
+        .collect()
+}
+
+/// Finds http(s)/ftp URLs inside one extracted string. Commas end a URL so the
+/// joined `ExtractedUrls` list stays unambiguous.
+fn urls_in(s: &str) -> Vec<&str> {
+    let lower = s.to_ascii_lowercase();
+    let mut urls = Vec::new();
+    let mut pos = 0;
+
+    while let Some((start, scheme)) = URL_SCHEMES
+        .iter()
+        .filter_map(|scheme| lower[pos..].find(scheme).map(|idx| (pos + idx, *scheme)))
+        .min_by_key(|(idx, _)| *idx)
+    {
+        let rest_start = start + scheme.len();
+        let end = s[rest_start..]
+            .find(|c: char| !is_url_char(c))
+            .map_or(s.len(), |idx| rest_start + idx);
+        let url = s[start..end].trim_end_matches(URL_TRAILING_PUNCTUATION);
+
+        let host = url[scheme.len().min(url.len())..]
+            .split(['/', ':', '?', '#'])
+            .next()
+            .unwrap_or_default();
+        if is_valid_host(host) {
+            urls.push(url);
+        }
+        pos = end.max(rest_start);
+    }
+
+    urls
+}
+
+/// Finds the indicators of one kind in an extracted string.
+type Finder = fn(&str) -> Vec<String>;
+
+/// Runs `find` over every extracted string and keeps the distinct matches in
+/// first-seen order, capped at `MAX_EXTRACTED`.
+fn collect_unique(data: &[u8], find: Finder) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut found = Vec::new();
+
+    for s in printable_strings(data, MIN_STRING_LEN) {
+        for item in find(&s) {
+            if found.len() < MAX_EXTRACTED && seen.insert(item.clone()) {
+                found.push(item);
+            }
+        }
+    }
+
+    found
+}
+
+fn url_strings(s: &str) -> Vec<String> {
+    urls_in(s).into_iter().map(String::from).collect()
+}
+
+fn email_strings(s: &str) -> Vec<String> {
+    emails_in(s).into_iter().map(String::from).collect()
+}
+
+/// Every distinct http(s)/ftp URL embedded in `data`.
+pub fn extract_urls(data: &[u8]) -> Vec<String> {
+    collect_unique(data, url_strings)
+}
+
+/// Every distinct email address embedded in `data`.
+pub fn extract_emails(data: &[u8]) -> Vec<String> {
+    collect_unique(data, email_strings)
+}
+
+/// Every distinct bare domain (lowercased) embedded in `data`, including the
+/// hosts of extracted URLs and email addresses.
+pub fn extract_domains(data: &[u8]) -> Vec<String> {
+    collect_unique(data, domains_in)
+}
+
+/// `xxd`-style dump of the first `HEADER_DUMP_LEN` bytes: one line per 16
+/// bytes with the offset, hex bytes and an ASCII gutter.
+pub fn header_hex_dump(data: &[u8]) -> String {
+    let head = &data[..data.len().min(HEADER_DUMP_LEN)];
+    let lines: Vec<String> = head
+        .chunks(16)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+            format!("{:08x}  {:<47}  |{}|", row * 16, hex.join(" "), ascii)
+        })
+        .collect();
+    lines.join("\n")
+}
+
+type Extractor = (&'static str, fn(&[u8]) -> Vec<String>);
+
+const EXTRACTORS: [Extractor; 3] = [
+    ("ExtractedUrls", extract_urls),
+    ("ExtractedEmails", extract_emails),
+    ("ExtractedDomains", extract_domains),
+];
+
+/// Indicator fields added when `AnalysisOptions::deep_scan` is set, limited
+/// to the keys `options` asks for so unwanted scans are never run.
+pub fn insert_deep_scan_fields(data: &[u8], options: &AnalysisOptions, meta: &mut HashMap<String, String>) {
+    let scans = EXTRACTORS.iter().filter(|(key, _)| options.wants_field(key)).count();
+    let tagging = provenance::Tagging::begin(Source::Heuristic, meta);
+    for (key, extract) in EXTRACTORS {
+        if !options.wants_field(key) {
+            continue;
+        }
+        let stage = progress::stage(1.0 / scans as f64);
+        let values = extract(data);
+        drop(stage);
+        if !values.is_empty() {
+            meta.insert(key.into(), values.join(", "));
+        }
+    }
+    tagging.finish(meta);
+    if options.wants_field("HeaderHexDump") {
+        meta.insert("HeaderHexDump".into(), header_hex_dump(data));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_urls() {
+        let mut data = b"\x00\x01see https://example.com/setup.exe. and HTTP://Example.com/a?b=1\x00".to_vec();
+        data.extend("ftp://files.example.org/pub".encode_utf16().flat_map(u16::to_le_bytes));
+        data.extend_from_slice(b"\x00\x00https://example.com/setup.exe http://nohost/ https://");
+
+        assert_eq!(
+            extract_urls(&data),
+            vec![
+                "https://example.com/setup.exe",
+                "HTTP://Example.com/a?b=1",
+                "ftp://files.example.org/pub",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_emails_and_domains() {
+        let data = b"\x00Contact: <Support@Example.co.uk>, see update.example.com; load kernel32.dll\x00a@b\x00";
+
+        assert_eq!(extract_emails(data), vec!["Support@Example.co.uk"]);
+        assert_eq!(extract_domains(data), vec!["example.co.uk", "update.example.com"]);
+    }
+
+    #[test]
+    fn test_header_hex_dump() {
+        let mut data = b"MZ\x90\x00 hello".to_vec();
+        data.extend_from_slice(&[0xFF; 100]);
+
+        let dump = header_hex_dump(&data);
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            "00000000  4d 5a 90 00 20 68 65 6c 6c 6f ff ff ff ff ff ff  |MZ.. hello......|"
+        );
+        assert!(lines[3].starts_with("00000030  ff ff"));
+        assert_eq!(header_hex_dump(b"PK"), format!("00000000  {:<47}  |PK|", "50 4b"));
+    }
+}
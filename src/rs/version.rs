@@ -0,0 +1,369 @@
+//! Version strings in the schemes the formats use: dotted (`1.2.3.4`, with an
+//! optional `-pre-release`), RPM EVR (`epoch:version-release`) and Debian
+//! (`epoch:upstream-revision`). Plain string order gets `1.10` vs `1.9` and
+//! epochs wrong.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::provenance;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionScheme {
+    /// Numeric components compared one by one (missing ones count as 0) and
+    /// semver pre-release precedence.
+    Dotted,
+    /// `rpmvercmp` on the version, then the release.
+    Rpm,
+    /// dpkg's `verrevcmp` on the upstream version, then the revision.
+    Debian,
+}
+
+impl VersionScheme {
+    /// The scheme the given `Format` writes its versions in.
+    pub fn for_format(format: &str) -> Self {
+        match format {
+            "RPM" => VersionScheme::Rpm,
+            "DEB" => VersionScheme::Debian,
+            _ => VersionScheme::Dotted,
+        }
+    }
+}
+
+/// A parsed version. For `Dotted` versions `release` holds the pre-release
+/// and `epoch` is always 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    pub scheme: VersionScheme,
+    pub epoch: u64,
+    pub upstream: String,
+    pub release: Option<String>,
+}
+
+impl Version {
+    /// `None` when `value` is not a version in `scheme`. RPM and Debian
+    /// versions must start with a digit, so free text is not mistaken for one.
+    pub fn parse(value: &str, scheme: VersionScheme) -> Option<Self> {
+        let value = value.trim();
+        match scheme {
+            VersionScheme::Dotted => parse_dotted(value),
+            VersionScheme::Rpm | VersionScheme::Debian => parse_epoch_version_release(value, scheme),
+        }
+    }
+
+    /// Canonical spelling for display and equality: dotted components without
+    /// leading zeros or a `v` prefix, and the epoch only when it is not 0.
+    pub fn normalized(&self) -> String {
+        let mut normalized = String::new();
+        if self.epoch > 0 {
+            normalized.push_str(&format!("{}:", self.epoch));
+        }
+        normalized.push_str(&self.upstream);
+        if let Some(release) = &self.release {
+            normalized.push('-');
+            normalized.push_str(release);
+        }
+        normalized
+    }
+
+    /// Orders two versions of the same scheme (`other`'s scheme is ignored).
+    pub fn compare(&self, other: &Version) -> Ordering {
+        let compare = match self.scheme {
+            VersionScheme::Dotted => return compare_dotted(self, other),
+            VersionScheme::Rpm => rpm_compare,
+            VersionScheme::Debian => debian_compare,
+        };
+        self.epoch.cmp(&other.epoch).then_with(|| compare(&self.upstream, &other.upstream)).then_with(|| {
+            match (&self.release, &other.release) {
+                (Some(a), Some(b)) => compare(a, b),
+                (a, b) => a.is_some().cmp(&b.is_some()),
+            }
+        })
+    }
+}
+
+/// Orders two version strings in `scheme`; `None` unless both parse.
+pub fn compare_versions(a: &str, b: &str, scheme: VersionScheme) -> Option<Ordering> {
+    Some(Version::parse(a, scheme)?.compare(&Version::parse(b, scheme)?))
+}
+
+/// `VersionNormalized` from `ProductVersion`, prefixed with the RPM `Epoch`
+/// and suffixed with its `Release` so RPM packages sort by their full EVR.
+pub fn insert_version_normalized(format: &str, meta: &mut HashMap<String, String>) {
+    let Some(version) = meta.get("ProductVersion") else {
+        return;
+    };
+    let scheme = VersionScheme::for_format(format);
+    let value = match (scheme, meta.get("Epoch"), meta.get("Release")) {
+        (VersionScheme::Rpm, epoch, release) => {
+            let epoch = epoch.map(|epoch| format!("{}:", epoch)).unwrap_or_default();
+            let release = release.map(|release| format!("-{}", release)).unwrap_or_default();
+            format!("{}{}{}", epoch, version, release)
+        }
+        _ => version.clone(),
+    };
+    if let Some(version) = Version::parse(&value, scheme) {
+        meta.insert("VersionNormalized".to_string(), version.normalized());
+        provenance::derive("VersionNormalized", "ProductVersion");
+    }
+}
+
+/// `v1.2.3-rc.1+build`: one to four numeric components separated by dots (or
+/// the `1, 2, 3, 4` commas of old PE version strings); build metadata is
+/// dropped.
+fn parse_dotted(value: &str) -> Option<Version> {
+    let value = value.strip_prefix(['v', 'V']).unwrap_or(value);
+    let value = value.split_once('+').map_or(value, |(version, _)| version);
+    let (core, pre_release) = match value.split_once('-') {
+        Some((core, pre_release)) => (core, Some(pre_release)),
+        None => (value, None),
+    };
+    let components: Vec<&str> = core.split(['.', ',']).map(str::trim).collect();
+    let numeric = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    if components.len() > 4 || !components.iter().all(|part| numeric(part)) {
+        return None;
+    }
+    let pre_release_chars = |b: u8| b.is_ascii_alphanumeric() || b == b'.' || b == b'-';
+    if pre_release.is_some_and(|pre| pre.is_empty() || !pre.bytes().all(pre_release_chars)) {
+        return None;
+    }
+    let upstream = components.iter().map(|part| strip_zeros(part)).collect::<Vec<_>>().join(".");
+    Some(Version { scheme: VersionScheme::Dotted, epoch: 0, upstream, release: pre_release.map(str::to_string) })
+}
+
+fn parse_epoch_version_release(value: &str, scheme: VersionScheme) -> Option<Version> {
+    let (epoch, rest) = match value.split_once(':') {
+        Some((epoch, rest)) => (epoch.parse().ok()?, rest),
+        None => (0, value),
+    };
+    let (upstream, release) = match rest.rsplit_once('-') {
+        Some((upstream, release)) => (upstream, Some(release)),
+        None => (rest, None),
+    };
+    let (upstream_extra, release_extra) = match scheme {
+        VersionScheme::Debian => (".+~-", ".+~"),
+        _ => ("._+~^", "._+~^"),
+    };
+    let valid = |part: &str, extra: &str| {
+        !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || extra.contains(c))
+    };
+    if !upstream.starts_with(|c: char| c.is_ascii_digit())
+        || !valid(upstream, upstream_extra)
+        || release.is_some_and(|release| !valid(release, release_extra))
+    {
+        return None;
+    }
+    Some(Version { scheme, epoch, upstream: upstream.to_string(), release: release.map(str::to_string) })
+}
+
+fn strip_zeros(digits: &str) -> &str {
+    let stripped = digits.trim_start_matches('0');
+    if stripped.is_empty() { "0" } else { stripped }
+}
+
+/// Compares digit strings of any length without overflowing.
+fn compare_numeric(a: &str, b: &str) -> Ordering {
+    let (a, b) = (strip_zeros(a), strip_zeros(b));
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+fn compare_dotted(a: &Version, b: &Version) -> Ordering {
+    let len = a.upstream.split('.').count().max(b.upstream.split('.').count());
+    let component = |version: &Version, i: usize| version.upstream.split('.').nth(i).unwrap_or("0").to_string();
+    (0..len)
+        .map(|i| compare_numeric(&component(a, i), &component(b, i)))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| match (&a.release, &b.release) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(a), Some(b)) => compare_pre_release(a, b),
+        })
+}
+
+/// Semver precedence for dot-separated pre-release identifiers: numeric ones
+/// compare numerically and sort before alphanumeric ones.
+fn compare_pre_release(a: &str, b: &str) -> Ordering {
+    let mut a_ids = a.split('.');
+    let mut b_ids = b.split('.');
+    loop {
+        let ordering = match (a_ids.next(), b_ids.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => {
+                let numeric = |id: &str| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit());
+                match (numeric(a), numeric(b)) {
+                    (true, true) => compare_numeric(a, b),
+                    (true, false) => Ordering::Less,
+                    (false, true) => Ordering::Greater,
+                    (false, false) => a.cmp(b),
+                }
+            }
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+}
+
+/// Length of the leading run of bytes matching `class`.
+fn run_len(data: &[u8], class: fn(u8) -> bool) -> usize {
+    data.iter().position(|&b| !class(b)).unwrap_or(data.len())
+}
+
+fn is_digit(c: u8) -> bool {
+    c.is_ascii_digit()
+}
+
+fn is_letter(c: u8) -> bool {
+    c.is_ascii_alphabetic()
+}
+
+/// Bytes `rpmvercmp` skips between segments.
+fn is_rpm_separator(c: u8) -> bool {
+    !c.is_ascii_alphanumeric() && c != b'~' && c != b'^'
+}
+
+fn as_str(bytes: &[u8]) -> &str {
+    std::str::from_utf8(bytes).unwrap_or_default()
+}
+
+/// `rpmvercmp`: alternating digit and letter segments, separators ignored,
+/// `~` sorting before and `^` after the end of the version.
+fn rpm_compare(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.as_bytes(), b.as_bytes());
+    loop {
+        a = &a[run_len(a, is_rpm_separator)..];
+        b = &b[run_len(b, is_rpm_separator)..];
+        match (a.first() == Some(&b'~'), b.first() == Some(&b'~')) {
+            (true, true) => {
+                (a, b) = (&a[1..], &b[1..]);
+                continue;
+            }
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            (false, false) => {}
+        }
+        match (a.first() == Some(&b'^'), b.first() == Some(&b'^')) {
+            (true, true) => {
+                (a, b) = (&a[1..], &b[1..]);
+                continue;
+            }
+            (true, false) => return if b.is_empty() { Ordering::Greater } else { Ordering::Less },
+            (false, true) => return if a.is_empty() { Ordering::Less } else { Ordering::Greater },
+            (false, false) => {}
+        }
+        let (Some(&first), false) = (a.first(), b.is_empty()) else {
+            return (!a.is_empty()).cmp(&!b.is_empty());
+        };
+        let class: fn(u8) -> bool = if first.is_ascii_digit() { is_digit } else { is_letter };
+        let (a_segment, a_rest) = a.split_at(run_len(a, class));
+        let (b_segment, b_rest) = b.split_at(run_len(b, class));
+        if b_segment.is_empty() {
+            // Segments of different types: the numeric one is newer
+            return if first.is_ascii_digit() { Ordering::Greater } else { Ordering::Less };
+        }
+        let ordering = if first.is_ascii_digit() {
+            compare_numeric(as_str(a_segment), as_str(b_segment))
+        } else {
+            a_segment.cmp(b_segment)
+        };
+        if ordering.is_ne() {
+            return ordering;
+        }
+        (a, b) = (a_rest, b_rest);
+    }
+}
+
+/// dpkg's sort weight for a non-digit byte: `~` before the end of the string,
+/// letters before everything else.
+fn debian_weight(c: Option<&u8>) -> i32 {
+    match c {
+        None => 0,
+        Some(&b'~') => -1,
+        Some(&c) if c.is_ascii_digit() => 0,
+        Some(&c) if c.is_ascii_alphabetic() => c as i32,
+        Some(&c) => c as i32 + 256,
+    }
+}
+
+/// dpkg's `verrevcmp`: non-digit runs compared by `debian_weight`, digit runs
+/// numerically.
+fn debian_compare(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.as_bytes(), b.as_bytes());
+    while !a.is_empty() || !b.is_empty() {
+        while a.first().is_some_and(|c| !c.is_ascii_digit()) || b.first().is_some_and(|c| !c.is_ascii_digit()) {
+            let ordering = debian_weight(a.first()).cmp(&debian_weight(b.first()));
+            if ordering.is_ne() {
+                return ordering;
+            }
+            a = a.get(1..).unwrap_or_default();
+            b = b.get(1..).unwrap_or_default();
+        }
+        let (a_digits, a_rest) = a.split_at(run_len(a, is_digit));
+        let (b_digits, b_rest) = b.split_at(run_len(b, is_digit));
+        let ordering = compare_numeric(as_str(a_digits), as_str(b_digits));
+        if ordering.is_ne() {
+            return ordering;
+        }
+        (a, b) = (a_rest, b_rest);
+    }
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(a: &str, b: &str, scheme: VersionScheme) -> Option<Ordering> {
+        compare_versions(a, b, scheme)
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        use Ordering::{Equal, Greater, Less};
+        use VersionScheme::{Debian, Dotted, Rpm};
+
+        assert_eq!(order("1.9", "1.10", Dotted), Some(Less));
+        assert_eq!(order("1.2", "1.2.0.0", Dotted), Some(Equal));
+        assert_eq!(order("1, 2, 0, 7", "1.2.0.10", Dotted), Some(Less));
+        assert_eq!(order("v2.0.0", "1.99", Dotted), Some(Greater));
+        assert_eq!(order("1.0.0-rc.2", "1.0.0-rc.10", Dotted), Some(Less));
+        assert_eq!(order("1.0.0-rc.1", "1.0.0", Dotted), Some(Less));
+        assert_eq!(order("1.0.0+build.5", "1.0.0", Dotted), Some(Equal));
+        assert_eq!(order("Acme", "1.0", Dotted), None);
+
+        assert_eq!(order("1.10-1", "1.9-3", Rpm), Some(Greater));
+        assert_eq!(order("1:1.0-1", "2.0-1", Rpm), Some(Greater));
+        assert_eq!(order("1.0~rc1-1", "1.0-1", Rpm), Some(Less));
+        assert_eq!(order("1.0^git1-1", "1.0-1", Rpm), Some(Greater));
+        assert_eq!(order("1.0a", "1.0.1", Rpm), Some(Less));
+        assert_eq!(order("2.0-1.el9", "2.0-1.el9_1", Rpm), Some(Less));
+
+        assert_eq!(order("7.88.1-10", "7.88.1-9", Debian), Some(Greater));
+        assert_eq!(order("1:1.0-1", "9.9-1", Debian), Some(Greater));
+        assert_eq!(order("1.0~rc1-1", "1.0-1", Debian), Some(Less));
+        assert_eq!(order("1.0+dfsg-1", "1.0-1", Debian), Some(Greater));
+        assert_eq!(order("1.0a-1", "1.0+b1-1", Debian), Some(Less));
+        assert_eq!(order("2.36-9+deb12u4", "2.36-9+deb12u10", Debian), Some(Less));
+        assert_eq!(order("unstable", "1.0", Debian), None);
+    }
+
+    #[test]
+    fn test_version_normalized() {
+        let normalized = |format: &str, pairs: &[(&str, &str)]| {
+            let mut meta: HashMap<String, String> =
+                pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            insert_version_normalized(format, &mut meta);
+            meta.remove("VersionNormalized")
+        };
+        assert_eq!(normalized("PE", &[("ProductVersion", "v01.2, 0, 7")]).as_deref(), Some("1.2.0.7"));
+        assert_eq!(normalized("DEB", &[("ProductVersion", "0:7.88.1-10")]).as_deref(), Some("7.88.1-10"));
+        assert_eq!(
+            normalized("RPM", &[("ProductVersion", "2.0"), ("Epoch", "1"), ("Release", "3.el9")]).as_deref(),
+            Some("1:2.0-3.el9")
+        );
+        assert_eq!(normalized("MSI", &[("ProductVersion", "latest")]), None);
+    }
+}
@@ -0,0 +1,172 @@
+//! `#[wasm_bindgen]` exports. Each one is a thin JSON wrapper over the native
+//! API in the crate root.
+use wasm_bindgen::prelude::*;
+
+use crate::{batch, AnalysisOptions, MetadataResult};
+
+#[wasm_bindgen(start)]
+pub fn init_panic_hook() {
+    console_error_panic_hook::set_once();
+}
+
+/// Serializes a result as either the metadata object or `{"error": ...}`.
+fn to_json(result: MetadataResult) -> String {
+    match result {
+        Ok(meta) => crate::metadata_to_json(&meta),
+        Err(e) => serde_json::json!({ "error": e }).to_string(),
+    }
+}
+
+#[wasm_bindgen]
+pub fn analyze_file(data: &[u8]) -> String {
+    to_json(crate::parse_metadata(data))
+}
+
+/// Analyze a file with opt-in behaviour controlled by a JSON `AnalysisOptions`
+/// object, e.g. `{"deepScan": true}`. An empty string uses the defaults, which
+/// makes this equivalent to `analyze_file`.
+#[wasm_bindgen]
+pub fn analyze_file_with_options(data: &[u8], options_json: &str) -> String {
+    to_json(
+        AnalysisOptions::from_json(options_json)
+            .and_then(|options| crate::parse_metadata_with_options(data, &options)),
+    )
+}
+
+/// `analyze_file_with_options` for large uploads: `on_progress` is called with
+/// the fraction of the analysis done (0 to 1) as the full-file passes
+/// (Authenticode hashing, checksum, deep scan) advance, at most once per
+/// percent, and with 1 when it is over. The call is still synchronous, so run
+/// it in a Web Worker to keep the page responsive.
+#[wasm_bindgen]
+pub fn analyze_file_with_progress(data: &[u8], options_json: &str, on_progress: &js_sys::Function) -> String {
+    let on_progress = on_progress.clone();
+    to_json(AnalysisOptions::from_json(options_json).and_then(|options| {
+        crate::parse_metadata_with_progress(
+            data,
+            &options,
+            Box::new(move |fraction| {
+                let _ = on_progress.call1(&JsValue::NULL, &JsValue::from_f64(fraction));
+            }),
+        )
+    }))
+}
+
+/// Incremental SHA-256 for streamed uploads: `update` it with each chunk as
+/// it arrives, then pass `finalize()` as the `sha256` analysis option so the
+/// analysis reports it as `Sha256` without hashing the file again.
+#[wasm_bindgen(js_name = Hasher)]
+#[derive(Default)]
+pub struct WasmHasher(crate::Hasher);
+
+#[wasm_bindgen(js_class = Hasher)]
+impl WasmHasher {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    /// The digest as 64 lowercase hex digits. Consumes the hasher.
+    pub fn finalize(self) -> String {
+        self.0.finalize()
+    }
+}
+
+/// Analyze only a prefix of a file (e.g. its first megabyte).
+///
+/// Every result carries `Partial: "true"`. Fields that are reliable from a prefix:
+/// - PE: COFF and optional header fields (Machine, Architecture, Timestamp, EntryPoint,
+///   Subsystem, ...). Version resources, overlay data and Authenticode (`SignedBy`)
+///   may be missing because they usually live past the prefix.
+/// - MSI: Property-table fields when the compound file is intact; otherwise the
+///   heuristic fallback. SummaryInformation fields may be missing.
+/// - RPM: all lead/header fields as long as the header fits in the prefix.
+/// - DEB: control fields, since `control.tar` precedes the payload.
+/// - DMG: not detectable, the koly trailer lives at the end of the image.
+#[wasm_bindgen]
+pub fn analyze_header(data: &[u8]) -> String {
+    to_json(crate::parse_header_metadata(data))
+}
+
+/// Analyze several files in one call to avoid per-call WASM boundary overhead.
+///
+/// Takes a JSON array of base64-encoded files and returns a JSON array where each
+/// entry carries its `index` and either `metadata` or an `error` object.
+#[wasm_bindgen]
+pub fn analyze_batch(files_json: &str) -> String {
+    batch::analyze_batch_json(files_json)
+}
+
+/// List the formats this build can analyze as a JSON array of
+/// `{format, extensions, mime}`, in detection order.
+#[wasm_bindgen]
+pub fn supported_formats() -> String {
+    let formats: Vec<serde_json::Value> = crate::supported_formats()
+        .into_iter()
+        .map(|format| {
+            serde_json::json!({
+                "format": format.name,
+                "extensions": format.extensions,
+                "mime": format.mime_type,
+            })
+        })
+        .collect();
+    serde_json::Value::Array(formats).to_string()
+}
+
+/// Identify the analyzer build that produced a result: crate version, the
+/// formats it can detect and the Cargo features it was compiled with.
+#[wasm_bindgen]
+pub fn analyzer_version() -> String {
+    let formats: Vec<&str> = crate::supported_formats().iter().map(|format| format.name).collect();
+    serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "formats": formats,
+        "features": crate::enabled_features(),
+    })
+    .to_string()
+}
+
+/// Compare two `analyze_file` results (JSON metadata objects) and return a
+/// JSON array of `{field, kind, old, new, versionChange?}` for every field
+/// added, removed or changed, sorted by field name.
+#[wasm_bindgen]
+pub fn diff_analyses(a_json: &str, b_json: &str) -> String {
+    let parse = |json: &str| {
+        serde_json::from_str::<std::collections::HashMap<String, String>>(json)
+            .map_err(|e| format!("Invalid analysis result: {}", e))
+    };
+    match parse(a_json).and_then(|a| Ok((a, parse(b_json)?))) {
+        Ok((a, b)) => serde_json::to_string(&crate::diff::diff_fields(&a, &b)).unwrap_or_else(|_| "[]".to_string()),
+        Err(e) => serde_json::json!({ "error": e }).to_string(),
+    }
+}
+
+/// Order two version strings in the scheme of `format` (`RPM` EVR, `DEB`,
+/// otherwise dotted): -1, 0 or 1, or `undefined` when either does not parse.
+#[wasm_bindgen]
+pub fn compare_versions(a: &str, b: &str, format: &str) -> Option<i32> {
+    crate::compare_versions(a, b, crate::VersionScheme::for_format(format)).map(|ordering| ordering as i32)
+}
+
+/// Describe every field this build's analyzers can emit, as a JSON
+/// `{fields: [{name, type, formats}], openFormats}` object generated from the
+/// crate's field registry.
+#[wasm_bindgen]
+pub fn output_schema() -> String {
+    crate::output_schema().to_string()
+}
+
+#[wasm_bindgen]
+pub fn get_file_info(data: &[u8]) -> String {
+    to_json(crate::get_file_info(data))
+}
+
+#[wasm_bindgen]
+pub fn analyze_pe_file(data: &[u8]) -> String {
+    to_json(crate::parse_metadata(data))
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use crate::{Confidence, FileAnalyzer, FormatInfo, MetadataResult};
+
+const WASM_MAGIC: &[u8] = b"\0asm";
+const WASM_HEADER_SIZE: usize = 8;
+
+const SECTION_CUSTOM: u8 = 0;
+const SECTION_IMPORT: u8 = 2;
+const SECTION_EXPORT: u8 = 7;
+
+const FORMAT: FormatInfo = FormatInfo {
+    name: "WebAssembly",
+    mime_type: "application/wasm",
+    extensions: &["wasm"],
+};
+
+pub struct WasmAnalyzer;
+
+impl FileAnalyzer for WasmAnalyzer {
+    fn format(&self) -> &'static FormatInfo {
+        &FORMAT
+    }
+
+    fn sniff(&self, data: &[u8]) -> bool {
+        is_wasm_file(data)
+    }
+
+    fn detection_confidence(&self, data: &[u8]) -> Confidence {
+        if read_sections(data).is_some() {
+            Confidence::High
+        } else {
+            Confidence::Medium
+        }
+    }
+
+    fn parse_metadata(&self, data: &[u8]) -> MetadataResult {
+        parse_wasm_metadata(data)
+    }
+}
+
+pub fn is_wasm_file(data: &[u8]) -> bool {
+    data.len() >= WASM_HEADER_SIZE && data.starts_with(WASM_MAGIC)
+}
+
+/// Reads an unsigned LEB128 value, advancing `pos` past it.
+fn read_uleb128(data: &[u8], pos: &mut usize) -> Option<u32> {
+    let mut result: u32 = 0;
+    for shift in (0..35).step_by(7) {
+        let byte = *data.get(*pos)?;
+        *pos += 1;
+        result |= ((byte & 0x7F) as u32).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(result);
+        }
+    }
+    None
+}
+
+struct Section<'a> {
+    id: u8,
+    contents: &'a [u8],
+}
+
+/// Splits the module body into sections. `None` if a section header or
+/// length runs past the end of the data.
+fn read_sections(data: &[u8]) -> Option<Vec<Section<'_>>> {
+    let mut sections = Vec::new();
+    let mut pos = WASM_HEADER_SIZE;
+    while pos < data.len() {
+        let id = data[pos];
+        pos += 1;
+        let size = read_uleb128(data, &mut pos)? as usize;
+        let contents = data.get(pos..pos.checked_add(size)?)?;
+        sections.push(Section { id, contents });
+        pos += size;
+    }
+    Some(sections)
+}
+
+fn custom_section_name(contents: &[u8]) -> Option<&str> {
+    let mut pos = 0;
+    let len = read_uleb128(contents, &mut pos)? as usize;
+    std::str::from_utf8(contents.get(pos..pos.checked_add(len)?)?).ok()
+}
+
+fn parse_wasm_metadata(data: &[u8]) -> MetadataResult {
+    if !is_wasm_file(data) {
+        return Err("Not a valid WebAssembly module".to_string());
+    }
+
+    let mut meta = HashMap::new();
+    meta.insert("Format".into(), "WebAssembly".into());
+
+    let version = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+    meta.insert("Version".into(), version.to_string());
+
+    let sections = read_sections(data).ok_or("Truncated WebAssembly section")?;
+    meta.insert("NumberOfSections".into(), sections.len().to_string());
+
+    let mut custom_names = Vec::new();
+    for Section { id, contents } in &sections {
+        match *id {
+            SECTION_IMPORT => {
+                let count = read_uleb128(contents, &mut 0).unwrap_or(0);
+                meta.insert("ImportCount".into(), count.to_string());
+            }
+            SECTION_EXPORT => {
+                let count = read_uleb128(contents, &mut 0).unwrap_or(0);
+                meta.insert("ExportCount".into(), count.to_string());
+            }
+            SECTION_CUSTOM => {
+                if let Some(name) = custom_section_name(contents) {
+                    custom_names.push(name);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    meta.entry("ImportCount".into()).or_insert_with(|| "0".into());
+    meta.entry("ExportCount".into()).or_insert_with(|| "0".into());
+    if custom_names.contains(&"name") {
+        meta.insert("HasNameSection".into(), "true".into());
+    }
+    if !custom_names.is_empty() {
+        meta.insert("CustomSections".into(), custom_names.join(", "));
+    }
+
+    Ok(meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wasm_metadata() {
+        let mut module = b"\0asm\x01\0\0\0".to_vec();
+        // import section with 2 entries (contents truncated to the count)
+        module.extend_from_slice(&[SECTION_IMPORT, 1, 2]);
+        // export section with 1 entry
+        module.extend_from_slice(&[SECTION_EXPORT, 1, 1]);
+        // custom "name" section
+        module.extend_from_slice(&[SECTION_CUSTOM, 5, 4]);
+        module.extend_from_slice(b"name");
+
+        let meta = parse_wasm_metadata(&module).unwrap_or_default();
+        assert_eq!(meta.get("Version").map(String::as_str), Some("1"));
+        assert_eq!(meta.get("ImportCount").map(String::as_str), Some("2"));
+        assert_eq!(meta.get("ExportCount").map(String::as_str), Some("1"));
+        assert_eq!(meta.get("HasNameSection").map(String::as_str), Some("true"));
+
+        module.extend_from_slice(&[SECTION_EXPORT, 10]);
+        assert!(parse_wasm_metadata(&module).is_err());
+    }
+}
@@ -0,0 +1,54 @@
+//! Just enough XML for the small documents analyzers read (application
+//! manifests, App Installer files): find a tag, read an attribute or the text
+//! of an element. No entity decoding or validation.
+
+/// Byte range of the attributes in the first `name` opening tag (namespace
+/// prefix allowed), ending just before its `>`.
+fn xml_element_span(xml: &str, name: &str) -> Option<(usize, usize)> {
+    let mut pos = 0;
+    while let Some(idx) = xml[pos..].find(name) {
+        let start = pos + idx;
+        let end = start + name.len();
+        let opens_tag = xml[..start].trim_end_matches(|c: char| c.is_alphanumeric() || c == '_').ends_with(['<', ':']);
+        let ends_name = xml[end..].starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/');
+        if opens_tag && ends_name {
+            let tag_end = xml[end..].find('>').map_or(xml.len(), |i| end + i);
+            return Some((end, tag_end));
+        }
+        pos = end;
+    }
+    None
+}
+
+pub fn xml_element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    xml_element_span(xml, name).map(|(start, end)| &xml[start..end])
+}
+
+pub fn xml_attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut pos = 0;
+    while let Some(idx) = tag[pos..].find(name) {
+        let start = pos + idx;
+        let rest = tag[start + name.len()..].trim_start();
+        let preceded_by_space = tag[..start].ends_with(char::is_whitespace);
+        if let (true, Some(value)) = (preceded_by_space, rest.strip_prefix('=')) {
+            let value = value.trim_start();
+            let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+            let value = &value[1..];
+            return value.find(quote).map(|end| &value[..end]);
+        }
+        pos = start + name.len();
+    }
+    None
+}
+
+/// Trimmed text content of the first `name` element.
+#[cfg(feature = "pe")]
+pub fn xml_element_text<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let (start, end) = xml_element_span(xml, name)?;
+    if xml[start..end].ends_with('/') {
+        return None;
+    }
+    let content = xml.get(end + 1..)?;
+    let text = content[..content.find('<')?].trim();
+    (!text.is_empty()).then_some(text)
+}
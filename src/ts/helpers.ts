@@ -8,9 +8,21 @@ import type {
   FileAnalysis,
   PEAnalysis,
   MSIAnalysis,
+  CompoundFileAnalysis,
   DMGAnalysis,
   DEBAnalysis,
   RPMAnalysis,
+  SnapAnalysis,
+  FlatpakAnalysis,
+  CPIOAnalysis,
+  ISOAnalysis,
+  LNKAnalysis,
+  MSUAnalysis,
+  AppInstallerAnalysis,
+  ELFAnalysis,
+  JavaClassAnalysis,
+  MachOAnalysis,
+  WasmAnalysis,
   AnalysisError
 } from './types';
 
@@ -24,6 +36,10 @@ export function isMSIAnalysis(analysis: FileAnalysis): analysis is MSIAnalysis {
   return 'Format' in analysis && analysis.Format === 'MSI';
 }
 
+export function isCompoundFileAnalysis(analysis: FileAnalysis): analysis is CompoundFileAnalysis {
+  return 'Format' in analysis && analysis.Format === 'CompoundFile';
+}
+
 export function isDMGAnalysis(analysis: FileAnalysis): analysis is DMGAnalysis {
   return 'Format' in analysis && analysis.Format === 'DMG';
 }
@@ -36,6 +52,50 @@ export function isRPMAnalysis(analysis: FileAnalysis): analysis is RPMAnalysis {
   return 'Format' in analysis && analysis.Format === 'RPM';
 }
 
+export function isSnapAnalysis(analysis: FileAnalysis): analysis is SnapAnalysis {
+  return 'Format' in analysis && analysis.Format === 'Snap';
+}
+
+export function isFlatpakAnalysis(analysis: FileAnalysis): analysis is FlatpakAnalysis {
+  return 'Format' in analysis && analysis.Format === 'Flatpak';
+}
+
+export function isCPIOAnalysis(analysis: FileAnalysis): analysis is CPIOAnalysis {
+  return 'Format' in analysis && analysis.Format === 'CPIO';
+}
+
+export function isISOAnalysis(analysis: FileAnalysis): analysis is ISOAnalysis {
+  return 'Format' in analysis && analysis.Format === 'ISO9660';
+}
+
+export function isLNKAnalysis(analysis: FileAnalysis): analysis is LNKAnalysis {
+  return 'Format' in analysis && analysis.Format === 'LNK';
+}
+
+export function isMSUAnalysis(analysis: FileAnalysis): analysis is MSUAnalysis {
+  return 'Format' in analysis && analysis.Format === 'MSU';
+}
+
+export function isAppInstallerAnalysis(analysis: FileAnalysis): analysis is AppInstallerAnalysis {
+  return 'Format' in analysis && analysis.Format === 'AppInstaller';
+}
+
+export function isELFAnalysis(analysis: FileAnalysis): analysis is ELFAnalysis {
+  return 'Format' in analysis && analysis.Format === 'ELF';
+}
+
+export function isJavaClassAnalysis(analysis: FileAnalysis): analysis is JavaClassAnalysis {
+  return 'Format' in analysis && analysis.Format === 'JavaClass';
+}
+
+export function isMachOAnalysis(analysis: FileAnalysis): analysis is MachOAnalysis {
+  return 'Format' in analysis && analysis.Format === 'Mach-O';
+}
+
+export function isWasmAnalysis(analysis: FileAnalysis): analysis is WasmAnalysis {
+  return 'Format' in analysis && analysis.Format === 'WebAssembly';
+}
+
 export function isAnalysisError(analysis: FileAnalysis): analysis is AnalysisError {
   return 'error' in analysis;
 }
@@ -4,17 +4,111 @@
 
 // ========== Basic File Info Types ==========
 
-export interface FileInfo {
+/**
+ * Type hints derived from the detected format (never from the uploaded filename)
+ */
+export interface DetectionInfo {
+  MimeType: string;
+  Extension: string;
+  DetectionConfidence: "High" | "Medium" | "Low";
+  /** Set by analyze_header: trailer-dependent fields may be missing */
+  Partial?: "true";
+  /**
+   * The file's own headers describe more bytes than were uploaded (PE, ELF,
+   * MSI, RPM, ISO); ExpectedSize and ActualSize are byte counts
+   */
+  Truncated?: "true";
+  ExpectedSize?: string;
+  ActualSize?: string;
+  /**
+   * DEB (data.tar member), RPM (payload) and MSU (cabinet) bytes, and the size
+   * their headers declare once unpacked (installed size, cabinet file sizes)
+   */
+  CompressedSize?: string;
+  UncompressedSize?: string;
+  /** UncompressedSize / CompressedSize with one decimal, e.g. "3.2" */
+  CompressionRatio?: string;
+  /** CompressionRatio exceeds AnalysisOptions.maxCompressionRatio: a likely decompression bomb */
+  SuspiciousCompressionRatio?: "true" | "false";
+  /**
+   * BCP-47 tag (e.g. "en-US") from PE version info, MSI ProductLanguage or a
+   * bundle's CFBundleDevelopmentRegion; unmapped Windows LCIDs stay decimal
+   */
+  Language?: string;
+  /** Canonical product fields, filled from each format's own keys where the data exists */
+  ProductName?: string;
+  ProductVersion?: string;
+  /**
+   * ProductVersion in canonical form ("1, 2, 0, 7" -> "1.2.0.7"); RPM adds
+   * Epoch and Release ("1:2.0-3.el9"). Order with compare_versions
+   */
+  VersionNormalized?: string;
+  Publisher?: string;
+  /** High-level facet; a PE with IsInstaller "true" is a "Windows Installer" */
+  Category?:
+    | "Windows Installer"
+    | "Linux Package"
+    | "macOS Installer"
+    | "Executable"
+    | "Archive"
+    | "Document"
+    | "Shortcut";
+  /** Deep scan only (AnalysisOptions.deepScan): comma-separated http(s)/ftp URLs */
+  ExtractedUrls?: string;
+  /** Deep scan only: comma-separated email addresses */
+  ExtractedEmails?: string;
+  /** Deep scan only: comma-separated lowercased domains with a well-known TLD */
+  ExtractedDomains?: string;
+  /** Deep scan only: newline-separated hex + ASCII dump of the first 64 bytes */
+  HeaderHexDump?: string;
+  /**
+   * Lowercase hex SHA-256 of the whole file: AnalysisOptions.sha256 when
+   * given, otherwise computed by the deep scan
+   */
+  Sha256?: string;
+  /**
+   * Deep scan only: base64 of the embedded Info.plist (DMG, Mach-O) or
+   * application manifest (PE) exactly as stored, for re-parsing downstream
+   */
+  RawManifest?: string;
+  /**
+   * Deep scan only: comma-separated paths of the first 200 files in a CPIO,
+   * DEB, MSU, RPM or Snap package, in archive order
+   */
+  FileList?: string;
+  /** "true" when the package holds more files than FileList lists */
+  FileListTruncated?: "true";
+  /**
+   * Deep scan only, for the formats listed in FileList: whether any entry has
+   * a ".." component or, outside RPM, an absolute path (zip-slip)
+   */
+  HasPathTraversal?: "true" | "false";
+  /**
+   * Only with AnalysisOptions.maxEmbeddedDepth > 0: JSON-encoded analysis of the
+   * first embedded object (e.g. the MSI in a PE bootstrapper), with its byte Offset
+   */
+  Embedded?: string;
+  /**
+   * Only with AnalysisOptions.includeSources: JSON-encoded object mapping every
+   * other field but Format to its FieldSource
+   */
+  _sources?: string;
+}
+
+export type FieldSource = "structured" | "heuristic" | "signature" | "embedded";
+
+export interface FileInfo extends DetectionInfo {
   Format: string;
   Size: string;
 }
 
 // ========== PE File Analysis Types ==========
 
-export interface PEAnalysis {
+export interface PEAnalysis extends DetectionInfo {
   // Basic Format
   Format: "PE";
-  Architecture: "x86" | "x64";
+  /** From the COFF Machine field */
+  Architecture: "x86" | "x64" | "ARM64" | "ARM" | "IA64" | "RISCV32" | "RISCV64" | "LoongArch64";
 
   // File Header
   Machine?: string;
@@ -24,12 +118,25 @@ export interface PEAnalysis {
   PointerToSymbolTable?: string;
   NumberOfSymbols?: string;
   Timestamp?: string;
+  /** Timestamp is 0, or a /Brepro debug entry says it is a hash of the image */
+  LikelyReproducibleBuild?: "true" | "false";
 
   // Optional Header
   EntryPoint?: string;
   ImageBase?: string;
   SizeOfImage?: string;
+  /** "Windows GUI", "Windows Console", "Native", "EFI Application", ...; the raw value when unknown */
   Subsystem?: string;
+  IsGuiApp?: "true" | "false";
+
+  // Decoded Characteristics / DllCharacteristics bits
+  IsDll?: "true" | "false";
+  IsSystem?: "true" | "false";
+  ASLR?: "true" | "false";
+  DEP?: "true" | "false";
+  HighEntropyVA?: "true" | "false";
+  ControlFlowGuard?: "true" | "false";
+  ForceIntegrity?: "true" | "false";
   DllCharacteristics?: string;
 
   // Version Information
@@ -44,7 +151,9 @@ export interface PEAnalysis {
   FileType?: string;
 
   // String Version Info
+  /** From version info, or else the Authenticode signer (see CompanyNameSource) */
   CompanyName?: string;
+  CompanyNameSource?: "version-info" | "digital-signature";
   ProductName?: string;
   FileDescription?: string;
   InternalName?: string;
@@ -59,11 +168,61 @@ export interface PEAnalysis {
 
   // Digital Signature
   SignedBy?: string;
+  /**
+   * Signer certificate classification (no chain or signature validation):
+   * its issuer is its own subject, or an issuer up its embedded chain is
+   * named like a well-known public CA. A hint, not trust
+   */
+  CertificateTrust?: "SelfSigned" | "IssuerNameResemblesKnownCA" | "Unknown";
+  /** UTC time from the signature's RFC 3161 or legacy timestamp countersignature, e.g. "2024-01-02T03:04:05Z" */
+  SigningTime?: string;
 
   // Installer Detection
   InstallerType?: "Inno Setup" | "NSIS (Nullsoft)" | "InstallShield" | "WiX Toolset" | "Wise Installer" | "Setup Factory" | "Smart Install Maker";
   EmbeddedMSI?: "true" | "false";
+  /** Offset of the embedded compound file; the one actually parsed when several are present */
   MSIOffset?: string;
+  /** Why no embedded compound file yielded MSI metadata, when one was found */
+  EmbeddedMSIWarning?: string;
+  /** "true" when an installer toolkit or an embedded MSI was found */
+  IsInstaller?: "true" | "false";
+
+  // Overlay (data past the last section) and appended archives
+  OverlayOffset?: string;
+  OverlaySize?: string;
+  ContainedArchive?: "ZIP" | "7z";
+  ContainedArchiveOffset?: string;
+  ContainedArchiveEntries?: string;
+
+  /** Authenticode SHA-256 image hash (excludes CheckSum, security directory entry and certificates) */
+  AuthentiHash?: string;
+  /** MD5 over the ordered import table (pefile-compatible imphash) */
+  ImpHash?: string;
+  /** Comma-separated DLLs from the import table, then the delay-load and bound import directories */
+  ImportedDlls?: string;
+  /** Comma-separated subset of ImportedDlls that is delay-loaded */
+  DelayLoadedDlls?: string;
+  /** Optional-header CheckSum as "0x%08X"; 0x00000000 means the linker left it unset */
+  CheckSumStored?: string;
+  /** Checksum recomputed over the file with the standard PE algorithm */
+  CheckSumComputed?: string;
+  CheckSumValid?: "true" | "false";
+  /** TLS callbacks run before the entry point */
+  HasTlsCallbacks?: "true" | "false";
+  TlsCallbackCount?: string;
+
+  // CodeView debug entry
+  PdbPath?: string;
+  /** RSDS GUID, e.g. "3F2504E0-4F89-11D3-9A0C-0305E82C3301" */
+  PdbGuid?: string;
+  PdbAge?: string;
+
+  // Application manifest (RT_MANIFEST)
+  RequestedExecutionLevel?: "asInvoker" | "highestAvailable" | "requireAdministrator";
+  /** dpiAwareness value when declared, otherwise dpiAware (e.g. "true/pm", "PerMonitorV2") */
+  DpiAware?: string;
+  /** Comma-separated OS names from the manifest compatibility section */
+  SupportedOS?: string;
 
   // Translation/Language
   TranslationCount?: string;
@@ -87,7 +246,7 @@ export interface PEAnalysis {
 
 // ========== MSI File Analysis Types ==========
 
-export interface MSIAnalysis {
+export interface MSIAnalysis extends DetectionInfo {
   // Basic Format
   Format: "MSI";
   Architecture: "Windows Installer Package";
@@ -95,13 +254,18 @@ export interface MSIAnalysis {
   // Standard Metadata
   ProductName?: string;
   ProductVersion?: string;
+  /** Whether ProductVersion is major.minor.build (255.255.65535, optional 4th field) */
+  ProductVersionValid?: "true" | "false";
   Manufacturer?: string;
   Publisher?: string;
   CompanyName?: string;
   Vendor?: string;
   ProductCode?: string;
   UpgradeCode?: string;
+  /** Summary information PID 9, as an uppercase braced GUID */
   PackageCode?: string;
+  /** First 16 hex digits of the SHA-256 of PackageCode; stable per package build */
+  PackageId?: string;
   Title?: string;
   Comments?: string;
   Keywords?: string;
@@ -115,24 +279,68 @@ export interface MSIAnalysis {
   // Requirements
   LaunchConditions?: string;
 
+  /** Complete Property table as a JSON-encoded `Record<string, string>` */
+  Properties?: string;
+  /** From `ALLUSERS`/`MSIINSTALLPERUSER`; only set when the Property table was read */
+  InstallScope?: "PerMachine" | "PerUser";
+  /** "false" only for per-user packages whose summary Word Count has the no-elevation bit (0x08) */
+  RequiresElevation?: "true" | "false";
+
+  // Add/Remove Programs (ARP* properties)
+  HelpLink?: string;
+  HelpTelephone?: string;
+  AboutUrl?: string;
+  UpdateUrl?: string;
+  Contact?: string;
+  /** ARPSIZE, in kilobytes */
+  EstimatedSize?: string;
+  /** Set when ARPNOREMOVE is authored: the product cannot be removed from Add/Remove Programs */
+  NoRemove?: "true";
+
   // Installer Framework
   InstallerFramework?: "WiX Toolset" | "InstallShield" | "Advanced Installer";
 
   // Compound File
+  /** Root storage CLSID as an uppercase braced GUID; absent when nil */
+  RootClsid?: string;
+  /** "MSI Package", "MSI Patch" or "MSI Transform" per RootClsid */
+  CompoundFileType?: CompoundFileType;
   HasCompoundFile?: "true" | "false";
   HasSummaryInfo?: "true" | "false";
   CompoundFileError?: string;
 }
 
+// ========== Compound File Analysis Types ==========
+
+export type CompoundFileType =
+  | "MSI Package"
+  | "MSI Patch"
+  | "MSI Transform"
+  | "Word Document"
+  | "Word 6/95 Document"
+  | "Excel Workbook"
+  | "Excel 5/95 Workbook"
+  | "PowerPoint Presentation";
+
+export interface CompoundFileAnalysis extends DetectionInfo {
+  // Basic Format
+  Format: "CompoundFile";
+  /** Root storage CLSID as an uppercase braced GUID; absent when nil */
+  RootClsid?: string;
+  /** Document type for a well-known RootClsid */
+  CompoundFileType?: CompoundFileType;
+}
+
 // ========== DMG File Analysis Types ==========
 
-export interface DMGAnalysis {
+export interface DMGAnalysis extends DetectionInfo {
   // Basic Format
   Format: "DMG";
   Architecture: "macOS Disk Image";
 
   // DMG Information
   ImageType?: string;
+  /** From the blkx chunk types: "zlib", "bzip2", "lzfse", "lzma", "adc" or "uncompressed"; "gzip" or "unknown" when guessed */
   Compression?: string;
   HasKolySignature?: "true" | "false";
   KolyOffset?: string;
@@ -168,7 +376,7 @@ export interface DMGAnalysis {
 
 // ========== DEB File Analysis Types ==========
 
-export interface DEBAnalysis {
+export interface DEBAnalysis extends DetectionInfo {
   // Basic Format
   Format: "DEB";
   Architecture?: string;
@@ -180,6 +388,32 @@ export interface DEBAnalysis {
   Description?: string;
   Section?: string;
   Priority?: string;
+  /** Section without its archive area, lowercased (e.g. "net" for "contrib/net") */
+  PackageSection?: string;
+  /** Lowercased Priority; the deprecated "extra" is reported as "optional" */
+  PackagePriority?: string;
+  /** Lowercased Multi-Arch; "no" when the control file does not set it */
+  MultiArch?: "no" | "same" | "foreign" | "allowed";
+  /** "true" when the control file declares Essential: yes */
+  IsEssential?: "true" | "false";
+  /** "true" for debug symbol packages (.ddeb): a -dbgsym name or Auto-Built-Package: debug-symbols */
+  IsDebugPackage?: "true" | "false";
+
+  // Provenance
+  /** Source package name from Source, or the binary package name when Source is absent */
+  SourcePackage?: string;
+  /** Version in Source's parentheses, or the binary Version when it has none */
+  SourceVersion?: string;
+  /** JSON object mapping each Built-Using source package to its version */
+  BuiltUsing?: string;
+
+  // Signatures
+  /** "true" when a _gpgorigin, _gpgbuilder or _gpgmaint ar member is present */
+  IsSigned?: "true" | "false";
+  /** Comma-separated signature member names */
+  SignatureMembers?: string;
+  /** Total size of the signature members, in bytes */
+  SignatureSize?: string;
   Depends?: string;
   Homepage?: string;
 
@@ -200,7 +434,7 @@ export interface DEBAnalysis {
 
 // ========== RPM File Analysis Types ==========
 
-export interface RPMAnalysis {
+export interface RPMAnalysis extends DetectionInfo {
   // Basic Format
   Format: "RPM";
   Architecture?: string;
@@ -209,12 +443,27 @@ export interface RPMAnalysis {
   Package?: string;
   Version?: string;
   Release?: string;
+  Epoch?: string;
   Vendor?: string;
+  /** In AnalysisOptions.locale when the header has it, otherwise `C` */
   Summary?: string;
+  /** Localized like Summary; the Summary when the header has no description */
+  Description?: string;
+  /** Comma-separated locales the header's I18N strings are translated into, `C` first */
+  Locales?: string;
   License?: string;
   GroupName?: string;
   Url?: string;
   SourceRpm?: string;
+  HeaderSha1?: string;
+  HasPreInstallScript?: string;
+  HasPostInstallScript?: string;
+  HasPreUninstallScript?: string;
+  HasPostUninstallScript?: string;
+  PreInstallInterpreter?: string;
+  PostInstallInterpreter?: string;
+  PreUninstallInterpreter?: string;
+  PostUninstallInterpreter?: string;
 
   // Standard Metadata
   ProductName?: string;
@@ -231,22 +480,411 @@ export interface RPMAnalysis {
   Keywords?: string;
 }
 
+// ========== Snap Package Analysis Types ==========
+
+export interface SnapAnalysis extends DetectionInfo {
+  // Basic Format
+  Format: "Snap";
+
+  // meta/snap.yaml (ProductName and ProductVersion mirror Name and Version)
+  Name?: string;
+  Version?: string;
+  Summary?: string;
+  /** "stable" or "devel" */
+  Grade?: string;
+  /** "strict", "classic" or "devmode" */
+  Confinement?: string;
+  Base?: string;
+  SnapType?: string;
+  Architectures?: string;
+  HasSnapYaml: "true" | "false";
+
+  // SquashFS superblock
+  Compression?: "gzip" | "lzma" | "lzo" | "xz" | "lz4" | "zstd";
+  BlockSize: string;
+  InodeCount: string;
+  /** mkfs time, Unix seconds */
+  Timestamp: string;
+}
+
+// ========== Flatpak Bundle Analysis Types ==========
+
+export interface FlatpakAnalysis extends DetectionInfo {
+  // Basic Format
+  Format: "Flatpak";
+
+  // Bundle ref ("app/org.gnome.Maps/x86_64/stable"); ProductName mirrors AppId
+  Ref?: string;
+  BundleKind?: "app" | "runtime";
+  AppId?: string;
+  Architecture?: string;
+  Branch?: string;
+
+  // [Application] group of the metadata key file
+  Runtime?: string;
+  Sdk?: string;
+  Command?: string;
+
+  // Where updates come from
+  Origin?: string;
+  RuntimeRepo?: string;
+}
+
+// ========== CPIO Archive Analysis Types ==========
+
+export interface CPIOAnalysis extends DetectionInfo {
+  // Basic Format
+  Format: "CPIO";
+  CpioFormat?: "newc" | "crc" | "odc";
+
+  // Archive Contents
+  EntryCount?: string;
+  TotalFileSize?: string;
+  FirstEntries?: string;
+  MissingTrailer?: "true";
+}
+
+// ========== ISO 9660 Image Analysis Types ==========
+
+export interface ISOAnalysis extends DetectionInfo {
+  // Basic Format
+  Format: "ISO9660";
+
+  // Primary Volume Descriptor
+  SystemIdentifier?: string;
+  VolumeIdentifier?: string;
+  VolumeSetIdentifier?: string;
+  PublisherIdentifier?: string;
+  DataPreparerIdentifier?: string;
+  ApplicationIdentifier?: string;
+  VolumeSpaceSize?: string;
+  LogicalBlockSize?: string;
+  VolumeSize?: string;
+  CreationDate?: string;
+
+  // Standard Metadata (aliases)
+  ProductName?: string;
+  CompanyName?: string;
+}
+
+// ========== Windows Shortcut (LNK) Analysis Types ==========
+
+export interface LNKAnalysis extends DetectionInfo {
+  // Basic Format
+  Format: "LNK";
+
+  // ShellLinkHeader
+  LinkFlags?: string;
+  LinkFlagNames?: string;
+  TargetAttributes?: string;
+  TargetFileSize?: string;
+
+  // Link target and StringData
+  LinkTarget?: string;
+  Description?: string;
+  RelativePath?: string;
+  WorkingDirectory?: string;
+  CommandLineArgs?: string;
+  IconLocation?: string;
+}
+
+// ========== Windows Update Package (MSU) Analysis Types ==========
+
+export interface MSUAnalysis extends DetectionInfo {
+  // Basic Format
+  Format: "MSU";
+
+  // From *-pkgProperties.txt, or the update file names
+  KbNumber?: string;
+  TargetOS?: string;
+  Architecture?: string;
+  PackageType?: string;
+  InstallationType?: string;
+  AppliesTo?: string;
+  SupportLink?: string;
+
+  // Cabinet contents
+  CabinetFiles?: string;
+}
+
+// ========== App Installer File Analysis Types ==========
+
+export interface AppInstallerAnalysis extends DetectionInfo {
+  // Basic Format
+  Format: "AppInstaller";
+
+  // <AppInstaller> root element
+  AppInstallerUri?: string;
+  AppInstallerVersion?: string;
+
+  // <MainBundle> or <MainPackage>
+  PackageType?: "Bundle" | "Package";
+  PackageUri?: string;
+  Architecture?: string;
+  Dependencies?: string;
+
+  // <UpdateSettings>
+  HoursBetweenUpdateChecks?: string;
+  AutomaticBackgroundTask?: "true" | "false";
+}
+
+// ========== ELF File Analysis Types ==========
+
+export interface ELFAnalysis extends DetectionInfo {
+  // Basic Format
+  Format: "ELF";
+  Architecture: string;
+
+  // ELF Header
+  Class?: "ELF32" | "ELF64";
+  Endianness?: "Little" | "Big";
+  FileType?: "Executable" | "Shared Object" | "Relocatable" | "Core" | string;
+  EntryPoint?: string;
+
+  // Dynamic Linking
+  Interpreter?: string;
+  SOName?: string;
+  NeededLibraries?: string;
+  LibcFlavor?: "glibc" | "musl" | "unknown";
+
+  // Sections and Segments
+  SectionCount?: string;
+  SegmentCount?: string;
+  /** A program header is both writable and executable */
+  HasWxSegment?: "true" | "false";
+
+  // Packing
+  /** Bits per byte (0-8) with two decimals, e.g. "7.92" */
+  Entropy?: string;
+  Packer?: "UPX";
+
+  // Hardening
+  /** Both IBT and SHSTK in the GNU property note */
+  HasCET?: "true" | "false";
+  /** Comma-separated subset of "IBT", "SHSTK" */
+  CETFeatures?: string;
+  HasRelro?: "true" | "false";
+  /** Heuristic: a __stack_chk_fail symbol is referenced */
+  HasStackProtector?: "true" | "false";
+  /** Executables only: position independent */
+  IsPIE?: "true" | "false";
+}
+
+// ========== Java Class File Analysis Types ==========
+
+export interface JavaClassAnalysis extends DetectionInfo {
+  // Basic Format
+  Format: "JavaClass";
+
+  // Class File Version
+  MajorVersion?: string;
+  MinorVersion?: string;
+  JavaVersion?: string;
+  PreviewFeatures?: "true";
+}
+
+// ========== Mach-O File Analysis Types ==========
+
+export interface MachOAnalysis extends DetectionInfo {
+  // Basic Format
+  Format: "Mach-O";
+  Architecture: string;
+
+  // Mach-O Header
+  CpuType?: string;
+  CpuSubType?: string;
+  FileType?: "Executable" | "Dylib" | "Bundle" | "Object" | "Core" | "Dylinker" | "dSYM" | "Kext Bundle" | string;
+  Flags?: string;
+  FlagNames?: string;
+  NumberOfLoadCommands?: string;
+  HasTextSegment?: "true";
+
+  // Dylib identity (LC_ID_DYLIB)
+  InstallName?: string;
+  CurrentVersion?: string;
+  CompatibilityVersion?: string;
+
+  // LC_LOAD_DYLIB / LC_LOAD_WEAK_DYLIB, comma-separated
+  LinkedLibraries?: string;
+
+  // Code signature (LC_CODE_SIGNATURE)
+  IsSigned?: "true";
+  SigningIdentifier?: string;
+  TeamIdentifier?: string;
+
+  // Deployment target (LC_BUILD_VERSION / LC_VERSION_MIN_*)
+  MinimumSystemVersion?: string;
+  Platform?: "macOS" | "iOS" | "tvOS" | "watchOS" | "Mac Catalyst" | "visionOS" | string;
+
+  // Embedded Info.plist (__TEXT,__info_plist)
+  ProductName?: string;
+  ProductVersion?: string;
+  FileVersion?: string;
+  BundleIdentifier?: string;
+  ExecutableName?: string;
+  CompanyName?: string;
+  LegalCopyright?: string;
+
+  // Universal (fat) binaries
+  IsUniversal?: "true";
+  SliceCount?: string;
+  Architectures?: string;
+  [key: `Slice_${number}_Architecture`]: string | undefined;
+  [key: `Slice_${number}_Offset`]: string | undefined;
+  [key: `Slice_${number}_Size`]: string | undefined;
+}
+
+// ========== WebAssembly Module Analysis Types ==========
+
+export interface WasmAnalysis extends DetectionInfo {
+  // Basic Format
+  Format: "WebAssembly";
+
+  // Module Header
+  Version?: string;
+  NumberOfSections?: string;
+
+  // Sections
+  ImportCount?: string;
+  ExportCount?: string;
+  HasNameSection?: "true";
+  CustomSections?: string;
+}
+
 // ========== Error Response ==========
 
 export interface AnalysisError {
   error: string;
 }
 
+// ========== Analysis Options ==========
+
+export interface AnalysisOptions {
+  /** Scan the whole file for embedded URLs, email addresses and domains, and add RawManifest (default false) */
+  deepScan?: boolean;
+  /** Levels of embedded objects to analyze into `Embedded` (default 0, capped at 4) */
+  maxEmbeddedDepth?: number;
+  /**
+   * Operations the analysis may spend before failing with a
+   * "ResourceLimitExceeded: ..." error (default 128 per input byte, at least 256 Mi)
+   */
+  workBudget?: number;
+  /**
+   * Prefix every key but `Format` with its source (`pe.CompanyName`) and
+   * flatten `Embedded` into `embedded.`-prefixed keys (default false). The
+   * per-format result types do not describe this shape.
+   */
+  namespaceKeys?: boolean;
+  /**
+   * Keys to keep, e.g. ["ProductName", "ProductVersion"] (default: all).
+   * `Format` is always kept; deep-scan and embedded analysis only run when
+   * one of their keys is listed.
+   */
+  fields?: string[];
+  /**
+   * Locale for localized fields such as the RPM Summary, e.g. "de_DE" or
+   * "de-DE" (default: each format's own default, `C` for RPM)
+   */
+  locale?: string;
+  /**
+   * SHA-256 of the file as 64 hex digits, e.g. from a `Hasher` fed during the
+   * upload; reported as `Sha256` instead of hashing the file again
+   */
+  sha256?: string;
+  /** Add `_sources`, the provenance of every field (default false) */
+  includeSources?: boolean;
+  /** Ratio above which SuspiciousCompressionRatio is set (default 1000) */
+  maxCompressionRatio?: number;
+  /**
+   * Size of the whole file when only its leading bytes are passed; truncation
+   * and the RPM CompressedSize are measured against it (default: the data length)
+   */
+  fileSize?: number;
+}
+
+// ========== Supported Formats ==========
+
+export interface SupportedFormat {
+  format: string;
+  extensions: string[];
+  mime: string;
+}
+
+// ========== Build Info ==========
+
+export interface AnalyzerVersion {
+  version: string;
+  formats: string[];
+  features: string[];
+}
+
+// ========== Output Schema ==========
+
+export interface OutputField {
+  /** May contain `{n}`/`{key}` placeholders, e.g. "Slice_{n}_Size" */
+  name: string;
+  /** How to read the string value; "boolean" is "true"/"false", "list" is comma-separated */
+  type: "string" | "boolean" | "integer" | "hex" | "list" | "json";
+  /** Formats that emit the field; ["*"] for fields any analysis may carry */
+  formats: string[];
+}
+
+export interface OutputSchema {
+  fields: OutputField[];
+  /** Formats that also pass source keys through verbatim (PE version info, MSI Property table, DEB control fields) */
+  openFormats: string[];
+}
+
+// ========== Analysis Diff ==========
+
+export interface FieldDiff {
+  field: string;
+  kind: "added" | "removed" | "changed";
+  /** Value in the first analysis; null when the field was added */
+  old: string | null;
+  /** Value in the second analysis; null when the field was removed */
+  new: string | null;
+  /** Present on changed fields whose values both parse as versions */
+  versionChange?: "upgrade" | "downgrade";
+}
+
+// ========== Batch Analysis ==========
+
+export type BatchEntry =
+  | { index: number; metadata: Exclude<FileAnalysis, AnalysisError> }
+  | { index: number; error: { message: string } };
+
 // ========== Union Types ==========
 
 export type FileAnalysis =
   | PEAnalysis
   | MSIAnalysis
+  | CompoundFileAnalysis
   | DMGAnalysis
   | DEBAnalysis
   | RPMAnalysis
+  | SnapAnalysis
+  | FlatpakAnalysis
+  | CPIOAnalysis
+  | ISOAnalysis
+  | LNKAnalysis
+  | MSUAnalysis
+  | AppInstallerAnalysis
+  | ELFAnalysis
+  | JavaClassAnalysis
+  | MachOAnalysis
+  | WasmAnalysis
   | AnalysisError;
 
+// ========== Hashing ==========
+
+export interface Hasher {
+  /** Feed the next chunk of the file */
+  update(chunk: Uint8Array): void;
+  /** The SHA-256 as 64 lowercase hex digits; the hasher cannot be used afterwards */
+  finalize(): string;
+}
+
 // ========== WASM Module Interface ==========
 
 export interface UploadAnalyzerWASM {
@@ -275,4 +913,77 @@ export interface UploadAnalyzerWASM {
    * @returns JSON string containing detailed metadata
    */
   analyze_file(data: Uint8Array): string;
+
+  /**
+   * Analyze any supported file format with opt-in behaviour
+   * @param data - File data as Uint8Array
+   * @param optionsJson - JSON-encoded AnalysisOptions ("" for defaults)
+   * @returns JSON string containing detailed metadata
+   */
+  analyze_file_with_options(data: Uint8Array, optionsJson: string): string;
+
+  /**
+   * analyze_file_with_options, reporting progress through the full-file passes
+   * @param data - File data as Uint8Array
+   * @param optionsJson - JSON-encoded AnalysisOptions ("" for defaults)
+   * @param onProgress - Called with the fraction done (0 to 1), at most once per percent, then with 1
+   * @returns JSON string containing detailed metadata
+   */
+  analyze_file_with_progress(
+    data: Uint8Array,
+    optionsJson: string,
+    onProgress: (fraction: number) => void
+  ): string;
+
+  /**
+   * Incremental SHA-256 for streamed uploads; pass the digest as
+   * AnalysisOptions.sha256
+   */
+  Hasher: new () => Hasher;
+
+  /**
+   * Analyze only a prefix of the file (e.g. the first megabyte)
+   * @param data - Leading bytes of the file as Uint8Array
+   * @returns JSON string containing header-derived metadata with Partial set
+   */
+  analyze_header(data: Uint8Array): string;
+
+  /**
+   * Analyze several files in a single WASM call
+   * @param filesJson - JSON array of base64-encoded files
+   * @returns JSON string containing a BatchEntry[] in input order
+   */
+  analyze_batch(filesJson: string): string;
+
+  /**
+   * List the formats this build can analyze
+   * @returns JSON string containing a SupportedFormat[] in detection order
+   */
+  supported_formats(): string;
+
+  /**
+   * Identify the analyzer build (crate version, formats and Cargo features)
+   * @returns JSON string containing an AnalyzerVersion
+   */
+  analyzer_version(): string;
+
+  /**
+   * Describe every field this build's analyzers can emit
+   * @returns JSON string containing an OutputSchema
+   */
+  output_schema(): string;
+
+  /**
+   * Compare two analysis results field by field
+   * @param aJson - JSON metadata returned by analyze_file for the older file
+   * @param bJson - JSON metadata returned by analyze_file for the newer file
+   * @returns JSON string containing a FieldDiff[] sorted by field name
+   */
+  diff_analyses(aJson: string, bJson: string): string;
+
+  /**
+   * Order two version strings in the scheme of a format ("RPM" EVR, "DEB", otherwise dotted)
+   * @returns -1, 0 or 1, or undefined when either is not a version
+   */
+  compare_versions(a: string, b: string, format: string): number | undefined;
 }